@@ -1,12 +1,127 @@
 use clap::{Parser, ValueEnum, error::ErrorKind};
+use rand::{SeedableRng, rngs::StdRng};
 
-use crate::{dynamic_object::DynamicObject, engine, error::CliError, k8s, output, parser};
+use crate::{
+    dynamic_object::DynamicObject, engine, error::CliError, k8s, output, parser, snapshot,
+};
 
-#[derive(Clone, Debug, ValueEnum)]
+#[derive(Clone, Debug)]
 enum OutputArg {
     Table,
     Json,
     Yaml,
+    Value,
+    Prometheus,
+    Env,
+    Tsv,
+    Ndjson,
+    Kv,
+    Name,
+    /// `custom-columns=HEADER:path,...` в стиле kubectl.
+    CustomColumns(Vec<(String, String)>),
+}
+
+const OUTPUT_FORMATS: &[(&str, OutputArg)] = &[
+    ("table", OutputArg::Table),
+    ("json", OutputArg::Json),
+    ("yaml", OutputArg::Yaml),
+    ("value", OutputArg::Value),
+    ("prometheus", OutputArg::Prometheus),
+    ("env", OutputArg::Env),
+    ("tsv", OutputArg::Tsv),
+    ("ndjson", OutputArg::Ndjson),
+    ("kv", OutputArg::Kv),
+    ("name", OutputArg::Name),
+];
+
+/// Имя формата без учета регистра или `custom-columns=<spec>`.
+fn parse_output_arg(raw: &str) -> Result<OutputArg, String> {
+    if let Some(spec) = raw.strip_prefix("custom-columns=") {
+        return parse_custom_columns(spec).map(OutputArg::CustomColumns);
+    }
+    OUTPUT_FORMATS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(raw))
+        .map(|(_, format)| format.clone())
+        .ok_or_else(|| {
+            let names: Vec<&str> = OUTPUT_FORMATS.iter().map(|(name, _)| *name).collect();
+            format!(
+                "unsupported output format `{raw}`; expected one of {}, \
+                 custom-columns=HEADER:path,...",
+                names.join(", ")
+            )
+        })
+}
+
+/// `NAME:metadata.name,PHASE:.status.phase` -> пары `(заголовок, путь)`;
+/// ведущая `.` пути (как в kubectl) отбрасывается.
+fn parse_custom_columns(spec: &str) -> Result<Vec<(String, String)>, String> {
+    spec.split(',')
+        .map(|entry| {
+            let column = entry.split_once(':').and_then(|(header, path)| {
+                let path = path.trim().strip_prefix('.').unwrap_or(path.trim());
+                let header = header.trim();
+                (!header.is_empty() && !path.is_empty())
+                    .then(|| (header.to_string(), path.to_string()))
+            });
+            column.ok_or_else(|| {
+                format!("invalid custom-columns entry `{entry}`; expected HEADER:path")
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum RetryOnArg {
+    Transport,
+    #[value(name = "transport+429")]
+    TransportAnd429,
+    #[default]
+    All,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum TableStyleArg {
+    #[default]
+    Ascii,
+    Unicode,
+}
+
+impl From<TableStyleArg> for output::TableStyle {
+    fn from(value: TableStyleArg) -> Self {
+        match value {
+            TableStyleArg::Ascii => Self::Ascii,
+            TableStyleArg::Unicode => Self::Unicode,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+enum ArrayModeArg {
+    Indexed,
+    Whole,
+    #[default]
+    Both,
+}
+
+impl From<ArrayModeArg> for crate::path::ArrayMode {
+    fn from(value: ArrayModeArg) -> Self {
+        match value {
+            ArrayModeArg::Indexed => Self::Indexed,
+            ArrayModeArg::Whole => Self::Whole,
+            ArrayModeArg::Both => Self::Both,
+        }
+    }
+}
+
+impl From<RetryOnArg> for k8s::RetryOn {
+    fn from(value: RetryOnArg) -> Self {
+        match value {
+            RetryOnArg::Transport => Self::Transport,
+            RetryOnArg::TransportAnd429 => Self::TransportAnd429,
+            RetryOnArg::All => Self::All,
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -17,22 +132,146 @@ struct CliArgs {
     #[arg(
         short = 'o',
         long = "output",
+        value_name = "format",
         default_value = "table",
-        value_enum,
-        ignore_case = true
+        value_parser = parse_output_arg
     )]
     output: OutputArg,
 
     #[arg(short = 'd', long = "describe")]
     describe: bool,
 
+    #[arg(short = 'n', long = "namespace", value_name = "namespace")]
+    namespace: Option<String>,
+
+    #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+    all_namespaces: bool,
+
     #[arg(long = "no-pushdown-warnings")]
     no_pushdown_warnings: bool,
 
+    #[arg(long = "no-server-warnings")]
+    no_server_warnings: bool,
+
+    #[arg(long = "no-pushdown")]
+    no_pushdown: bool,
+
+    #[arg(long = "require-pushdown", conflicts_with = "no_pushdown")]
+    require_pushdown: bool,
+
+    #[arg(long = "name", value_name = "name")]
+    name: Option<String>,
+
+    #[arg(long = "max-value-bytes", value_name = "bytes")]
+    max_value_bytes: Option<usize>,
+
+    #[arg(long = "omit", value_name = "paths", value_delimiter = ',')]
+    omit: Vec<String>,
+
+    #[arg(
+        long = "grep",
+        value_name = "substr",
+        conflicts_with = "grep_ignore_case"
+    )]
+    grep: Option<String>,
+
+    #[arg(long = "grep-i", value_name = "substr")]
+    grep_ignore_case: Option<String>,
+
+    #[arg(long = "diff-with", value_name = "snapshot.json")]
+    diff_with: Option<String>,
+
+    #[arg(long = "only-varying")]
+    only_varying: bool,
+
+    #[arg(long = "sample", value_name = "n")]
+    sample: Option<usize>,
+
+    #[arg(long = "seed", value_name = "seed", requires = "sample")]
+    seed: Option<u64>,
+
+    #[arg(long = "bucket", value_name = "path")]
+    bucket: Option<String>,
+
+    #[arg(long = "single")]
+    single: bool,
+
+    #[arg(long = "rename-cols", value_name = "file")]
+    rename_cols: Option<String>,
+
+    #[arg(long = "max-columns", value_name = "n")]
+    max_columns: Option<usize>,
+
+    #[arg(long = "table-style", value_enum, default_value_t = TableStyleArg::Ascii)]
+    table_style: TableStyleArg,
+
+    #[arg(long = "decode-base64", value_name = "path-prefix")]
+    decode_base64: Option<String>,
+
+    #[arg(long = "ignore-case")]
+    ignore_case: bool,
+
+    #[arg(long = "keep-input-order", conflicts_with = "tie_break")]
+    keep_input_order: bool,
+
+    #[arg(long = "tie-break", value_name = "path")]
+    tie_break: Option<String>,
+
+    #[arg(long = "fail-on-any-diagnostic")]
+    fail_on_any_diagnostic: bool,
+
+    #[arg(long = "explain-costs")]
+    explain_costs: bool,
+
+    #[arg(long = "explain-resolution")]
+    explain_resolution: bool,
+
+    #[arg(long = "dump-plan")]
+    dump_plan: bool,
+
+    #[arg(long = "summary")]
+    summary: bool,
+
+    #[arg(long = "shard-by", value_name = "path", value_parser = k8s::parse_shard_by)]
+    shard_by: Option<String>,
+
+    #[arg(long = "empty-message", value_name = "text")]
+    empty_message: Option<String>,
+
+    #[arg(long = "histogram", conflicts_with = "output")]
+    histogram: bool,
+
+    #[arg(long = "insecure-skip-tls-verify")]
+    insecure_skip_tls_verify: bool,
+
+    #[arg(long = "header", value_name = "Key: Value", value_parser = k8s::parse_header)]
+    headers: Vec<(http::HeaderName, http::HeaderValue)>,
+
+    #[arg(long = "retry-on", value_enum, default_value_t = RetryOnArg::All)]
+    retry_on: RetryOnArg,
+
+    #[arg(long = "array-mode", value_enum, default_value_t = ArrayModeArg::Both)]
+    array_mode: ArrayModeArg,
+
+    #[arg(long = "discovery-timeout-ms", value_name = "ms")]
+    discovery_timeout_ms: Option<u64>,
+
+    #[arg(long = "kubeconfig", value_name = "path")]
+    kubeconfig: Option<std::path::PathBuf>,
+
+    #[arg(long = "context", value_name = "ctx", conflicts_with = "contexts")]
+    context: Option<String>,
+
+    #[arg(long = "contexts", value_name = "ctx,...", value_delimiter = ',')]
+    contexts: Vec<String>,
+
+    #[arg(long = "strict", requires = "contexts")]
+    strict: bool,
+
     #[arg(value_name = "resource")]
     resource: String,
 
-    #[arg(value_name = "query", required = true, num_args = 1..)]
+    #[arg(value_name = "query", required_unless_present = "name", num_args = 1..)]
     query: Vec<String>,
 }
 
@@ -46,9 +285,13 @@ pub async fn run_async() -> Result<(), CliError> {
     let Some(args) = parse_cli_args()? else {
         return Ok(());
     };
-    let ast = parse_query_tokens(&args.query)?;
-    let pushdown_plan = k8s::planner::plan_pushdown(&ast.predicates);
-    let plan = ast_to_engine_plan(&ast);
+    let ast = build_query_ast(&args.query, args.name.as_deref())?;
+    let pushdown_plan = plan_pushdown(&args, &ast);
+    let plan = engine_plan(&args, &ast)?;
+    if args.dump_plan {
+        println!("{}", dump_plan(&plan)?);
+        return Ok(());
+    }
 
     if !args.no_pushdown_warnings {
         for diagnostic in &pushdown_plan.diagnostics {
@@ -56,24 +299,59 @@ pub async fn run_async() -> Result<(), CliError> {
         }
     }
 
-    let list_result = match k8s::list_async(&args.resource, &pushdown_plan.options).await {
-        Ok(result) => result,
-        Err(error) => {
-            if !args.no_pushdown_warnings
-                && let Some(diagnostic) = k8s::retry_summary_diagnostic(&error)
-            {
-                eprintln!("{}", format_k8s_diagnostic(&diagnostic));
-            }
-            return Err(CliError::K8s(error));
-        }
-    };
-    if !args.no_pushdown_warnings {
-        for diagnostic in &list_result.diagnostics {
-            eprintln!("{}", format_k8s_diagnostic(diagnostic));
+    let previous_snapshot = args
+        .diff_with
+        .as_deref()
+        .map(|path| snapshot::load_snapshot(path, args.array_mode.into()))
+        .transpose()
+        .map_err(CliError::Snapshot)?;
+
+    let column_names = args
+        .rename_cols
+        .as_deref()
+        .map(output::load_column_names)
+        .transpose()
+        .map_err(CliError::Output)?
+        .unwrap_or_default();
+
+    check_pushdown_required(&args, &pushdown_plan)?;
+
+    if args.insecure_skip_tls_verify {
+        eprintln!(
+            "WARNING: --insecure-skip-tls-verify is set; API server certificates are NOT verified"
+        );
+    }
+
+    let mode = list_mode(&args, &plan, &pushdown_plan);
+    let options = list_options(&args, &plan, &pushdown_plan);
+    let (objects, counted, k8s_diagnostic_count) =
+        if let Some(contradiction) = engine::find_contradiction(&plan) {
+            eprintln!("{}", format_contradiction(&contradiction));
+            (Vec::new(), None, 1)
+        } else if args.contexts.is_empty() {
+            list_current_context(&args, &options, mode).await?
+        } else {
+            let (objects, diagnostic_count) = list_across_contexts(&args, &options).await?;
+            (objects, None, diagnostic_count)
+        };
+
+    if args.explain_costs {
+        let counts = engine::predicate_match_counts(&plan, &objects);
+        for (predicate, matched) in ast.predicates().into_iter().zip(counts) {
+            eprintln!(
+                "{}",
+                format_predicate_cost(predicate, matched, objects.len())
+            );
         }
     }
 
-    let filtered = engine::evaluate(&plan, &list_result.objects);
+    let mut filtered = engine::evaluate(&plan, &objects);
+    if let Some((needle, ignore_case)) = grep_filter(&args) {
+        filtered.retain(|object| object.contains_text(needle, ignore_case));
+    }
+    if let Some(size) = args.sample {
+        filtered = sample_objects(filtered, size, args.seed);
+    }
     let is_aggregation = matches!(
         plan.selection,
         Some(engine::EngineSelection::Aggregations(_))
@@ -83,12 +361,53 @@ pub async fn run_async() -> Result<(), CliError> {
             "`--describe` is not supported for aggregation queries".to_string(),
         ));
     }
+    if args.diff_with.is_some() && is_aggregation {
+        return Err(CliError::InvalidArgs(
+            "`--diff-with` is not supported for aggregation queries".to_string(),
+        ));
+    }
 
-    let rows = if is_aggregation {
-        engine::aggregate(&plan, &filtered).map_err(CliError::Engine)?
+    let mut rows = if let Some(count) = counted {
+        engine::filter_groups(&plan, engine::count_rows(&plan, count))
+    } else if is_aggregation {
+        let rows = engine::aggregate(&plan, &filtered).map_err(CliError::Engine)?;
+        engine::filter_groups(&plan, rows)
     } else {
-        engine::sort_objects(&plan, &filtered)
+        engine::sort_objects(
+            &plan,
+            &filtered,
+            &engine::SortOptions {
+                ignore_case: args.ignore_case,
+                tie_break: tie_break(&args),
+            },
+        )
     };
+    rows = engine::distinct_rows(&plan, rows);
+    if let Some(limit) = plan.limit {
+        limit.apply(&mut rows);
+    }
+    if !args.omit.is_empty() && !is_aggregation {
+        for row in &mut rows {
+            row.omit_paths(&args.omit);
+        }
+    }
+    if let Some(mut previous) = previous_snapshot {
+        for object in &mut previous {
+            object.omit_paths(&args.omit);
+        }
+        rows = snapshot::diff_with_snapshot(rows, &previous);
+    }
+    if let Some(prefix) = args.decode_base64.as_deref()
+        && !is_aggregation
+    {
+        for path in decode_base64_rows(&mut rows, prefix) {
+            eprintln!("[decode] value at `{path}` is not valid base64; left unchanged");
+        }
+    }
+    engine::compute_columns(&plan, &mut rows);
+    for (path, target) in coerce_rows(&mut rows, &select_coercions(&ast)) {
+        eprintln!("[coerce] value at `{path}` cannot be coerced to {target}; rendered as null");
+    }
 
     let detail = if args.describe {
         output::DetailLevel::Describe
@@ -96,174 +415,942 @@ pub async fn run_async() -> Result<(), CliError> {
         output::DetailLevel::Summary
     };
 
-    let output_paths = output_paths_for_rows(&plan, &rows);
+    let mut output_paths = output_paths_for_plan(&plan);
+    if args.diff_with.is_some()
+        && let Some(paths) = output_paths.as_mut()
+    {
+        paths.push(crate::dynamic_object::CHANGED_FIELD.to_string());
+    }
+
+    if let Some(message) = empty_result_message(&args, &rows) {
+        eprintln!("{message}");
+    }
 
     output::print(
         &rows,
-        map_output_format(args.output),
+        output_format(&args),
         detail,
         output_paths.as_deref(),
+        &output::RenderOptions {
+            max_value_bytes: args.max_value_bytes,
+            duration_columns: duration_columns(&plan),
+            max_columns: args.max_columns,
+            metric_columns: metric_columns(&plan),
+            only_varying: args.only_varying,
+            bucket: args.bucket.clone(),
+            single: args.single,
+            column_names,
+            table_style: args.table_style.into(),
+            custom_columns: match &args.output {
+                OutputArg::CustomColumns(columns) => columns.clone(),
+                _ => Vec::new(),
+            },
+        },
     )
     .map_err(CliError::Output)?;
 
+    check_diagnostics(
+        args.fail_on_any_diagnostic,
+        pushdown_plan.diagnostics.len() + k8s_diagnostic_count,
+    )
+}
+
+fn check_diagnostics(
+    fail_on_any_diagnostic: bool,
+    diagnostic_count: usize,
+) -> Result<(), CliError> {
+    if fail_on_any_diagnostic && diagnostic_count > 0 {
+        return Err(CliError::DiagnosticsReported(diagnostic_count));
+    }
     Ok(())
 }
 
-fn parse_cli_args() -> Result<Option<CliArgs>, CliError> {
-    match CliArgs::try_parse() {
-        Ok(args) => Ok(Some(args)),
+async fn list_current_context(
+    args: &CliArgs,
+    options: &k8s::ListQueryOptions,
+    mode: k8s::ListMode,
+) -> Result<(Vec<DynamicObject>, Option<usize>, usize), CliError> {
+    let connection = connection_options(args, args.context.as_deref());
+    let listed = match mode {
+        k8s::ListMode::Objects => {
+            k8s::list_with_connection_async(&args.resource, options, &connection).await
+        }
+        k8s::ListMode::CountOnly => {
+            k8s::count_with_connection_async(&args.resource, options, &connection).await
+        }
+    };
+    let list_result = match listed {
+        Ok(result) => result,
         Err(error) => {
-            if matches!(
-                error.kind(),
-                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
-            ) {
-                print!("{error}");
-                return Ok(None);
+            if !args.no_pushdown_warnings
+                && let Some(diagnostic) = k8s::retry_summary_diagnostic(&error)
+            {
+                eprintln!("{}", format_k8s_diagnostic(&diagnostic));
             }
-            Err(CliError::InvalidArgs(error.to_string()))
+            return Err(CliError::K8s(error));
+        }
+    };
+    for diagnostic in &list_result.diagnostics {
+        if should_print_k8s_diagnostic(args, diagnostic) {
+            eprintln!("{}", format_k8s_diagnostic(diagnostic));
+        }
+    }
+    if args.summary {
+        eprintln!("{}", format_list_summary(&list_result));
+    }
+    if args.explain_resolution {
+        for line in format_resolution_trace(&args.resource, list_result.resolution.as_ref()) {
+            eprintln!("{line}");
         }
     }
+
+    Ok((
+        list_result.objects,
+        list_result.counted,
+        list_result.diagnostics.len(),
+    ))
 }
 
-fn parse_query_tokens(tokens: &[String]) -> Result<parser::QueryAst, CliError> {
-    if tokens
-        .first()
-        .is_some_and(|token| token.eq_ignore_ascii_case("where"))
-    {
-        parser::parse_query_args(tokens).map_err(CliError::Parse)
+/// Случайные `size` объектов в исходном порядке; с `seed` выбор
+/// воспроизводим.
+fn sample_objects(
+    objects: Vec<DynamicObject>,
+    size: usize,
+    seed: Option<u64>,
+) -> Vec<DynamicObject> {
+    if size >= objects.len() {
+        return objects;
+    }
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut chosen = rand::seq::index::sample(&mut rng, objects.len(), size).into_vec();
+    chosen.sort_unstable();
+    let mut chosen = chosen.into_iter().peekable();
+    objects
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| chosen.next_if_eq(index).is_some())
+        .map(|(_, object)| object)
+        .collect()
+}
+
+/// `count(*)` без сортировки, где каждый predicate (`==` или `exists`)
+/// целиком ушел в selectors, считается по страницам без материализации
+/// объектов. `!=` не подходит: selector `key!=value` матчит и объекты без
+/// поля, а движок — нет.
+fn list_mode(
+    args: &CliArgs,
+    plan: &engine::QueryPlan,
+    pushdown_plan: &k8s::planner::PushdownPlan,
+) -> k8s::ListMode {
+    let fully_pushed = is_fully_pushed(args, plan, pushdown_plan);
+    let needs_objects = args.explain_costs
+        || args.describe
+        || args.diff_with.is_some()
+        || args.sample.is_some()
+        || grep_filter(args).is_some();
+    if engine::is_count_only(plan) && plan.sort_keys.is_none() && fully_pushed && !needs_objects {
+        k8s::ListMode::CountOnly
     } else {
-        parser::parse_query(&tokens.join(" ")).map_err(CliError::Parse)
+        k8s::ListMode::Objects
     }
 }
 
-fn ast_to_engine_plan(ast: &parser::QueryAst) -> engine::QueryPlan {
-    engine::QueryPlan {
-        predicates: ast.predicates.iter().map(predicate_to_engine).collect(),
-        selection: ast.select.as_ref().map(select_clause_to_engine),
-        sort_keys: ast
-            .order_by
-            .as_ref()
-            .map(|keys| keys.iter().map(sort_key_to_engine).collect()),
+/// Selector не сужает выборку сильнее движка, только если каждый predicate
+/// ушел в API и не использует `!=`/`not in`, которые пропускают объекты без поля.
+fn is_fully_pushed(
+    args: &CliArgs,
+    plan: &engine::QueryPlan,
+    pushdown_plan: &k8s::planner::PushdownPlan,
+) -> bool {
+    pushdown_plan.diagnostics.is_empty()
+        && (!args.no_pushdown || plan.filter.is_none())
+        && plan.predicates().into_iter().all(|predicate| {
+            !matches!(
+                predicate.op,
+                engine::EngineOperator::Ne | engine::EngineOperator::NotIn { .. }
+            )
+        })
+}
+
+/// `limit` уходит в API, только когда клиент не отбрасывает и не переставляет
+/// объекты: иначе первые N объектов ответа не совпадут с первыми N строками.
+/// Слияние шардов и веток fan-out по имени тоже меняет порядок ответа.
+fn list_options(
+    args: &CliArgs,
+    plan: &engine::QueryPlan,
+    pushdown_plan: &k8s::planner::PushdownPlan,
+) -> k8s::ListQueryOptions {
+    let keeps_api_order = plan.sort_keys.is_none()
+        && !matches!(
+            plan.selection,
+            Some(engine::EngineSelection::Aggregations(_))
+        )
+        && !args.explain_costs
+        && !plan.distinct
+        && args.sample.is_none()
+        && grep_filter(args).is_none()
+        && args.shard_by.is_none()
+        && pushdown_plan.options.name_fan_out.is_empty();
+    k8s::ListQueryOptions {
+        limit: plan
+            .limit
+            .map(engine::EngineLimit::end)
+            .filter(|_| keeps_api_order && is_fully_pushed(args, plan, pushdown_plan)),
+        shard_by: args.shard_by.clone(),
+        ..pushdown_plan.options.clone()
     }
 }
 
-fn select_clause_to_engine(clause: &parser::SelectClause) -> engine::EngineSelection {
-    match clause {
-        parser::SelectClause::Paths(paths) => engine::EngineSelection::Paths(paths.clone()),
-        parser::SelectClause::Aggregations(expressions) => engine::EngineSelection::Aggregations(
-            expressions.iter().map(aggregation_to_engine).collect(),
-        ),
-        parser::SelectClause::Mixed { .. } => {
-            unreachable!("mixed select clause must be rejected by parser validation")
+async fn list_across_contexts(
+    args: &CliArgs,
+    options: &k8s::ListQueryOptions,
+) -> Result<(Vec<DynamicObject>, usize), CliError> {
+    let mut results = Vec::with_capacity(args.contexts.len());
+    for context in &args.contexts {
+        let connection = connection_options(args, Some(context));
+        let result = k8s::list_with_connection_async(&args.resource, options, &connection).await;
+        if let Ok(list_result) = &result {
+            if args.summary {
+                eprintln!("[context={}] {}", context, format_list_summary(list_result));
+            }
+            if args.explain_resolution {
+                let trace = list_result.resolution.as_ref();
+                for line in format_resolution_trace(&args.resource, trace) {
+                    eprintln!("[context={context}] {line}");
+                }
+            }
         }
+        results.push((context.clone(), result));
     }
-}
 
-fn aggregation_to_engine(expression: &parser::AggregationExpr) -> engine::EngineAggregationExpr {
-    engine::EngineAggregationExpr {
-        function: aggregation_function_to_engine(&expression.function),
-        path: expression.path.clone(),
+    let merged = merge_context_results(results, args.strict)?;
+    for diagnostic in &merged.diagnostics {
+        if let ContextDiagnostic::K8s { diagnostic, .. } = diagnostic
+            && !should_print_k8s_diagnostic(args, diagnostic)
+        {
+            continue;
+        }
+        eprintln!("{}", format_context_diagnostic(diagnostic));
     }
+
+    Ok((merged.objects, merged.diagnostics.len()))
 }
 
-fn aggregation_function_to_engine(
-    function: &parser::AggregationFunction
-) -> engine::EngineAggregationFunction {
-    match function {
-        parser::AggregationFunction::Count => engine::EngineAggregationFunction::Count,
-        parser::AggregationFunction::Sum => engine::EngineAggregationFunction::Sum,
-        parser::AggregationFunction::Min => engine::EngineAggregationFunction::Min,
-        parser::AggregationFunction::Max => engine::EngineAggregationFunction::Max,
-        parser::AggregationFunction::Avg => engine::EngineAggregationFunction::Avg,
+fn should_print_k8s_diagnostic(args: &CliArgs, diagnostic: &k8s::K8sDiagnostic) -> bool {
+    match diagnostic {
+        k8s::K8sDiagnostic::ServerWarning { .. } => !args.no_server_warnings,
+        _ => !args.no_pushdown_warnings,
     }
 }
 
-fn predicate_to_engine(predicate: &parser::Predicate) -> engine::EnginePredicate {
-    engine::EnginePredicate {
-        path: predicate.path.clone(),
-        op: operator_to_engine(&predicate.op),
-        value: predicate.value.clone(),
+fn connection_options(args: &CliArgs, context: Option<&str>) -> k8s::ConnectionOptions {
+    k8s::ConnectionOptions {
+        context: context.map(str::to_string),
+        insecure_skip_tls_verify: args.insecure_skip_tls_verify,
+        headers: args.headers.clone(),
+        retry_on: args.retry_on.into(),
+        discovery_timeout: args
+            .discovery_timeout_ms
+            .map(std::time::Duration::from_millis),
+        namespace: args.namespace.clone(),
+        all_namespaces: args.all_namespaces,
+        kubeconfig: args.kubeconfig.clone(),
+        array_mode: args.array_mode.into(),
     }
 }
 
-fn operator_to_engine(op: &parser::Operator) -> engine::EngineOperator {
-    match op {
-        parser::Operator::Eq => engine::EngineOperator::Eq,
-        parser::Operator::Ne => engine::EngineOperator::Ne,
-    }
+#[derive(Debug, Default)]
+struct MergedContexts {
+    objects: Vec<DynamicObject>,
+    diagnostics: Vec<ContextDiagnostic>,
 }
 
-fn sort_key_to_engine(key: &parser::SortKey) -> engine::EngineSortKey {
-    engine::EngineSortKey {
-        path: key.path.clone(),
-        direction: sort_direction_to_engine(key.direction),
-    }
+#[derive(Debug)]
+enum ContextDiagnostic {
+    K8s {
+        context: String,
+        diagnostic: k8s::K8sDiagnostic,
+    },
+    Failed {
+        context: String,
+        error: String,
+    },
 }
 
-fn sort_direction_to_engine(direction: parser::SortDirection) -> engine::EngineSortDirection {
-    match direction {
-        parser::SortDirection::Asc => engine::EngineSortDirection::Asc,
-        parser::SortDirection::Desc => engine::EngineSortDirection::Desc,
+fn merge_context_results(
+    results: Vec<(String, Result<k8s::ListResult, crate::error::K8sError>)>,
+    strict: bool,
+) -> Result<MergedContexts, CliError> {
+    let mut merged = MergedContexts::default();
+    let mut first_error = None;
+    let mut succeeded = 0;
+
+    for (context, result) in results {
+        match result {
+            Ok(list_result) => {
+                succeeded += 1;
+                merged
+                    .diagnostics
+                    .extend(list_result.diagnostics.into_iter().map(|diagnostic| {
+                        ContextDiagnostic::K8s {
+                            context: context.clone(),
+                            diagnostic,
+                        }
+                    }));
+                merged.objects.extend(
+                    list_result
+                        .objects
+                        .into_iter()
+                        .map(|object| tag_with_context(object, &context)),
+                );
+            }
+            Err(error) if strict => return Err(CliError::K8s(error)),
+            Err(error) => {
+                merged.diagnostics.push(ContextDiagnostic::Failed {
+                    context,
+                    error: error.to_string(),
+                });
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+
+    match first_error {
+        Some(error) if succeeded == 0 => Err(CliError::K8s(error)),
+        _ => Ok(merged),
     }
 }
 
-fn output_paths_for_rows(
-    plan: &engine::QueryPlan,
-    rows: &[DynamicObject],
-) -> Option<Vec<String>> {
-    match &plan.selection {
-        Some(engine::EngineSelection::Paths(paths)) => Some(paths.clone()),
-        Some(engine::EngineSelection::Aggregations(_)) => rows
-            .first()
-            .map(|row| row.fields.keys().cloned().collect())
-            .or_else(|| Some(Vec::new())),
-        None => None,
+fn tag_with_context(mut object: DynamicObject, context: &str) -> DynamicObject {
+    object.fields.insert(
+        crate::dynamic_object::CONTEXT_FIELD.to_string(),
+        serde_json::Value::String(context.to_string()),
+    );
+    object
+}
+
+fn format_list_summary(result: &k8s::ListResult) -> String {
+    let listed = result.counted.unwrap_or(result.objects.len());
+    let mut summary = match result.estimated_total {
+        Some(estimated_total) => format!("[summary] listed {listed} of ~{estimated_total} objects"),
+        None => format!("[summary] listed {listed} objects"),
+    };
+    summary.push_str(&format!(
+        " (pages={}, requests={})",
+        result.pages_fetched, result.request_count
+    ));
+    if result.truncated {
+        summary.push_str(", truncated");
     }
+    summary
 }
 
-fn map_output_format(format: OutputArg) -> output::OutputFormat {
-    match format {
-        OutputArg::Table => output::OutputFormat::Table,
-        OutputArg::Json => output::OutputFormat::Json,
-        OutputArg::Yaml => output::OutputFormat::Yaml,
+fn format_resolution_trace(
+    resource: &str,
+    trace: Option<&k8s::resolution::ResolutionTrace>,
+) -> Vec<String> {
+    let Some(trace) = trace else {
+        return vec![format!(
+            "[resolution] `{resource}` resolved from discovery cache"
+        )];
+    };
+
+    let mut lines = vec![format!(
+        "[resolution] `{}`: scanned {} discovered resources",
+        trace.resource,
+        trace.considered.len()
+    )];
+    for candidate in &trace.considered {
+        let verdict = match candidate.matched_by {
+            Some(matched_by) => format!("matched by {}", format_resolution_match(matched_by)),
+            None => "no match".to_string(),
+        };
+        lines.push(format!(
+            "[resolution]   {} {} (kind {}): {verdict}",
+            candidate.group_version(),
+            candidate.plural,
+            candidate.kind
+        ));
     }
+    if let Some(chosen) = trace.chosen() {
+        lines.push(format!(
+            "[resolution] chose {} {} by {}",
+            chosen.group_version(),
+            chosen.plural,
+            chosen.matched_by.map_or("-", format_resolution_match)
+        ));
+    }
+    for alternative in trace.alternatives() {
+        lines.push(format!(
+            "[resolution] alternative {} {} also matched by {}",
+            alternative.group_version(),
+            alternative.plural,
+            alternative.matched_by.map_or("-", format_resolution_match)
+        ));
+    }
+    lines
 }
 
-fn format_planner_diagnostic(diagnostic: &k8s::planner::PlannerDiagnostic) -> String {
-    format!(
-        "[pushdown] predicate `{}` {} was not pushed: {}",
-        diagnostic.path,
-        format_operator(&diagnostic.op),
-        format_not_pushable_reason(&diagnostic.reason)
-    )
+fn format_resolution_match(matched_by: k8s::resolution::ResolutionMatch) -> &'static str {
+    match matched_by {
+        k8s::resolution::ResolutionMatch::Plural => "plural",
+        k8s::resolution::ResolutionMatch::Qualified => "plural.group",
+        k8s::resolution::ResolutionMatch::Kind => "kind",
+    }
 }
 
-fn format_k8s_diagnostic(diagnostic: &k8s::K8sDiagnostic) -> String {
+fn format_context_diagnostic(diagnostic: &ContextDiagnostic) -> String {
     match diagnostic {
-        k8s::K8sDiagnostic::SelectorFallback { reason, attempted } => {
-            format!(
-                "[pushdown] API rejected selectors ({}); retried without selectors (field_selector={:?}, label_selector={:?})",
-                format_selector_fallback_reason(reason),
-                attempted.field_selector,
-                attempted.label_selector
-            )
-        }
-        k8s::K8sDiagnostic::RetrySummary {
-            stage,
-            attempts,
-            reason,
-            final_error,
-        } => {
+        ContextDiagnostic::K8s {
+            context,
+            diagnostic,
+        } => format!(
+            "[context={}] {}",
+            context,
+            format_k8s_diagnostic(diagnostic)
+        ),
+        ContextDiagnostic::Failed { context, error } => {
             format!(
-                "[retry] stage={} attempts={} stop_reason={} final_error={}",
-                stage, attempts, reason, final_error
+                "[context={}] query failed, skipping context: {}",
+                context, error
             )
         }
     }
 }
 
-fn format_selector_fallback_reason(reason: &k8s::SelectorFallbackReason) -> &'static str {
-    match reason {
+/// Пары `(путь, тип)` из `select path::type`.
+fn select_coercions(ast: &parser::QueryAst) -> Vec<(String, String)> {
+    let Some(parser::SelectClause::Paths(paths)) = ast.select.as_ref() else {
+        return Vec::new();
+    };
+    paths
+        .iter()
+        .filter_map(|path| match parser::split_type_annotation(path) {
+            (path, Some(target)) => Some((path.to_string(), target.to_string())),
+            (_, None) => None,
+        })
+        .collect()
+}
+
+/// Возвращает каждую неудавшуюся пару `(путь, тип)` один раз, сколько бы
+/// строк ее ни содержало.
+fn coerce_rows(
+    rows: &mut [DynamicObject],
+    coercions: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut failed = Vec::new();
+    for row in rows {
+        for coercion in coercions {
+            if !row.coerce_path(&coercion.0, &coercion.1) && !failed.contains(coercion) {
+                failed.push(coercion.clone());
+            }
+        }
+    }
+    failed
+}
+
+fn decode_base64_rows(rows: &mut [DynamicObject], prefix: &str) -> Vec<String> {
+    rows.iter_mut()
+        .flat_map(|row| row.decode_base64_fields(prefix))
+        .collect()
+}
+
+fn parse_cli_args() -> Result<Option<CliArgs>, CliError> {
+    match CliArgs::try_parse() {
+        Ok(args) => Ok(Some(args)),
+        Err(error) => {
+            if matches!(
+                error.kind(),
+                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
+            ) {
+                print!("{error}");
+                return Ok(None);
+            }
+            Err(CliError::InvalidArgs(error.to_string()))
+        }
+    }
+}
+
+fn plan_pushdown(args: &CliArgs, ast: &parser::QueryAst) -> k8s::planner::PushdownPlan {
+    if args.no_pushdown {
+        return k8s::planner::PushdownPlan::default();
+    }
+    k8s::planner::plan_pushdown(ast.filter.as_ref())
+}
+
+/// `--keep-input-order` фиксирует поведение по умолчанию: равные по
+/// `order by` объекты остаются в порядке ответа API.
+fn tie_break(args: &CliArgs) -> engine::TieBreak {
+    match &args.tie_break {
+        Some(path) if !args.keep_input_order => engine::TieBreak::Path(path.clone()),
+        _ => engine::TieBreak::InputOrder,
+    }
+}
+
+fn engine_plan(args: &CliArgs, ast: &parser::QueryAst) -> Result<engine::QueryPlan, CliError> {
+    let plan = ast_to_engine_plan(ast)?;
+    if ast.limit.is_some_and(|limit| limit.offset.is_some())
+        && matches!(ast.select, Some(parser::SelectClause::Aggregations(_)))
+    {
+        return Err(CliError::InvalidArgs(
+            "`offset` is not supported for aggregation queries".to_string(),
+        ));
+    }
+    if let Some(having) = ast.having.as_ref() {
+        check_having_columns(ast, having)?;
+    }
+    if args.tie_break.is_some() && ast.order_by.is_none() {
+        return Err(CliError::InvalidArgs(
+            "`--tie-break` only applies to `order by` keys".to_string(),
+        ));
+    }
+    Ok(plan)
+}
+
+/// `having` видит только строки aggregation: колонки агрегатов и ключи
+/// `group by`.
+fn check_having_columns(
+    ast: &parser::QueryAst,
+    having: &parser::Expr<parser::HavingPredicate>,
+) -> Result<(), CliError> {
+    let Some(parser::SelectClause::Aggregations(aggregations)) = ast.select.as_ref() else {
+        return Err(CliError::InvalidArgs(
+            "`having` requires an aggregation query".to_string(),
+        ));
+    };
+    let mut columns: Vec<String> = aggregations
+        .iter()
+        .map(|expression| engine::aggregation_key(&aggregation_to_engine(expression)))
+        .collect();
+    columns.extend(ast.group_by.iter().flatten().cloned());
+    match having
+        .predicates()
+        .into_iter()
+        .map(|predicate| having_column_key(&predicate.column))
+        .find(|column| !columns.contains(column))
+    {
+        Some(column) => Err(CliError::InvalidArgs(format!(
+            "`having` refers to `{column}`, which is not an aggregation or `group by` column"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn check_pushdown_required(
+    args: &CliArgs,
+    pushdown_plan: &k8s::planner::PushdownPlan,
+) -> Result<(), CliError> {
+    let options = &pushdown_plan.options;
+    if args.require_pushdown
+        && options.field_selector.is_none()
+        && options.label_selector.is_none()
+        && options.name_fan_out.is_empty()
+    {
+        return Err(CliError::InvalidArgs(
+            "`--require-pushdown` is set, but no predicate can be sent as a field/label selector; the query would list every object and filter client-side".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn build_query_ast(tokens: &[String], name: Option<&str>) -> Result<parser::QueryAst, CliError> {
+    let mut ast = if tokens.is_empty() {
+        parser::QueryAst {
+            filter: None,
+            select: None,
+            order_by: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        }
+    } else {
+        parse_query_tokens(tokens)?
+    };
+
+    if let Some(name) = name {
+        let by_name = parser::Expr::Leaf(name_filter_predicate(name));
+        ast.filter = Some(match ast.filter.take() {
+            Some(filter) => filter.and(by_name),
+            None => by_name,
+        });
+    }
+    Ok(ast)
+}
+
+/// Имя только с `*`/`?` — glob (`like`), с другими regex-метасимволами
+/// (кроме `.`, обычного в именах) — regex, якорный с обеих сторон;
+/// иначе точное `==`, которое можно отдать в field selector.
+fn name_filter_predicate(name: &str) -> parser::Predicate {
+    let is_regex = name.contains(['^', '$', '+', '(', ')', '[', ']', '{', '}', '|', '\\']);
+    let is_glob = name.contains(['*', '?']);
+    let (op, value) = if is_regex {
+        (
+            parser::Operator::Regex,
+            serde_json::Value::String(format!("^(?:{name})$")),
+        )
+    } else if is_glob {
+        (
+            parser::Operator::Like(name.to_string()),
+            serde_json::Value::Null,
+        )
+    } else {
+        (
+            parser::Operator::Eq,
+            serde_json::Value::String(name.to_string()),
+        )
+    };
+    parser::Predicate {
+        path: "metadata.name".to_string(),
+        function: None,
+        op,
+        value,
+        value_path: None,
+        value_function: None,
+    }
+}
+
+fn parse_query_tokens(tokens: &[String]) -> Result<parser::QueryAst, CliError> {
+    if tokens
+        .first()
+        .is_some_and(|token| token.eq_ignore_ascii_case("where"))
+    {
+        parser::parse_query_args(tokens).map_err(CliError::Parse)
+    } else {
+        parser::parse_query(&tokens.join(" ")).map_err(CliError::Parse)
+    }
+}
+
+fn ast_to_engine_plan(ast: &parser::QueryAst) -> Result<engine::QueryPlan, CliError> {
+    Ok(engine::QueryPlan {
+        filter: ast.filter.as_ref().map(expr_to_engine).transpose()?,
+        selection: ast.select.as_ref().map(select_clause_to_engine),
+        sort_keys: ast
+            .order_by
+            .as_ref()
+            .map(|keys| keys.iter().map(sort_key_to_engine).collect()),
+        limit: ast.limit.map(|limit| engine::EngineLimit {
+            count: limit.count,
+            offset: limit.offset.unwrap_or_default(),
+        }),
+        computed: ast
+            .computed
+            .iter()
+            .map(|column| {
+                Ok(engine::EngineComputedColumn {
+                    alias: column.alias.clone(),
+                    expr: expr_to_engine(&column.expr)?,
+                })
+            })
+            .collect::<Result<_, CliError>>()?,
+        group_by: ast.group_by.clone(),
+        having: ast.having.as_ref().map(having_to_engine).transpose()?,
+        distinct: ast.distinct,
+    })
+}
+
+fn select_clause_to_engine(clause: &parser::SelectClause) -> engine::EngineSelection {
+    match clause {
+        parser::SelectClause::Paths(paths) => engine::EngineSelection::Paths(
+            paths
+                .iter()
+                .map(|path| parser::split_type_annotation(path).0.to_string())
+                .collect(),
+        ),
+        parser::SelectClause::Aggregations(expressions) => engine::EngineSelection::Aggregations(
+            expressions.iter().map(aggregation_to_engine).collect(),
+        ),
+        parser::SelectClause::Mixed { .. } => {
+            unreachable!("mixed select clause must be rejected by parser validation")
+        }
+    }
+}
+
+fn aggregation_to_engine(expression: &parser::AggregationExpr) -> engine::EngineAggregationExpr {
+    engine::EngineAggregationExpr {
+        function: aggregation_function_to_engine(&expression.function),
+        path: expression.path.clone(),
+        field_function: expression.field_function.map(field_function_to_engine),
+    }
+}
+
+fn aggregation_function_to_engine(
+    function: &parser::AggregationFunction
+) -> engine::EngineAggregationFunction {
+    match function {
+        parser::AggregationFunction::Count => engine::EngineAggregationFunction::Count,
+        parser::AggregationFunction::Sum => engine::EngineAggregationFunction::Sum,
+        parser::AggregationFunction::Min => engine::EngineAggregationFunction::Min,
+        parser::AggregationFunction::Max => engine::EngineAggregationFunction::Max,
+        parser::AggregationFunction::Avg => engine::EngineAggregationFunction::Avg,
+        parser::AggregationFunction::Range => engine::EngineAggregationFunction::Range,
+        parser::AggregationFunction::Median => engine::EngineAggregationFunction::Median,
+        parser::AggregationFunction::Stddev => engine::EngineAggregationFunction::Stddev,
+        parser::AggregationFunction::Percentile(rank) => {
+            engine::EngineAggregationFunction::Percentile(rank.clone())
+        }
+        parser::AggregationFunction::Mode => engine::EngineAggregationFunction::Mode,
+        parser::AggregationFunction::GroupConcat(separator) => {
+            engine::EngineAggregationFunction::GroupConcat(separator.clone())
+        }
+    }
+}
+
+fn expr_to_engine(expr: &parser::Expr) -> Result<engine::EngineExpr, CliError> {
+    tree_to_engine(expr, predicate_to_engine)
+}
+
+fn having_to_engine(
+    having: &parser::Expr<parser::HavingPredicate>,
+) -> Result<engine::EngineExpr, CliError> {
+    tree_to_engine(having, having_predicate_to_engine)
+}
+
+fn tree_to_engine<L>(
+    expr: &parser::Expr<L>,
+    leaf: fn(&L) -> Result<engine::EnginePredicate, CliError>,
+) -> Result<engine::EngineExpr, CliError> {
+    let terms = |terms: &[parser::Expr<L>]| -> Result<Vec<_>, CliError> {
+        terms
+            .iter()
+            .map(|term| tree_to_engine(term, leaf))
+            .collect()
+    };
+    Ok(match expr {
+        parser::Expr::And(items) => engine::EngineExpr::And(terms(items)?),
+        parser::Expr::Or(items) => engine::EngineExpr::Or(terms(items)?),
+        parser::Expr::Not(inner) => engine::EngineExpr::Not(Box::new(tree_to_engine(inner, leaf)?)),
+        parser::Expr::Leaf(predicate) => engine::EngineExpr::Leaf(leaf(predicate)?),
+    })
+}
+
+/// Колонка aggregation адресуется тем же именем, под которым
+/// `engine::aggregate` кладет ее в строку.
+fn having_column_key(column: &parser::HavingColumn) -> String {
+    match column {
+        parser::HavingColumn::Aggregation(expression) => {
+            engine::aggregation_key(&aggregation_to_engine(expression))
+        }
+        parser::HavingColumn::Path(path) => path.clone(),
+    }
+}
+
+fn having_predicate_to_engine(
+    predicate: &parser::HavingPredicate,
+) -> Result<engine::EnginePredicate, CliError> {
+    Ok(engine::EnginePredicate {
+        path: having_column_key(&predicate.column),
+        function: None,
+        op: operator_to_engine(&predicate.op, &predicate.value)?,
+        value: predicate.value.clone(),
+        value_path: None,
+        value_function: None,
+    })
+}
+
+fn predicate_to_engine(predicate: &parser::Predicate) -> Result<engine::EnginePredicate, CliError> {
+    Ok(engine::EnginePredicate {
+        path: predicate.path.clone(),
+        function: predicate.function.map(field_function_to_engine),
+        op: operator_to_engine(&predicate.op, &predicate.value)?,
+        value: predicate.value.clone(),
+        value_path: predicate.value_path.clone(),
+        value_function: predicate.value_function.map(value_function_to_engine),
+    })
+}
+
+fn operator_to_engine(
+    op: &parser::Operator,
+    value: &serde_json::Value,
+) -> Result<engine::EngineOperator, CliError> {
+    Ok(match op {
+        parser::Operator::Eq => engine::EngineOperator::Eq,
+        parser::Operator::Ne => engine::EngineOperator::Ne,
+        parser::Operator::Gt => engine::EngineOperator::Gt,
+        parser::Operator::Ge => engine::EngineOperator::Ge,
+        parser::Operator::Lt => engine::EngineOperator::Lt,
+        parser::Operator::Le => engine::EngineOperator::Le,
+        parser::Operator::In { values } => engine::EngineOperator::In {
+            values: values.clone(),
+        },
+        parser::Operator::NotIn { values } => engine::EngineOperator::NotIn {
+            values: values.clone(),
+        },
+        parser::Operator::Like(pattern) => engine::EngineOperator::Like(pattern.clone()),
+        parser::Operator::Regex => engine::EngineOperator::Regex(compile_regex(value)?),
+        parser::Operator::NotRegex => engine::EngineOperator::NotRegex(compile_regex(value)?),
+        parser::Operator::IsNull => engine::EngineOperator::IsNull,
+        parser::Operator::IsNotNull => engine::EngineOperator::IsNotNull,
+        parser::Operator::Exists => engine::EngineOperator::Exists,
+        parser::Operator::HasKey => engine::EngineOperator::HasKey,
+    })
+}
+
+fn compile_regex(value: &serde_json::Value) -> Result<engine::PredicateRegex, CliError> {
+    let pattern = format_predicate_value(value);
+    regex::Regex::new(&pattern)
+        .map(engine::PredicateRegex)
+        .map_err(|error| CliError::Parse(format!("invalid regex `{pattern}`: {error}")))
+}
+
+fn sort_key_to_engine(key: &parser::SortKey) -> engine::EngineSortKey {
+    engine::EngineSortKey {
+        path: key.path.clone(),
+        function: key.function.map(field_function_to_engine),
+        direction: sort_direction_to_engine(key.direction),
+    }
+}
+
+fn value_function_to_engine(function: parser::ValueFunction) -> engine::EngineValueFunction {
+    match function {
+        parser::ValueFunction::Now => engine::EngineValueFunction::Now,
+    }
+}
+
+fn field_function_to_engine(function: parser::FieldFunction) -> engine::EngineFieldFunction {
+    match function {
+        parser::FieldFunction::Len => engine::EngineFieldFunction::Len,
+        parser::FieldFunction::StrLen => engine::EngineFieldFunction::StrLen,
+        parser::FieldFunction::Age => engine::EngineFieldFunction::Age,
+        parser::FieldFunction::ImageRepo => engine::EngineFieldFunction::ImageRepo,
+        parser::FieldFunction::ImageTag => engine::EngineFieldFunction::ImageTag,
+        parser::FieldFunction::ArraySum => engine::EngineFieldFunction::ArraySum,
+        parser::FieldFunction::ArrayMin => engine::EngineFieldFunction::ArrayMin,
+        parser::FieldFunction::ArrayMax => engine::EngineFieldFunction::ArrayMax,
+        parser::FieldFunction::ArrayAvg => engine::EngineFieldFunction::ArrayAvg,
+    }
+}
+
+fn sort_direction_to_engine(direction: parser::SortDirection) -> engine::EngineSortDirection {
+    match direction {
+        parser::SortDirection::Asc => engine::EngineSortDirection::Asc,
+        parser::SortDirection::Desc => engine::EngineSortDirection::Desc,
+    }
+}
+
+fn duration_columns(plan: &engine::QueryPlan) -> Vec<String> {
+    let Some(engine::EngineSelection::Aggregations(expressions)) = &plan.selection else {
+        return Vec::new();
+    };
+    expressions
+        .iter()
+        .filter(|expression| {
+            expression.field_function == Some(engine::EngineFieldFunction::Age)
+                && !matches!(
+                    expression.function,
+                    engine::EngineAggregationFunction::Count
+                        | engine::EngineAggregationFunction::Range
+                )
+        })
+        .map(engine::aggregation_key)
+        .collect()
+}
+
+fn metric_columns(plan: &engine::QueryPlan) -> Vec<String> {
+    let Some(engine::EngineSelection::Aggregations(expressions)) = &plan.selection else {
+        return Vec::new();
+    };
+    expressions.iter().map(engine::aggregation_key).collect()
+}
+
+/// Колонки вывода в порядке запроса: пути `select` или ключи `group by`,
+/// за которыми идут aggregation в порядке их записи.
+fn output_paths_for_plan(plan: &engine::QueryPlan) -> Option<Vec<String>> {
+    match &plan.selection {
+        Some(engine::EngineSelection::Paths(paths)) => Some(paths.clone()),
+        Some(engine::EngineSelection::Aggregations(expressions)) => {
+            let group_by = plan.group_by.iter().flatten().cloned();
+            Some(
+                group_by
+                    .chain(expressions.iter().map(engine::aggregation_key))
+                    .collect(),
+            )
+        }
+        None => None,
+    }
+}
+
+/// `--empty-message` печатается в stderr, только если не осталось ни одной
+/// строки; stdout форматов не меняется.
+fn empty_result_message<'a>(args: &'a CliArgs, rows: &[DynamicObject]) -> Option<&'a str> {
+    args.empty_message.as_deref().filter(|_| rows.is_empty())
+}
+
+fn output_format(args: &CliArgs) -> output::OutputFormat {
+    if args.histogram {
+        return output::OutputFormat::Histogram;
+    }
+    map_output_format(args.output.clone())
+}
+
+fn map_output_format(format: OutputArg) -> output::OutputFormat {
+    match format {
+        OutputArg::Table => output::OutputFormat::Table,
+        OutputArg::Json => output::OutputFormat::Json,
+        OutputArg::Yaml => output::OutputFormat::Yaml,
+        OutputArg::Value => output::OutputFormat::Value,
+        OutputArg::Prometheus => output::OutputFormat::Prometheus,
+        OutputArg::Env => output::OutputFormat::Env,
+        OutputArg::Tsv => output::OutputFormat::Tsv,
+        OutputArg::Ndjson => output::OutputFormat::Ndjson,
+        OutputArg::Kv => output::OutputFormat::Kv,
+        OutputArg::CustomColumns(_) => output::OutputFormat::CustomColumns,
+        OutputArg::Name => output::OutputFormat::Name,
+    }
+}
+
+fn format_planner_diagnostic(diagnostic: &k8s::planner::PlannerDiagnostic) -> String {
+    format!(
+        "[pushdown] predicate `{}` {} was not pushed: {}",
+        diagnostic.path,
+        format_operator(&diagnostic.op),
+        format_not_pushable_reason(&diagnostic.reason)
+    )
+}
+
+fn format_k8s_diagnostic(diagnostic: &k8s::K8sDiagnostic) -> String {
+    match diagnostic {
+        k8s::K8sDiagnostic::SelectorFallback { reason, attempted } => {
+            format!(
+                "[pushdown] API rejected selectors ({}); retried without selectors (field_selector={:?}, label_selector={:?})",
+                format_selector_fallback_reason(reason),
+                attempted.field_selector,
+                attempted.label_selector
+            )
+        }
+        k8s::K8sDiagnostic::RetrySummary {
+            stage,
+            attempts,
+            reason,
+            final_error,
+        } => {
+            format!(
+                "[retry] stage={} attempts={} stop_reason={} final_error={}",
+                stage, attempts, reason, final_error
+            )
+        }
+        k8s::K8sDiagnostic::ServerWarning { message } => format!("[server] warning: {message}"),
+        k8s::K8sDiagnostic::NamespaceIgnored {
+            resource,
+            namespace,
+        } => format!(
+            "[namespace] `{resource}` is cluster-scoped; --namespace {namespace} was ignored"
+        ),
+        k8s::K8sDiagnostic::AllNamespacesIgnored { resource } => {
+            format!("[namespace] `{resource}` is cluster-scoped; --all-namespaces has no effect")
+        }
+        k8s::K8sDiagnostic::PaginationUnsupported { resource } => format!(
+            "[pagination] API for `{resource}` rejected limit/continue; listed in a single request"
+        ),
+    }
+}
+
+fn format_selector_fallback_reason(reason: &k8s::SelectorFallbackReason) -> &'static str {
+    match reason {
         k8s::SelectorFallbackReason::ApiRejectedBadRequest => "bad request",
     }
 }
@@ -272,86 +1359,971 @@ fn format_not_pushable_reason(reason: &k8s::planner::NotPushableReason) -> &'sta
     match reason {
         k8s::planner::NotPushableReason::UnsupportedPath => "unsupported path",
         k8s::planner::NotPushableReason::UnsupportedOperator => "unsupported operator",
+        k8s::planner::NotPushableReason::RangeOperator => {
+            "selectors have no range comparison; filtered client-side"
+        }
         k8s::planner::NotPushableReason::NonStringValue => "non-string value",
         k8s::planner::NotPushableReason::UnsafeSelectorValue => "unsafe selector value",
         k8s::planner::NotPushableReason::UnsafeLabelKey => "unsafe label key",
+        k8s::planner::NotPushableReason::ComputedField => "computed field",
+        k8s::planner::NotPushableReason::FieldReference => "field reference value",
+        k8s::planner::NotPushableReason::ComputedValue => "computed value",
+        k8s::planner::NotPushableReason::WildcardPath => "wildcard path",
+        k8s::planner::NotPushableReason::DuplicateFieldSelector => "duplicate field selector key",
+        k8s::planner::NotPushableReason::SetOnFieldSelector => {
+            "field selectors have no set-based form"
+        }
+        k8s::planner::NotPushableReason::InsideOr => "inside `or`",
+        k8s::planner::NotPushableReason::InsideNot => "inside `not`",
+        k8s::planner::NotPushableReason::NameFanOutLimit => {
+            "too many `metadata.name` branches in `or` for per-name list fan-out"
+        }
     }
 }
 
-fn format_operator(operator: &parser::Operator) -> &'static str {
-    match operator {
-        parser::Operator::Eq => "==",
-        parser::Operator::Ne => "!=",
+fn grep_filter(args: &CliArgs) -> Option<(&str, bool)> {
+    args.grep
+        .as_deref()
+        .map(|needle| (needle, false))
+        .or_else(|| {
+            args.grep_ignore_case
+                .as_deref()
+                .map(|needle| (needle, true))
+        })
+}
+
+fn dump_plan(plan: &engine::QueryPlan) -> Result<String, CliError> {
+    serde_json::to_string_pretty(plan)
+        .map_err(|source| CliError::Output(crate::error::OutputError::JsonSerialize { source }))
+}
+
+fn format_contradiction(contradiction: &engine::Contradiction) -> String {
+    let format_side = |(op, value): &(engine::EngineOperator, serde_json::Value)| {
+        let op = match op {
+            engine::EngineOperator::Eq => "==",
+            engine::EngineOperator::Ne => "!=",
+            engine::EngineOperator::Gt => ">",
+            engine::EngineOperator::Ge => ">=",
+            engine::EngineOperator::Lt => "<",
+            engine::EngineOperator::Le => "<=",
+            engine::EngineOperator::In { .. } => "in",
+            engine::EngineOperator::NotIn { .. } => "not in",
+            engine::EngineOperator::Like(_) => "like",
+            engine::EngineOperator::Regex(_) => "=~",
+            engine::EngineOperator::NotRegex(_) => "!~",
+            engine::EngineOperator::IsNull => "is null",
+            engine::EngineOperator::IsNotNull => "is not null",
+            engine::EngineOperator::Exists => "exists",
+            engine::EngineOperator::HasKey => "haskey",
+        };
+        format!("{op} {}", format_predicate_value(value))
+    };
+    format!(
+        "[plan] predicates `{path} {}` and `{path} {}` can never both match; skipping list",
+        format_side(&contradiction.left),
+        format_side(&contradiction.right),
+        path = contradiction.path,
+    )
+}
+
+fn format_predicate_cost(predicate: &parser::Predicate, matched: usize, total: usize) -> String {
+    let expression = match predicate.op {
+        parser::Operator::Exists => format!("exists {}", predicate.path),
+        parser::Operator::HasKey => format!(
+            "haskey {} {}",
+            predicate.path,
+            format_predicate_value(&predicate.value)
+        ),
+        parser::Operator::IsNull | parser::Operator::IsNotNull => format!(
+            "{} {}",
+            format_predicate_lhs(predicate),
+            format_operator(&predicate.op)
+        ),
+        parser::Operator::Like(ref pattern) => {
+            format!("{} like {pattern}", format_predicate_lhs(predicate))
+        }
+        parser::Operator::In { ref values } | parser::Operator::NotIn { ref values } => format!(
+            "{} {} ({})",
+            format_predicate_lhs(predicate),
+            format_operator(&predicate.op),
+            values
+                .iter()
+                .map(format_predicate_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => format!(
+            "{} {} {}",
+            format_predicate_lhs(predicate),
+            format_operator(&predicate.op),
+            format_predicate_rhs(predicate)
+        ),
+    };
+    format!("[explain] predicate `{expression}` matches {matched} of {total} objects")
+}
+
+fn format_predicate_rhs(predicate: &parser::Predicate) -> String {
+    if let Some(path) = &predicate.value_path {
+        return format!("@{path}");
+    }
+    match predicate.value_function {
+        Some(parser::ValueFunction::Now) => "now()".to_string(),
+        None => format_predicate_value(&predicate.value),
+    }
+}
+
+fn format_predicate_lhs(predicate: &parser::Predicate) -> String {
+    let Some(function) = predicate.function else {
+        return predicate.path.clone();
+    };
+    let name = match function {
+        parser::FieldFunction::Len => "len",
+        parser::FieldFunction::StrLen => "strlen",
+        parser::FieldFunction::Age => "age",
+        parser::FieldFunction::ImageRepo => "image_repo",
+        parser::FieldFunction::ImageTag => "image_tag",
+        parser::FieldFunction::ArraySum => "sum",
+        parser::FieldFunction::ArrayMin => "min",
+        parser::FieldFunction::ArrayMax => "max",
+        parser::FieldFunction::ArrayAvg => "avg",
+    };
+    format!("{name}({})", predicate.path.replace(".*", "[*]"))
+}
+
+fn format_predicate_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn format_operator(operator: &parser::Operator) -> &'static str {
+    match operator {
+        parser::Operator::Eq => "==",
+        parser::Operator::Ne => "!=",
+        parser::Operator::Gt => ">",
+        parser::Operator::Ge => ">=",
+        parser::Operator::Lt => "<",
+        parser::Operator::Le => "<=",
+        parser::Operator::In { .. } => "in",
+        parser::Operator::NotIn { .. } => "not in",
+        parser::Operator::Like(_) => "like",
+        parser::Operator::Regex => "=~",
+        parser::Operator::NotRegex => "!~",
+        parser::Operator::IsNull => "is null",
+        parser::Operator::IsNotNull => "is not null",
+        parser::Operator::Exists => "exists",
+        parser::Operator::HasKey => "haskey",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::error::{CliError, K8sError, OutputError, RetryErrorKind, RetryStopReason, boxed_error};
+
+    use super::{
+        CliArgs, OutputArg, ast_to_engine_plan, build_query_ast, check_diagnostics,
+        check_pushdown_required, coerce_rows, connection_options, dump_plan, duration_columns,
+        empty_result_message, engine_plan, format_context_diagnostic, format_contradiction,
+        format_k8s_diagnostic, format_list_summary, format_planner_diagnostic,
+        format_predicate_cost, format_resolution_trace, grep_filter, list_mode, list_options,
+        merge_context_results, output_paths_for_plan, parse_query_tokens, plan_pushdown,
+        sample_objects, select_coercions, tie_break,
+    };
+    use crate::{
+        dynamic_object::{CONTEXT_FIELD, DynamicObject},
+        engine::{
+            EngineAggregationFunction, EngineOperator, EngineSelection, EngineSortDirection,
+            TieBreak,
+        },
+        k8s::{
+            K8sDiagnostic, ListMode, ListQueryOptions, ListResult, SelectorFallbackReason,
+            planner::NotPushableReason,
+            resolution::{ResolutionCandidate, trace_resolution},
+        },
+        parser::{Operator, SelectClause},
+    };
+
+    #[test]
+    fn parses_flags_with_clap() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "-o",
+            "json",
+            "-d",
+            "pods",
+            "where",
+            "metadata.name",
+            "==",
+            "pod-a",
+        ]);
+
+        assert!(matches!(args.output, OutputArg::Json));
+        assert!(args.describe);
+        assert!(!args.no_pushdown_warnings);
+        assert_eq!(args.resource, "pods");
+        assert_eq!(args.query.first().map(String::as_str), Some("where"));
+    }
+
+    #[test]
+    fn parses_output_enum_case_insensitive() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--output",
+            "YAML",
+            "pods",
+            "where",
+            "metadata.name",
+            "==",
+            "pod-a",
+        ]);
+        assert!(matches!(args.output, OutputArg::Yaml));
+    }
+
+    #[test]
+    fn parses_custom_columns_output_spec() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "-o",
+            "custom-columns=NAME:.metadata.name,PHASE:status.phase",
+            "pods",
+            "where",
+            "metadata.namespace",
+            "==",
+            "demo-a",
+        ]);
+        assert!(matches!(
+            &args.output,
+            OutputArg::CustomColumns(columns) if columns == &[
+                ("NAME".to_string(), "metadata.name".to_string()),
+                ("PHASE".to_string(), "status.phase".to_string()),
+            ]
+        ));
+
+        let invalid = [
+            ("custom-columns=NAME:metadata.name,PHASE", "`PHASE`"),
+            ("custom-columns=:metadata.name", "`:metadata.name`"),
+            ("xml", "`xml`"),
+        ];
+        for (spec, token) in invalid {
+            let argv = ["kubiq", "-o", spec, "pods", "where", "a", "==", "b"];
+            let error = CliArgs::try_parse_from(argv).expect_err("invalid output spec must fail");
+            assert!(error.to_string().contains(token), "{error}");
+        }
+    }
+
+    #[test]
+    fn parses_no_pushdown_warnings_flag() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--no-pushdown-warnings",
+            "pods",
+            "where",
+            "metadata.name",
+            "==",
+            "pod-a",
+        ]);
+        assert!(args.no_pushdown_warnings);
+    }
+
+    #[test]
+    fn require_pushdown_rejects_full_scan_queries() {
+        let check = |query: &[&str]| {
+            let args =
+                CliArgs::parse_from(["kubiq", "--require-pushdown", "pods"].iter().chain(query));
+            let ast = build_query_ast(&args.query, None).expect("query must parse");
+            check_pushdown_required(&args, &plan_pushdown(&args, &ast))
+        };
+
+        assert!(matches!(
+            check(&["where", "spec.replicas", "==", "3"]),
+            Err(CliError::InvalidArgs(message)) if message.contains("--require-pushdown")
+        ));
+        assert!(check(&["where", "metadata.namespace", "==", "demo-a"]).is_ok());
+        assert!(
+            CliArgs::try_parse_from([
+                "kubiq",
+                "--require-pushdown",
+                "--no-pushdown",
+                "pods",
+                "--name",
+                "api",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn counts_without_objects_only_for_fully_pushed_count_queries() {
+        let mode = |flags: &[&str], query: &str, selection: &str| {
+            let argv = ["kubiq"].iter().chain(flags).chain(&["pods"]);
+            let args = CliArgs::parse_from(argv.chain(&[query, "select", selection]));
+            let ast = build_query_ast(&args.query, None).expect("query must parse");
+            let plan = engine_plan(&args, &ast).expect("plan must build");
+            list_mode(&args, &plan, &plan_pushdown(&args, &ast))
+        };
+        let labelled = "where metadata.labels.app == api";
+
+        assert_eq!(mode(&[], labelled, "count(*)"), ListMode::CountOnly);
+        assert_eq!(
+            mode(&[], "where exists metadata.labels.app", "count(*)"),
+            ListMode::CountOnly
+        );
+        assert_eq!(
+            mode(&[], "where spec.replicas == 3", "count(*)"),
+            ListMode::Objects
+        );
+        assert_eq!(
+            mode(&[], "where metadata.labels.app != api", "count(*)"),
+            ListMode::Objects
+        );
+        assert_eq!(
+            mode(&["--no-pushdown"], labelled, "count(*)"),
+            ListMode::Objects
+        );
+        assert_eq!(
+            mode(&["--grep", "api"], labelled, "count(*)"),
+            ListMode::Objects
+        );
+        assert_eq!(mode(&[], labelled, "count(spec)"), ListMode::Objects);
+    }
+
+    #[test]
+    fn pushes_limit_only_when_rows_follow_api_order() {
+        let limit = |flags: &[&str], query: &[&str]| {
+            let argv = ["kubiq"].iter().chain(flags).chain(&["pods"]);
+            let args = CliArgs::parse_from(argv.chain(query));
+            let ast = build_query_ast(&args.query, None).expect("query must parse");
+            let plan = engine_plan(&args, &ast).expect("plan must build");
+            list_options(&args, &plan, &plan_pushdown(&args, &ast)).limit
+        };
+        let labelled = ["where", "metadata.labels.app", "==", "api"];
+
+        assert_eq!(
+            limit(&[], &[&labelled[..], &["limit", "5"]].concat()),
+            Some(5)
+        );
+        assert_eq!(limit(&[], &labelled), None);
+        assert_eq!(
+            limit(&[], &["where", "spec.replicas", "==", "3", "limit", "5"]),
+            None
+        );
+        assert_eq!(
+            limit(
+                &[],
+                &[
+                    &labelled[..],
+                    &["order", "by", "metadata.name", "limit", "5"]
+                ]
+                .concat()
+            ),
+            None
+        );
+        assert_eq!(
+            limit(
+                &["--grep", "api"],
+                &[&labelled[..], &["limit", "5"]].concat()
+            ),
+            None
+        );
+        assert_eq!(
+            limit(
+                &[],
+                &[&labelled[..], &["limit", "5", "offset", "10"]].concat()
+            ),
+            Some(15)
+        );
+    }
+
+    #[test]
+    fn shard_by_takes_label_paths_and_disables_limit_pushdown() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--shard-by",
+            "metadata.labels.shard",
+            "pods",
+            "where",
+            "metadata.labels.app",
+            "==",
+            "api",
+            "limit",
+            "5",
+        ]);
+        let ast = build_query_ast(&args.query, None).expect("query must parse");
+        let plan = engine_plan(&args, &ast).expect("plan must build");
+        let options = list_options(&args, &plan, &plan_pushdown(&args, &ast));
+
+        assert_eq!(options.shard_by.as_deref(), Some("shard"));
+        assert_eq!(options.label_selector.as_deref(), Some("app=api"));
+        assert_eq!(options.limit, None);
+        assert!(
+            CliArgs::try_parse_from(["kubiq", "--shard-by", "spec.shard", "pods", "where"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn sample_picks_reproducible_subset_with_seed() {
+        let objects: Vec<DynamicObject> = (0..20)
+            .map(|index| {
+                let mut object = DynamicObject::default();
+                object.fields.insert(
+                    "metadata.name".to_string(),
+                    serde_json::Value::String(format!("pod-{index:02}")),
+                );
+                object
+            })
+            .collect();
+        let names = |sampled: Vec<DynamicObject>| -> Vec<String> {
+            sampled
+                .iter()
+                .filter_map(|object| object.get("metadata.name")?.as_str().map(str::to_string))
+                .collect()
+        };
+
+        let first = names(sample_objects(objects.clone(), 5, Some(42)));
+        assert_eq!(first.len(), 5);
+        assert_eq!(first, names(sample_objects(objects.clone(), 5, Some(42))));
+        assert!(first.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(sample_objects(objects.clone(), 20, Some(42)), objects);
+        assert_eq!(sample_objects(objects.clone(), 50, None), objects);
+    }
+
+    #[test]
+    fn no_pushdown_flag_sends_no_selectors() {
+        let query = [
+            "pods",
+            "where",
+            "metadata.namespace",
+            "==",
+            "demo-a",
+            "and",
+            "metadata.labels.app",
+            "==",
+            "api",
+        ];
+        let args = CliArgs::parse_from(["kubiq"].into_iter().chain(query));
+        let ast = build_query_ast(&args.query, None).expect("query must parse");
+        assert!(plan_pushdown(&args, &ast).options.label_selector.is_some());
+
+        let args = CliArgs::parse_from(["kubiq", "--no-pushdown"].into_iter().chain(query));
+        let ast = build_query_ast(&args.query, None).expect("query must parse");
+        let plan = plan_pushdown(&args, &ast);
+        assert_eq!(plan.options, ListQueryOptions::default());
+        assert!(plan.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn parses_max_value_bytes_flag() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--max-value-bytes",
+            "256",
+            "secrets",
+            "--name",
+            "tls",
+        ]);
+        assert_eq!(args.max_value_bytes, Some(256));
+    }
+
+    #[test]
+    fn decode_base64_is_disabled_by_default() {
+        let args = CliArgs::parse_from(["kubiq", "secrets", "--name", "db"]);
+        assert!(args.decode_base64.is_none());
+
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--decode-base64",
+            "data",
+            "secrets",
+            "--name",
+            "db",
+        ]);
+        assert_eq!(args.decode_base64.as_deref(), Some("data"));
+    }
+
+    #[test]
+    fn parses_ignore_case_flag() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--ignore-case",
+            "pods",
+            "where",
+            "metadata.namespace",
+            "==",
+            "demo-a",
+            "order",
+            "by",
+            "metadata.name",
+        ]);
+        assert!(args.ignore_case);
+    }
+
+    #[test]
+    fn insecure_skip_tls_verify_is_opt_in() {
+        let args = CliArgs::parse_from(["kubiq", "pods", "--name", "api"]);
+        assert!(!connection_options(&args, None).insecure_skip_tls_verify);
+
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--insecure-skip-tls-verify",
+            "pods",
+            "--name",
+            "api",
+        ]);
+        let connection = connection_options(&args, Some("dev"));
+        assert!(connection.insecure_skip_tls_verify);
+        assert_eq!(connection.context.as_deref(), Some("dev"));
+    }
+
+    #[test]
+    fn formats_resolution_trace_with_alternatives() {
+        let candidate = |group: &str, plural: &str, kind: &str| ResolutionCandidate {
+            group: group.to_string(),
+            version: "v1".to_string(),
+            plural: plural.to_string(),
+            kind: kind.to_string(),
+            matched_by: None,
+        };
+        let trace = trace_resolution(
+            "events",
+            [
+                candidate("", "pods", "Pod"),
+                candidate("", "events", "Event"),
+                candidate("events.k8s.io", "events", "Event"),
+            ],
+        );
+
+        let lines = format_resolution_trace("events", Some(&trace));
+        assert_eq!(
+            lines,
+            vec![
+                "[resolution] `events`: scanned 3 discovered resources",
+                "[resolution]   v1 pods (kind Pod): no match",
+                "[resolution]   v1 events (kind Event): matched by plural",
+                "[resolution]   events.k8s.io/v1 events (kind Event): matched by plural",
+                "[resolution] chose v1 events by plural",
+                "[resolution] alternative events.k8s.io/v1 events also matched by plural",
+            ]
+        );
+        assert_eq!(
+            format_resolution_trace("pods", None),
+            vec!["[resolution] `pods` resolved from discovery cache"]
+        );
+    }
+
+    #[test]
+    fn formats_list_summary_with_estimated_total() {
+        let mut result = list_result_with_names(&["api", "db"]);
+        result.request_count = 2;
+        assert_eq!(
+            format_list_summary(&result),
+            "[summary] listed 2 objects (pages=1, requests=2)"
+        );
+
+        result.estimated_total = Some(12_000);
+        result.truncated = true;
+        assert_eq!(
+            format_list_summary(&result),
+            "[summary] listed 2 of ~12000 objects (pages=1, requests=2), truncated"
+        );
+    }
+
+    #[test]
+    fn formats_predicate_cost_lines() {
+        let ast = build_query_ast(
+            &[
+                "where".to_string(),
+                "spec.replicas".to_string(),
+                "==".to_string(),
+                "3".to_string(),
+                "and".to_string(),
+                "exists".to_string(),
+                "metadata.labels.app".to_string(),
+            ],
+            None,
+        )
+        .expect("must build query");
+
+        assert_eq!(
+            format_predicate_cost(ast.predicates()[0], 4, 10),
+            "[explain] predicate `spec.replicas == 3` matches 4 of 10 objects"
+        );
+        assert_eq!(
+            format_predicate_cost(ast.predicates()[1], 7, 10),
+            "[explain] predicate `exists metadata.labels.app` matches 7 of 10 objects"
+        );
+    }
+
+    #[test]
+    fn fail_on_any_diagnostic_turns_diagnostics_into_error() {
+        assert!(check_diagnostics(false, 3).is_ok());
+        assert!(check_diagnostics(true, 0).is_ok());
+        assert!(matches!(
+            check_diagnostics(true, 2),
+            Err(CliError::DiagnosticsReported(2))
+        ));
+    }
+
+    #[test]
+    fn parses_repeatable_header_flag_into_connection_options() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--header",
+            "X-Audit-Reason: incident 4711",
+            "--header",
+            "X-Request-Id:abc",
+            "pods",
+            "--name",
+            "api",
+        ]);
+
+        let connection = connection_options(&args, None);
+        let headers: Vec<(&str, &str)> = connection
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            headers,
+            vec![("x-audit-reason", "incident 4711"), ("x-request-id", "abc")]
+        );
+    }
+
+    #[test]
+    fn parses_retry_on_flag_into_connection_options() {
+        let default_args = CliArgs::parse_from(["kubiq", "pods", "--name", "api"]);
+        assert_eq!(
+            connection_options(&default_args, None).retry_on,
+            crate::k8s::RetryOn::All
+        );
+
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--retry-on",
+            "transport+429",
+            "pods",
+            "--name",
+            "api",
+        ]);
+        assert_eq!(
+            connection_options(&args, None).retry_on,
+            crate::k8s::RetryOn::TransportAnd429
+        );
+    }
+
+    #[test]
+    fn parses_discovery_timeout_flag_into_connection_options() {
+        let default_args = CliArgs::parse_from(["kubiq", "pods", "--name", "api"]);
+        assert_eq!(
+            connection_options(&default_args, None).discovery_timeout,
+            None
+        );
+
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--discovery-timeout-ms",
+            "15000",
+            "pods",
+            "--name",
+            "api",
+        ]);
+        assert_eq!(
+            connection_options(&args, None).discovery_timeout,
+            Some(std::time::Duration::from_secs(15))
+        );
+    }
+
+    #[test]
+    fn parses_context_flag_into_connection_options() {
+        let args = CliArgs::parse_from(["kubiq", "--context", "staging", "pods", "--name", "api"]);
+        assert_eq!(
+            connection_options(&args, args.context.as_deref())
+                .context
+                .as_deref(),
+            Some("staging")
+        );
+        assert!(
+            CliArgs::try_parse_from([
+                "kubiq",
+                "--context",
+                "staging",
+                "--contexts",
+                "dev,prod",
+                "pods",
+                "--name",
+                "api",
+            ])
+            .is_err()
+        );
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use clap::Parser;
 
-    use crate::error::{CliError, K8sError, OutputError, RetryErrorKind, RetryStopReason, boxed_error};
+    #[test]
+    fn parses_namespace_scope_flags_into_connection_options() {
+        let default_args = CliArgs::parse_from(["kubiq", "pods", "--name", "api"]);
+        let connection = connection_options(&default_args, None);
+        assert_eq!(connection.namespace, None);
+        assert!(!connection.all_namespaces);
 
-    use super::{
-        CliArgs, OutputArg, ast_to_engine_plan, format_k8s_diagnostic, format_planner_diagnostic,
-        output_paths_for_rows, parse_query_tokens,
-    };
-    use crate::{
-        dynamic_object::DynamicObject,
-        engine::{EngineAggregationFunction, EngineOperator, EngineSelection, EngineSortDirection},
-        k8s::{
-            K8sDiagnostic, ListQueryOptions, SelectorFallbackReason, planner::NotPushableReason,
-        },
-        parser::{Operator, SelectClause},
-    };
+        let args = CliArgs::parse_from(["kubiq", "-n", "demo-a", "pods", "--name", "api"]);
+        assert_eq!(
+            connection_options(&args, None).namespace.as_deref(),
+            Some("demo-a")
+        );
+        let args = CliArgs::parse_from(["kubiq", "-A", "pods", "--name", "api"]);
+        assert!(connection_options(&args, None).all_namespaces);
+        assert!(
+            CliArgs::try_parse_from(["kubiq", "-A", "-n", "demo-a", "pods", "--name", "api"])
+                .is_err()
+        );
+    }
 
     #[test]
-    fn parses_flags_with_clap() {
-        let args = CliArgs::parse_from([
+    fn rejects_restricted_header_flag() {
+        let result = CliArgs::try_parse_from([
             "kubiq",
-            "-o",
-            "json",
-            "-d",
+            "--header",
+            "Authorization: Bearer x",
             "pods",
-            "where",
-            "metadata.name",
-            "==",
-            "pod-a",
+            "--name",
+            "api",
         ]);
+        assert!(result.is_err());
+    }
 
-        assert!(matches!(args.output, OutputArg::Json));
-        assert!(args.describe);
-        assert!(!args.no_pushdown_warnings);
-        assert_eq!(args.resource, "pods");
-        assert_eq!(args.query.first().map(String::as_str), Some("where"));
+    #[test]
+    fn parses_grep_flags_with_case_mode() {
+        let args = CliArgs::parse_from(["kubiq", "--grep", "billing", "pods", "--name", "api"]);
+        assert_eq!(grep_filter(&args), Some(("billing", false)));
+
+        let args = CliArgs::parse_from(["kubiq", "--grep-i", "Billing", "pods", "--name", "api"]);
+        assert_eq!(grep_filter(&args), Some(("Billing", true)));
+
+        let result = CliArgs::try_parse_from([
+            "kubiq", "--grep", "a", "--grep-i", "b", "pods", "--name", "api",
+        ]);
+        assert!(result.is_err());
     }
 
     #[test]
-    fn parses_output_enum_case_insensitive() {
+    fn parses_omit_flag_as_path_list() {
         let args = CliArgs::parse_from([
             "kubiq",
-            "--output",
-            "YAML",
+            "-d",
+            "--omit",
+            "metadata.managedFields,metadata.annotations",
             "pods",
-            "where",
-            "metadata.name",
-            "==",
-            "pod-a",
+            "--name",
+            "api",
         ]);
-        assert!(matches!(args.output, OutputArg::Yaml));
+        assert_eq!(
+            args.omit,
+            vec![
+                "metadata.managedFields".to_string(),
+                "metadata.annotations".to_string()
+            ]
+        );
     }
 
     #[test]
-    fn parses_no_pushdown_warnings_flag() {
+    fn parses_contexts_flag_as_list() {
         let args = CliArgs::parse_from([
             "kubiq",
-            "--no-pushdown-warnings",
+            "--contexts",
+            "ctx-a,ctx-b",
+            "--strict",
             "pods",
-            "where",
-            "metadata.name",
-            "==",
-            "pod-a",
+            "--name",
+            "api",
         ]);
-        assert!(args.no_pushdown_warnings);
+        assert_eq!(
+            args.contexts,
+            vec!["ctx-a".to_string(), "ctx-b".to_string()]
+        );
+        assert!(args.strict);
+    }
+
+    #[test]
+    fn rejects_strict_without_contexts() {
+        let result = CliArgs::try_parse_from(["kubiq", "--strict", "pods", "--name", "api"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn merge_context_results_tags_objects_with_context() {
+        let results = vec![
+            ("ctx-a".to_string(), Ok(list_result_with_names(&["api"]))),
+            (
+                "ctx-b".to_string(),
+                Ok(list_result_with_names(&["api", "db"])),
+            ),
+        ];
+
+        let merged = merge_context_results(results, false).expect("must merge");
+
+        let tagged: Vec<(String, String)> = merged
+            .objects
+            .iter()
+            .map(|object| {
+                (
+                    field_str(object, CONTEXT_FIELD),
+                    field_str(object, "metadata.name"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            tagged,
+            vec![
+                ("ctx-a".to_string(), "api".to_string()),
+                ("ctx-b".to_string(), "api".to_string()),
+                ("ctx-b".to_string(), "db".to_string()),
+            ]
+        );
+        assert!(merged.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn merge_context_results_turns_failures_into_diagnostics() {
+        let results = vec![
+            ("ctx-a".to_string(), Err(K8sError::EmptyResourceName)),
+            ("ctx-b".to_string(), Ok(list_result_with_names(&["api"]))),
+        ];
+
+        let merged = merge_context_results(results, false).expect("must merge");
+
+        assert_eq!(merged.objects.len(), 1);
+        assert_eq!(merged.diagnostics.len(), 1);
+        assert_eq!(
+            format_context_diagnostic(&merged.diagnostics[0]),
+            "[context=ctx-a] query failed, skipping context: resource name is empty"
+        );
+    }
+
+    #[test]
+    fn merge_context_results_fails_fast_in_strict_mode() {
+        let results = vec![
+            ("ctx-a".to_string(), Ok(list_result_with_names(&["api"]))),
+            ("ctx-b".to_string(), Err(K8sError::EmptyResourceName)),
+        ];
+
+        let result = merge_context_results(results, true);
+        assert!(matches!(
+            result,
+            Err(CliError::K8s(K8sError::EmptyResourceName))
+        ));
+    }
+
+    #[test]
+    fn merge_context_results_fails_when_every_context_fails() {
+        let results = vec![("ctx-a".to_string(), Err(K8sError::EmptyResourceName))];
+
+        let result = merge_context_results(results, false);
+        assert!(matches!(
+            result,
+            Err(CliError::K8s(K8sError::EmptyResourceName))
+        ));
+    }
+
+    #[test]
+    fn parses_name_flag_without_query() {
+        let args = CliArgs::parse_from(["kubiq", "pods", "--name", "api-xyz"]);
+        assert_eq!(args.name.as_deref(), Some("api-xyz"));
+        assert!(args.query.is_empty());
+    }
+
+    #[test]
+    fn rejects_missing_query_without_name_flag() {
+        let result = CliArgs::try_parse_from(["kubiq", "pods"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn name_flag_injects_metadata_name_predicate() {
+        let ast = build_query_ast(&[], Some("api-xyz")).expect("must build query");
+        assert_eq!(ast.predicates().len(), 1);
+        assert_eq!(ast.predicates()[0].path, "metadata.name");
+        assert_eq!(ast.predicates()[0].op, Operator::Eq);
+        assert_eq!(
+            ast.predicates()[0].value,
+            serde_json::Value::String("api-xyz".to_string())
+        );
+        assert_eq!(ast.select, None);
+        assert_eq!(ast.order_by, None);
+    }
+
+    #[test]
+    fn name_flag_with_regex_metacharacters_uses_regex_match() {
+        let ast = build_query_ast(&[], Some("^api-[0-9]+$")).expect("must build query");
+        assert_eq!(ast.predicates()[0].op, Operator::Regex);
+        let ast = build_query_ast(&[], Some("node.example.com")).expect("must build query");
+        assert_eq!(ast.predicates()[0].op, Operator::Eq);
+        let ast = build_query_ast(&[], Some("api-*")).expect("must build query");
+        assert_eq!(ast.predicates()[0].op, Operator::Like("api-*".to_string()));
+    }
+
+    #[test]
+    fn name_flag_patterns_match_the_whole_name() {
+        let objects: Vec<DynamicObject> = ["api-server", "my-api-server", "api-1", "old-api-1-x"]
+            .into_iter()
+            .map(|name| DynamicObject {
+                fields: [("metadata.name".to_string(), serde_json::Value::from(name))]
+                    .into_iter()
+                    .collect(),
+            })
+            .collect();
+        let matching = |name: &str| {
+            let ast = build_query_ast(&[], Some(name)).expect("must build query");
+            let plan = ast_to_engine_plan(&ast).expect("plan must build");
+            crate::engine::evaluate(&plan, &objects)
+                .iter()
+                .filter_map(|object| object.fields["metadata.name"].as_str().map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching("api-*"), vec!["api-server", "api-1"]);
+        assert_eq!(matching("api-?"), vec!["api-1"]);
+        assert_eq!(matching("api-[0-9]+"), vec!["api-1"]);
+        assert_eq!(
+            matching("*api*"),
+            vec!["api-server", "my-api-server", "api-1", "old-api-1-x"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_regex_when_building_plan() {
+        let ast = build_query_ast(&["where metadata.name =~ 'api-(['".to_string()], None)
+            .expect("query must parse");
+        let Err(CliError::Parse(message)) = ast_to_engine_plan(&ast) else {
+            panic!("invalid regex must be a parse error");
+        };
+        assert!(message.contains("invalid regex `api-([`"));
+    }
+
+    #[test]
+    fn name_flag_is_anded_with_explicit_where() {
+        let tokens = vec![
+            "where".to_string(),
+            "metadata.namespace".to_string(),
+            "==".to_string(),
+            "demo-a".to_string(),
+            "select".to_string(),
+            "metadata.name".to_string(),
+        ];
+
+        let ast = build_query_ast(&tokens, Some("api-xyz")).expect("must build query");
+        assert_eq!(ast.predicates().len(), 2);
+        assert_eq!(ast.predicates()[0].path, "metadata.namespace");
+        assert_eq!(ast.predicates()[1].path, "metadata.name");
+        assert_eq!(
+            ast.predicates()[1].value,
+            serde_json::Value::String("api-xyz".to_string())
+        );
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec!["metadata.name".to_string()]))
+        );
     }
 
     #[test]
@@ -366,7 +2338,7 @@ mod tests {
         ];
 
         let ast = parse_query_tokens(&tokens).expect("must parse query tokens");
-        assert_eq!(ast.predicates.len(), 1);
+        assert_eq!(ast.predicates().len(), 1);
         assert_eq!(
             ast.select,
             Some(SelectClause::Paths(vec!["metadata.name".to_string()]))
@@ -404,13 +2376,13 @@ mod tests {
         )
         .expect("must parse query");
 
-        let plan = ast_to_engine_plan(&ast);
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
 
-        assert_eq!(plan.predicates.len(), 2);
-        assert_eq!(plan.predicates[0].path, "metadata.namespace");
-        assert_eq!(plan.predicates[0].op, EngineOperator::Eq);
-        assert_eq!(plan.predicates[1].path, "spec.enabled");
-        assert_eq!(plan.predicates[1].op, EngineOperator::Ne);
+        assert_eq!(plan.predicates().len(), 2);
+        assert_eq!(plan.predicates()[0].path, "metadata.namespace");
+        assert_eq!(plan.predicates()[0].op, EngineOperator::Eq);
+        assert_eq!(plan.predicates()[1].path, "spec.enabled");
+        assert_eq!(plan.predicates()[1].op, EngineOperator::Ne);
         assert_eq!(
             plan.selection,
             Some(EngineSelection::Paths(vec!["metadata.name".to_string()]))
@@ -433,7 +2405,7 @@ mod tests {
         )
         .expect("must parse query");
 
-        let plan = ast_to_engine_plan(&ast);
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
         let Some(EngineSelection::Aggregations(expressions)) = plan.selection else {
             panic!("expected aggregation selection");
         };
@@ -445,17 +2417,227 @@ mod tests {
     }
 
     #[test]
-    fn output_paths_for_rows_uses_projection_paths() {
+    fn having_requires_aggregation_and_known_columns() {
+        let plan = |query: &str| {
+            let args = CliArgs::parse_from(["kubiq", "pods", query]);
+            let ast = build_query_ast(&args.query, None).expect("must parse");
+            engine_plan(&args, &ast)
+        };
+
+        let grouped = plan(
+            "where kind == Pod select count(*), max(age(metadata.ts)) group by spec.owner \
+             having count(*) > 1 and spec.owner != '' and max( age(metadata.ts) ) > 60",
+        )
+        .expect("plan must build");
+        let having = grouped.having.expect("having must be planned");
+        let paths: Vec<&str> = having
+            .predicates()
+            .into_iter()
+            .map(|predicate| predicate.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["count(*)", "spec.owner", "max(age(metadata.ts))"]
+        );
+        let Some(EngineSelection::Aggregations(expressions)) = grouped.selection else {
+            panic!("aggregation selection expected");
+        };
+        assert_eq!(crate::engine::aggregation_key(&expressions[1]), paths[2]);
+
+        assert!(matches!(
+            plan("where kind == Pod select metadata.name having count(*) > 1"),
+            Err(CliError::InvalidArgs(message)) if message.contains("aggregation query")
+        ));
+        assert!(matches!(
+            plan("where kind == Pod select count(*) having sum(spec.replicas) > 1"),
+            Err(CliError::InvalidArgs(message)) if message.contains("`sum(spec.replicas)`")
+        ));
+    }
+
+    #[test]
+    fn rejects_offset_for_aggregation_queries() {
+        let plan = |query: &str| {
+            let args = CliArgs::parse_from(["kubiq", "pods", query]);
+            let ast = build_query_ast(&args.query, None).expect("must parse");
+            engine_plan(&args, &ast)
+        };
+
+        assert!(matches!(
+            plan("where kind == Pod select count(*) limit 1 offset 2"),
+            Err(CliError::InvalidArgs(message)) if message.contains("`offset`")
+        ));
+        assert!(plan("where kind == Pod select count(*) limit 1").is_ok());
+        assert!(plan("where kind == Pod select metadata.name limit 1 offset 2").is_ok());
+    }
+
+    #[test]
+    fn tie_break_flags_select_mode_and_require_order_by() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "pods",
+            "--name",
+            "api",
+            "--tie-break",
+            "metadata.name",
+        ]);
+        assert_eq!(
+            tie_break(&args),
+            TieBreak::Path("metadata.name".to_string())
+        );
+        let ast = build_query_ast(&args.query, args.name.as_deref()).expect("must parse");
+        assert!(matches!(
+            engine_plan(&args, &ast),
+            Err(CliError::InvalidArgs(_))
+        ));
+
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "pods",
+            "--tie-break",
+            "metadata.name",
+            "where",
+            "a",
+            "==",
+            "1",
+            "order",
+            "by",
+            "status.phase",
+        ]);
+        let ast = build_query_ast(&args.query, None).expect("must parse");
+        assert!(engine_plan(&args, &ast).is_ok());
+
+        let args = CliArgs::parse_from(["kubiq", "pods", "--name", "api", "--keep-input-order"]);
+        assert_eq!(tie_break(&args), TieBreak::InputOrder);
+        let conflicting = [
+            "kubiq",
+            "pods",
+            "--name",
+            "api",
+            "--keep-input-order",
+            "--tie-break",
+            "a",
+        ];
+        assert!(CliArgs::try_parse_from(conflicting).is_err());
+    }
+
+    #[test]
+    fn formats_contradiction_diagnostic() {
+        let ast = build_query_ast(
+            &["where metadata.namespace == a and metadata.namespace == b".to_string()],
+            None,
+        )
+        .expect("query must parse");
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
+        let contradiction =
+            crate::engine::find_contradiction(&plan).expect("plan must be contradictory");
+
+        assert_eq!(
+            format_contradiction(&contradiction),
+            "[plan] predicates `metadata.namespace == a` and `metadata.namespace == b` can never both match; skipping list"
+        );
+    }
+
+    #[test]
+    fn dumps_engine_plan_as_stable_json() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "--dump-plan",
+            "pods",
+            "where",
+            "metadata.namespace",
+            "==",
+            "demo-a",
+            "and",
+            "exists",
+            "metadata.labels.app",
+            "order",
+            "by",
+            "len(spec.containers)",
+            "desc",
+            "select",
+            "metadata.name",
+            "limit",
+            "10",
+        ]);
+        assert!(args.dump_plan);
+        let ast = build_query_ast(&args.query, None).expect("must parse query");
+
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
+        let dumped: serde_json::Value =
+            serde_json::from_str(&dump_plan(&plan).expect("plan must dump"))
+                .expect("dump must be json");
+
+        assert_eq!(
+            dumped,
+            serde_json::json!({
+                "filter": {
+                    "and": [
+                        {
+                            "leaf": {
+                                "path": "metadata.namespace",
+                                "function": null,
+                                "op": "eq",
+                                "value": "demo-a",
+                                "value_path": null,
+                                "value_function": null
+                            }
+                        },
+                        {
+                            "leaf": {
+                                "path": "metadata.labels.app",
+                                "function": null,
+                                "op": "exists",
+                                "value": null,
+                                "value_path": null,
+                                "value_function": null
+                            }
+                        }
+                    ]
+                },
+                "selection": { "paths": ["metadata.name"] },
+                "sort_keys": [
+                    { "path": "spec.containers", "function": "len", "direction": "desc" }
+                ],
+                "limit": { "count": 10, "offset": 0 },
+                "computed": [],
+                "group_by": null,
+                "having": null,
+                "distinct": false
+            })
+        );
+    }
+
+    #[test]
+    fn marks_age_aggregations_as_duration_columns() {
+        let ast = crate::parser::parse_query(
+            "where kind == Pod select max(age(metadata.ts)), count(age(metadata.ts)), sum(spec.replicas)",
+        )
+        .expect("must parse");
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
+
+        assert_eq!(
+            duration_columns(&plan),
+            vec!["max(age(metadata.ts))".to_string()]
+        );
+    }
+
+    #[test]
+    fn output_paths_for_plan_uses_projection_paths() {
         let plan = crate::engine::QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Paths(vec![
                 "metadata.name".to_string(),
                 "metadata.namespace".to_string(),
             ])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
-        let paths = output_paths_for_rows(&plan, &[]).expect("paths must be present");
+        let paths = output_paths_for_plan(&plan).expect("paths must be present");
         assert_eq!(
             paths,
             vec![
@@ -466,29 +2648,101 @@ mod tests {
     }
 
     #[test]
-    fn output_paths_for_rows_uses_aggregation_row_keys() {
-        let plan = crate::engine::QueryPlan {
-            predicates: Vec::new(),
-            selection: Some(EngineSelection::Aggregations(Vec::new())),
-            sort_keys: None,
-        };
+    fn type_annotations_stay_out_of_output_paths_and_report_once() {
+        let ast = build_query_ast(
+            &["where kind == Pod select spec.replicas::int, metadata.name::int".to_string()],
+            None,
+        )
+        .expect("query must parse");
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
+        assert_eq!(
+            output_paths_for_plan(&plan),
+            Some(vec![
+                "spec.replicas".to_string(),
+                "metadata.name".to_string()
+            ])
+        );
 
-        let row = DynamicObject {
-            fields: [
-                ("count(*)".to_string(), serde_json::Value::from(2)),
-                ("sum(spec.replicas)".to_string(), serde_json::Value::from(5)),
-            ]
-            .into_iter()
-            .collect(),
+        let row = |name: &str| {
+            let mut object = DynamicObject::default();
+            object
+                .fields
+                .insert("spec.replicas".to_string(), serde_json::json!("3"));
+            object
+                .fields
+                .insert("metadata.name".to_string(), serde_json::json!(name));
+            object
         };
+        let mut rows = vec![row("pod-a"), row("pod-b")];
+        let failed = coerce_rows(&mut rows, &select_coercions(&ast));
+
+        assert_eq!(
+            failed,
+            vec![("metadata.name".to_string(), "int".to_string())]
+        );
+        assert!(rows.iter().all(|row| {
+            row.get("spec.replicas") == Some(&serde_json::json!(3))
+                && row.get("metadata.name") == Some(&serde_json::Value::Null)
+        }));
+    }
+
+    #[test]
+    fn output_paths_for_plan_keep_aggregation_order() {
+        let ast = build_query_ast(
+            &[
+                "where kind == Pod select sum(spec.replicas), count(*), avg(spec.replicas)"
+                    .to_string(),
+            ],
+            None,
+        )
+        .expect("query must parse");
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
 
-        let paths = output_paths_for_rows(&plan, &[row]).expect("paths must be present");
+        let paths = output_paths_for_plan(&plan).expect("paths must be present");
         assert_eq!(
             paths,
-            vec!["count(*)".to_string(), "sum(spec.replicas)".to_string()]
+            vec!["sum(spec.replicas)", "count(*)", "avg(spec.replicas)"]
         );
     }
 
+    #[test]
+    fn output_paths_for_grouped_rows_lead_with_group_keys() {
+        let ast = build_query_ast(
+            &[
+                "where kind == Pod select sum(spec.replicas), count(*) group by spec.owner"
+                    .to_string(),
+            ],
+            None,
+        )
+        .expect("query must parse");
+        let plan = ast_to_engine_plan(&ast).expect("plan must build");
+
+        let paths = output_paths_for_plan(&plan).expect("paths must be present");
+        assert_eq!(paths, vec!["spec.owner", "sum(spec.replicas)", "count(*)"]);
+    }
+
+    #[test]
+    fn empty_message_is_emitted_only_for_zero_rows() {
+        let args = CliArgs::parse_from([
+            "kubiq",
+            "pods",
+            "--name",
+            "api",
+            "--empty-message",
+            "no pods matched",
+        ]);
+        let row = DynamicObject {
+            fields: [("metadata.name".to_string(), serde_json::Value::from("api"))]
+                .into_iter()
+                .collect(),
+        };
+
+        assert_eq!(empty_result_message(&args, &[]), Some("no pods matched"));
+        assert_eq!(empty_result_message(&args, &[row]), None);
+        let silent = CliArgs::parse_from(["kubiq", "pods", "--name", "api"]);
+        assert_eq!(empty_result_message(&silent, &[]), None);
+    }
+
     #[test]
     fn k8s_error_contains_connectivity_tip() {
         let err = CliError::K8s(K8sError::ApiUnreachable {
@@ -500,6 +2754,18 @@ mod tests {
         assert!(rendered.contains("kubectl cluster-info"));
     }
 
+    #[test]
+    fn k8s_tls_error_contains_certificate_tip() {
+        let err = CliError::K8s(K8sError::TlsError {
+            stage: "list",
+            source: boxed_error(std::io::Error::other("invalid peer certificate: Expired")),
+        });
+        let rendered = err.to_string();
+        assert!(rendered.contains("tls/certificate verification failed during list"));
+        assert!(rendered.contains("CA bundle"));
+        assert!(rendered.contains("--insecure-skip-tls-verify"));
+    }
+
     #[test]
     fn k8s_error_not_found_contains_api_resources_tip() {
         let err = CliError::K8s(K8sError::ResourceNotFound {
@@ -594,6 +2860,9 @@ mod tests {
             attempted: ListQueryOptions {
                 field_selector: Some("metadata.namespace=demo-a".to_string()),
                 label_selector: None,
+                limit: None,
+                shard_by: None,
+                name_fan_out: Vec::new(),
             },
         };
 
@@ -617,4 +2886,31 @@ mod tests {
         assert!(rendered.contains("retry cap reached"));
         assert!(rendered.contains("request timeout"));
     }
+
+    fn list_result_with_names(names: &[&str]) -> ListResult {
+        ListResult {
+            objects: names
+                .iter()
+                .map(|name| {
+                    let mut object = DynamicObject::default();
+                    object.fields.insert(
+                        "metadata.name".to_string(),
+                        serde_json::Value::String(name.to_string()),
+                    );
+                    object
+                })
+                .collect(),
+            pages_fetched: 1,
+            ..ListResult::default()
+        }
+    }
+
+    fn field_str(object: &DynamicObject, path: &str) -> String {
+        object
+            .fields
+            .get(path)
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("-")
+            .to_string()
+    }
 }
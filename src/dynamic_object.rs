@@ -1,25 +1,210 @@
 use std::collections::BTreeMap;
+use std::ops::Bound;
 
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use serde_json::Value;
 
+pub const CONTEXT_FIELD: &str = "context";
+pub const CHANGED_FIELD: &str = "__changed";
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct DynamicObject {
     pub fields: BTreeMap<String, Value>,
 }
 
 impl DynamicObject {
-    pub fn get(
-        &self,
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        self.fields.get(path).or_else(|| {
+            self.encoded_candidates(path)
+                .find(|(encoded_path, _)| crate::path::decode_path(encoded_path) == path)
+                .map(|(_, value)| value)
+        })
+    }
+
+    pub fn has_path(&self, path: &str) -> bool {
+        if self.get(path).is_some_and(|value| !value.is_null()) {
+            return true;
+        }
+
+        let prefix = format!("{path}.");
+        self.keys_with_prefix(&prefix).next().is_some()
+            || self.encoded_candidates(&prefix).any(|(encoded_path, _)| {
+                crate::path::decode_path(encoded_path).starts_with(&prefix)
+            })
+    }
+
+    /// Значения всех полей, декодированный путь которых начинается с `prefix`.
+    pub fn values_with_path_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = &'a Value> {
+        self.keys_with_prefix(prefix)
+            .chain(self.encoded_candidates(prefix))
+            .filter(move |(encoded_path, _)| {
+                crate::path::decode_path(encoded_path).starts_with(prefix)
+            })
+            .map(|(_, value)| value)
+    }
+
+    /// Значения полей, декодированный путь которых совпадает с `pattern`,
+    /// где сегмент `*` означает любой индекс массива
+    /// (`spec.containers.*.ports.*.containerPort`).
+    pub fn values_matching_indices<'a>(
+        &'a self,
+        pattern: &'a str,
+    ) -> impl Iterator<Item = &'a Value> {
+        let prefix = pattern
+            .split_once('*')
+            .map_or(pattern, |(prefix, _)| prefix);
+        self.keys_with_prefix(prefix)
+            .chain(self.encoded_candidates(prefix))
+            .filter(move |(encoded_path, _)| {
+                let path = crate::path::decode_path(encoded_path);
+                let mut segments = path.split('.');
+                pattern.split('.').all(|expected| {
+                    segments.next().is_some_and(|segment| match expected {
+                        "*" => !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()),
+                        expected => segment == expected,
+                    })
+                }) && segments.next().is_none()
+            })
+            .map(|(_, value)| value)
+    }
+
+    /// Декодированные ключи первого уровня под `path`, например ключи
+    /// labels для `metadata.labels`.
+    pub fn child_keys<'a>(&'a self, path: &str) -> impl Iterator<Item = String> + 'a {
+        let prefix = format!("{}.", crate::path::encode_path(path));
+        let mut previous: Option<String> = None;
+        self.keys_with_prefix(prefix.clone())
+            .filter_map(move |(encoded_path, _)| {
+                let segment = encoded_path[prefix.len()..].split('.').next()?;
+                let key = crate::path::decode_segment(segment);
+                if previous.as_deref() == Some(key.as_str()) {
+                    return None;
+                }
+                previous = Some(key.clone());
+                Some(key)
+            })
+    }
+
+    /// Ключи, которые могут декодироваться в путь, начинающийся с `path`.
+    /// Закодированный ключ совпадает с декодированным путем до первого
+    /// экранированного `.`/`%`, где в ключе стоит `%`, поэтому достаточно
+    /// range-сканов по префиксам `path[..i] + "%"` вместо перебора всех полей.
+    fn encoded_candidates<'a>(
+        &'a self,
         path: &str,
-    ) -> Option<&Value> {
+    ) -> impl Iterator<Item = (&'a String, &'a Value)> {
+        path.match_indices(['.', '%'])
+            .flat_map(move |(index, _)| self.keys_with_prefix(format!("{}%", &path[..index])))
+    }
+
+    fn keys_with_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (&String, &Value)> {
+        let prefix = prefix.as_ref().to_string();
         self.fields
-            .get(path)
+            .range::<str, _>((Bound::Included(prefix.as_str()), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(&prefix))
+    }
+
+    /// Удаляет поля по путям `paths` вместе с их поддеревьями.
+    pub fn omit_paths(&mut self, paths: &[String]) {
+        let prefixes: Vec<String> = paths.iter().map(|path| format!("{path}.")).collect();
+        self.fields.retain(|encoded_path, _| {
+            let path = crate::path::decode_path(encoded_path);
+            !paths
+                .iter()
+                .zip(&prefixes)
+                .any(|(omitted, prefix)| path == *omitted || path.starts_with(prefix.as_str()))
+        });
+    }
+
+    /// Есть ли строковое поле, содержащее `needle` (для `ignore_case`
+    /// сравнение без учета регистра).
+    pub fn contains_text(&self, needle: &str, ignore_case: bool) -> bool {
+        let needle = if ignore_case {
+            needle.to_lowercase()
+        } else {
+            needle.to_string()
+        };
+        self.fields.values().any(|value| match value {
+            Value::String(text) if ignore_case => text.to_lowercase().contains(&needle),
+            Value::String(text) => text.contains(&needle),
+            _ => false,
+        })
+    }
+
+    /// Декодирует из base64 строковые поля под `prefix` (включая сам `prefix`).
+    /// Невалидные значения (не base64 или не UTF-8) остаются без изменений,
+    /// их пути возвращаются для диагностики.
+    pub fn decode_base64_fields(&mut self, prefix: &str) -> Vec<String> {
+        let nested_prefix = format!("{prefix}.");
+        let mut undecodable = Vec::new();
+        for (encoded_path, value) in &mut self.fields {
+            let path = crate::path::decode_path(encoded_path);
+            if path != prefix && !path.starts_with(&nested_prefix) {
+                continue;
+            }
+            let Value::String(encoded) = value else {
+                continue;
+            };
+            match STANDARD
+                .decode(encoded.as_bytes())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+            {
+                Some(decoded) => *encoded = decoded,
+                None => undecodable.push(path),
+            }
+        }
+        undecodable
+    }
+
+    /// Приводит значение `path` к типу `target` для `select path::type`.
+    /// Неприводимое значение, в том числе вложенный объект, заменяется на
+    /// `null`, и возвращается `false`; отсутствующее значение не трогается.
+    pub fn coerce_path(&mut self, path: &str, target: &str) -> bool {
+        let Some(value) = crate::path::select_path_value(&self.fields, path) else {
+            return true;
+        };
+        if value.is_null() {
+            return true;
+        }
+        let coerced = coerce_value(&value, target);
+        let coerced_ok = coerced.is_some();
+        self.fields.insert(
+            crate::path::encode_path(path),
+            coerced.unwrap_or(Value::Null),
+        );
+        coerced_ok
+    }
+}
+
+fn coerce_value(value: &Value, target: &str) -> Option<Value> {
+    match (target, value) {
+        ("int", Value::Number(number)) => number
+            .as_i64()
             .or_else(|| {
-                self.fields
-                    .iter()
-                    .find(|(encoded_path, _)| crate::path::decode_path(encoded_path) == path)
-                    .map(|(_, value)| value)
+                number
+                    .as_f64()
+                    .filter(|n| n.fract() == 0.0)
+                    .map(|n| n as i64)
             })
+            .map(Value::from),
+        ("int", Value::String(text)) => text.trim().parse::<i64>().ok().map(Value::from),
+        ("float", Value::Number(number)) => number.as_f64().map(Value::from),
+        ("float", Value::String(text)) => text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .map(Value::from),
+        ("string", Value::String(_)) => Some(value.clone()),
+        ("string", Value::Number(_) | Value::Bool(_)) => Some(Value::String(value.to_string())),
+        ("bool", Value::Bool(_)) => Some(value.clone()),
+        ("bool", Value::String(text)) => text.trim().parse::<bool>().ok().map(Value::from),
+        _ => None,
     }
 }
 
@@ -42,6 +227,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn has_path_matches_exact_key_and_indexed_subkeys() {
+        let mut fields = BTreeMap::new();
+        fields.insert("metadata.name".to_string(), Value::String("worker-a".to_string()));
+        fields.insert(
+            "status.conditions.0.type".to_string(),
+            Value::String("Ready".to_string()),
+        );
+        let object = DynamicObject { fields };
+
+        assert!(object.has_path("metadata.name"));
+        assert!(object.has_path("status.conditions"));
+        assert!(object.has_path("status"));
+        assert!(!object.has_path("status.cond"));
+        assert!(!object.has_path("spec"));
+    }
+
     #[test]
     fn get_reads_encoded_path_via_raw_query_path() {
         let mut fields = BTreeMap::new();
@@ -55,4 +257,214 @@ mod tests {
             Some(&Value::String("2026-02-22T10:00:00Z".to_string()))
         );
     }
+
+    #[test]
+    fn encoded_lookups_match_linear_scan_on_large_object() {
+        let mut fields = BTreeMap::new();
+        for index in 0..20_000 {
+            fields.insert(
+                format!("metadata.annotations.team{index}%2Eexample%2Ecom/owner"),
+                Value::from(index),
+            );
+            fields.insert(format!("metadata.labels.app{index}"), Value::from(index));
+        }
+        fields.insert(
+            "metadata.annotations.odd%25key.nested".to_string(),
+            Value::String("percent".to_string()),
+        );
+        let object = DynamicObject { fields };
+        let linear_get = |path: &str| {
+            object
+                .fields
+                .iter()
+                .find(|(encoded_path, _)| crate::path::decode_path(encoded_path) == path)
+                .map(|(_, value)| value)
+        };
+
+        for index in (0..20_000).step_by(97) {
+            let path = format!("metadata.annotations.team{index}.example.com/owner");
+            assert_eq!(object.get(&path), Some(&Value::from(index)));
+            let scanned: Vec<_> = object
+                .encoded_candidates(&path)
+                .map(|(key, _)| key)
+                .collect();
+            assert_eq!(
+                scanned,
+                vec![&format!(
+                    "metadata.annotations.team{index}%2Eexample%2Ecom/owner"
+                )],
+                "encoded lookups must range-scan only keys under the escaped prefixes"
+            );
+        }
+        assert!(
+            object
+                .encoded_candidates("metadata.labels.app7")
+                .next()
+                .is_none()
+        );
+
+        for path in [
+            "metadata.annotations.team42.example.com/owner",
+            "metadata.annotations.odd%key.nested",
+            "metadata.annotations.team42.example.com",
+            "metadata.labels.app7",
+            "metadata.annotations.missing.example.com/owner",
+        ] {
+            assert_eq!(object.get(path), linear_get(path), "{path}");
+        }
+        assert!(object.has_path("metadata.annotations.team42.example.com/owner"));
+        assert!(object.has_path("metadata.annotations.odd%key"));
+        assert!(!object.has_path("metadata.annotations.team42.sample"));
+    }
+
+    #[test]
+    fn coerce_path_converts_values_and_nulls_failures() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.labels.replicas".to_string(),
+            Value::String("3".to_string()),
+        );
+        fields.insert("spec.replicas".to_string(), Value::from(3));
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("pod-a".to_string()),
+        );
+        let mut object = DynamicObject { fields };
+
+        assert!(object.coerce_path("metadata.labels.replicas", "int"));
+        assert!(object.coerce_path("spec.replicas", "string"));
+        assert!(!object.coerce_path("metadata.name", "int"));
+        assert!(!object.coerce_path("metadata.labels", "int"));
+        assert!(object.coerce_path("spec.missing", "int"));
+
+        assert_eq!(
+            object.get("metadata.labels.replicas"),
+            Some(&Value::from(3))
+        );
+        assert_eq!(
+            object.get("spec.replicas"),
+            Some(&Value::String("3".to_string()))
+        );
+        assert_eq!(object.get("metadata.name"), Some(&Value::Null));
+        assert_eq!(object.get("metadata.labels"), Some(&Value::Null));
+        assert_eq!(object.get("spec.missing"), None);
+    }
+
+    #[test]
+    fn decode_base64_fields_decodes_values_under_prefix() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "data.password".to_string(),
+            Value::String("czNjcjN0".to_string()),
+        );
+        fields.insert(
+            "data.tls%2Ecrt".to_string(),
+            Value::String("Y2VydA==".to_string()),
+        );
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("YWJj".to_string()),
+        );
+        let mut object = DynamicObject { fields };
+
+        let undecodable = object.decode_base64_fields("data");
+
+        assert!(undecodable.is_empty());
+        assert_eq!(
+            object.get("data.password"),
+            Some(&Value::String("s3cr3t".to_string()))
+        );
+        assert_eq!(
+            object.get("data.tls.crt"),
+            Some(&Value::String("cert".to_string()))
+        );
+        assert_eq!(
+            object.get("metadata.name"),
+            Some(&Value::String("YWJj".to_string()))
+        );
+    }
+
+    #[test]
+    fn decode_base64_fields_passes_invalid_input_through() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "data.token".to_string(),
+            Value::String("not base64!".to_string()),
+        );
+        fields.insert("data.binary".to_string(), Value::String("/w==".to_string()));
+        let mut object = DynamicObject { fields };
+
+        let undecodable = object.decode_base64_fields("data");
+
+        assert_eq!(
+            undecodable,
+            vec!["data.binary".to_string(), "data.token".to_string()]
+        );
+        assert_eq!(
+            object.get("data.token"),
+            Some(&Value::String("not base64!".to_string()))
+        );
+        assert_eq!(
+            object.get("data.binary"),
+            Some(&Value::String("/w==".to_string()))
+        );
+    }
+
+    #[test]
+    fn contains_text_scans_any_string_field() {
+        let object = |key: &str, value: Value| {
+            let mut fields = BTreeMap::new();
+            fields.insert(
+                "metadata.name".to_string(),
+                Value::String("api".to_string()),
+            );
+            fields.insert(key.to_string(), value);
+            DynamicObject { fields }
+        };
+        let matching = object(
+            "spec.containers.0.env.0.value",
+            Value::String("https://Billing.internal".to_string()),
+        );
+        let other = object("spec.replicas", Value::from(3));
+
+        assert!(matching.contains_text("Billing", false));
+        assert!(!matching.contains_text("billing", false));
+        assert!(matching.contains_text("billing", true));
+        assert!(!other.contains_text("billing", true));
+        assert!(!other.contains_text("3", false));
+    }
+
+    #[test]
+    fn omit_paths_removes_subtrees_and_keeps_siblings() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("api".to_string()),
+        );
+        fields.insert(
+            "metadata.managedFields".to_string(),
+            serde_json::json!([{"manager": "kubectl"}]),
+        );
+        fields.insert(
+            "metadata.managedFields.0.manager".to_string(),
+            Value::String("kubectl".to_string()),
+        );
+        fields.insert(
+            "metadata.annotations.kubectl%2Ekubernetes%2Eio/restartedAt".to_string(),
+            Value::String("2026-02-22T10:00:00Z".to_string()),
+        );
+        fields.insert(
+            "metadata.annotationsExtra".to_string(),
+            Value::String("kept".to_string()),
+        );
+        let mut object = DynamicObject { fields };
+
+        object.omit_paths(&[
+            "metadata.managedFields".to_string(),
+            "metadata.annotations".to_string(),
+        ]);
+
+        let keys: Vec<&str> = object.fields.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["metadata.annotationsExtra", "metadata.name"]);
+    }
 }
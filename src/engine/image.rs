@@ -0,0 +1,95 @@
+const DEFAULT_TAG: &str = "latest";
+/// Имена Docker Hub, registry по умолчанию для образов без host.
+const DOCKER_HUB_HOSTS: &[&str] = &["docker.io", "index.docker.io", "registry-1.docker.io"];
+/// Namespace официальных образов Docker Hub: `nginx` == `library/nginx`.
+const DOCKER_HUB_OFFICIAL_PREFIX: &str = "library/";
+
+/// Repository образа без registry, tag и digest:
+/// `ghcr.io/org/app:v1` -> `org/app`, `nginx:1.25` -> `nginx`.
+/// Для Docker Hub срезается и `library/`: `docker.io/library/nginx` -> `nginx`.
+pub fn image_repo(reference: &str) -> &str {
+    let (name, _) = split_digest(reference);
+    let (repo, _) = split_tag(strip_registry(name));
+    if is_docker_hub(name) {
+        repo.strip_prefix(DOCKER_HUB_OFFICIAL_PREFIX)
+            .unwrap_or(repo)
+    } else {
+        repo
+    }
+}
+
+/// Tag образа; без явного tag и digest Kubernetes подставляет `latest`.
+pub fn image_tag(reference: &str) -> Option<&str> {
+    let (name, digest) = split_digest(reference);
+    match split_tag(strip_registry(name)) {
+        (_, Some(tag)) => Some(tag),
+        (_, None) if digest.is_none() => Some(DEFAULT_TAG),
+        (_, None) => None,
+    }
+}
+
+fn split_digest(reference: &str) -> (&str, Option<&str>) {
+    match reference.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (reference, None),
+    }
+}
+
+fn strip_registry(name: &str) -> &str {
+    match name.split_once('/') {
+        Some((host, rest)) if is_registry_host(host) => rest,
+        _ => name,
+    }
+}
+
+fn is_docker_hub(name: &str) -> bool {
+    match name.split_once('/') {
+        Some((host, _)) if is_registry_host(host) => DOCKER_HUB_HOSTS.contains(&host),
+        _ => true,
+    }
+}
+
+fn is_registry_host(component: &str) -> bool {
+    component.contains('.') || component.contains(':') || component == "localhost"
+}
+
+fn split_tag(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once(':') {
+        Some((repo, tag)) => (repo, Some(tag)),
+        None => (name, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{image_repo, image_tag};
+
+    #[test]
+    fn parses_images_with_and_without_registry() {
+        assert_eq!(image_repo("nginx"), "nginx");
+        assert_eq!(image_repo("nginx:1.25"), "nginx");
+        assert_eq!(image_repo("bitnami/redis:7.2"), "bitnami/redis");
+        assert_eq!(image_repo("ghcr.io/org/app:v1"), "org/app");
+        assert_eq!(image_repo("localhost:5000/app:dev"), "app");
+        assert_eq!(image_repo("registry.local/app@sha256:abc"), "app");
+    }
+
+    #[test]
+    fn normalizes_docker_hub_official_images() {
+        assert_eq!(image_repo("docker.io/library/nginx:1.25"), "nginx");
+        assert_eq!(image_repo("index.docker.io/library/nginx"), "nginx");
+        assert_eq!(image_repo("library/nginx@sha256:abc"), "nginx");
+        assert_eq!(image_repo("docker.io/bitnami/redis:7.2"), "bitnami/redis");
+        assert_eq!(image_repo("ghcr.io/library/tool:v1"), "library/tool");
+    }
+
+    #[test]
+    fn parses_explicit_and_implicit_tags() {
+        assert_eq!(image_tag("nginx:1.25"), Some("1.25"));
+        assert_eq!(image_tag("localhost:5000/app:dev"), Some("dev"));
+        assert_eq!(image_tag("nginx"), Some("latest"));
+        assert_eq!(image_tag("registry.local:5000/org/app"), Some("latest"));
+        assert_eq!(image_tag("app:v2@sha256:abc"), Some("v2"));
+        assert_eq!(image_tag("app@sha256:abc"), None);
+    }
+}
@@ -1,77 +1,344 @@
+pub mod image;
+pub mod quantity;
+
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::SystemTime;
 
 use crate::dynamic_object::DynamicObject;
 use crate::error::EngineError;
+use serde::Serialize;
 use serde_json::Value;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct QueryPlan {
-    pub predicates: Vec<EnginePredicate>,
+    pub filter: Option<EngineExpr>,
     pub selection: Option<EngineSelection>,
     pub sort_keys: Option<Vec<EngineSortKey>>,
+    pub limit: Option<EngineLimit>,
+    pub computed: Vec<EngineComputedColumn>,
+    pub group_by: Option<Vec<String>>,
+    pub having: Option<EngineExpr>,
+    pub distinct: bool,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl QueryPlan {
+    /// Все predicates фильтра в порядке записи.
+    pub fn predicates(&self) -> Vec<&EnginePredicate> {
+        self.filter
+            .as_ref()
+            .map_or_else(Vec::new, EngineExpr::predicates)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineExpr {
+    And(Vec<EngineExpr>),
+    Or(Vec<EngineExpr>),
+    Not(Box<EngineExpr>),
+    Leaf(EnginePredicate),
+}
+
+impl EngineExpr {
+    /// Конъюнкция predicates; `None` для пустого списка.
+    pub fn all(predicates: Vec<EnginePredicate>) -> Option<Self> {
+        let mut terms: Vec<Self> = predicates.into_iter().map(Self::Leaf).collect();
+        match terms.len() {
+            0 => None,
+            1 => terms.pop(),
+            _ => Some(Self::And(terms)),
+        }
+    }
+
+    pub fn predicates(&self) -> Vec<&EnginePredicate> {
+        match self {
+            Self::And(terms) | Self::Or(terms) => terms.iter().flat_map(Self::predicates).collect(),
+            Self::Not(inner) => inner.predicates(),
+            Self::Leaf(predicate) => vec![predicate],
+        }
+    }
+
+    /// Predicates верхнего уровня `and`: каждый из них обязан выполниться.
+    pub fn conjuncts(&self) -> Vec<&EnginePredicate> {
+        match self {
+            Self::And(terms) => terms.iter().flat_map(Self::conjuncts).collect(),
+            Self::Leaf(predicate) => vec![predicate],
+            Self::Or(_) | Self::Not(_) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct EnginePredicate {
     pub path: String,
+    pub function: Option<EngineFieldFunction>,
     pub op: EngineOperator,
     pub value: Value,
+    pub value_path: Option<String>,
+    pub value_function: Option<EngineValueFunction>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EngineOperator {
     Eq,
     Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    In { values: Vec<Value> },
+    NotIn { values: Vec<Value> },
+    Like(String),
+    Regex(PredicateRegex),
+    NotRegex(PredicateRegex),
+    IsNull,
+    IsNotNull,
+    Exists,
+    HasKey,
+}
+
+/// Regex компилируется один раз при построении плана; сравнивается и
+/// сериализуется по исходному pattern.
+#[derive(Clone, Debug)]
+pub struct PredicateRegex(pub regex::Regex);
+
+impl PartialEq for PredicateRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for PredicateRegex {}
+
+impl Serialize for PredicateRegex {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+/// Булева колонка `select (<условие>) as <alias>`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EngineComputedColumn {
+    pub alias: String,
+    pub expr: EngineExpr,
+}
+
+/// Окно строк после сортировки: пропустить `offset`, оставить `count`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct EngineLimit {
+    pub count: usize,
+    pub offset: usize,
+}
+
+impl EngineLimit {
+    /// Сколько объектов нужно прочитать из API, чтобы заполнить окно.
+    pub fn end(self) -> usize {
+        self.offset.saturating_add(self.count)
+    }
+
+    /// Offset за концом результата оставляет пустой список, а не ошибку.
+    pub fn apply<T>(self, rows: &mut Vec<T>) {
+        rows.drain(..self.offset.min(rows.len()));
+        rows.truncate(self.count);
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct EngineSortKey {
     pub path: String,
+    pub function: Option<EngineFieldFunction>,
     pub direction: EngineSortDirection,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineFieldFunction {
+    Len,
+    StrLen,
+    Age,
+    ImageRepo,
+    ImageTag,
+    ArraySum,
+    ArrayMin,
+    ArrayMax,
+    ArrayAvg,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineValueFunction {
+    Now,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SortOptions {
+    pub ignore_case: bool,
+    pub tie_break: TieBreak,
+}
+
+/// Порядок объектов с равными ключами `order by`: исходный порядок API
+/// (сортировка stable) или дополнительный ключ по пути по возрастанию.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    #[default]
+    InputOrder,
+    Path(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EngineSortDirection {
     Asc,
     Desc,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EngineSelection {
     Paths(Vec<String>),
     Aggregations(Vec<EngineAggregationExpr>),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct EngineAggregationExpr {
     pub function: EngineAggregationFunction,
     pub path: Option<String>,
+    pub field_function: Option<EngineFieldFunction>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EngineAggregationFunction {
     Count,
     Sum,
     Min,
     Max,
     Avg,
+    Range,
+    Median,
+    Stddev,
+    Percentile(serde_json::Number),
+    Mode,
+    GroupConcat(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contradiction {
+    pub path: String,
+    pub left: (EngineOperator, Value),
+    pub right: (EngineOperator, Value),
+}
+
+/// Ищет пару predicates по одному полю, которые не могут выполниться
+/// одновременно: `== a` и `== b` при `a != b`, либо `== a` и `!= a`.
+/// Рассматриваются только условия верхнего уровня `and`.
+pub fn find_contradiction(plan: &QueryPlan) -> Option<Contradiction> {
+    let literal_predicates: Vec<&EnginePredicate> = plan
+        .filter
+        .as_ref()
+        .map_or_else(Vec::new, EngineExpr::conjuncts)
+        .into_iter()
+        .filter(|predicate| predicate.value_path.is_none() && !predicate.value.is_null())
+        .collect();
+
+    for (index, left) in literal_predicates.iter().enumerate() {
+        for right in &literal_predicates[index + 1..] {
+            if right.path != left.path
+                || right.function != left.function
+                || left.path.ends_with('*')
+            {
+                continue;
+            }
+            let contradicts = match (&left.op, &right.op) {
+                (EngineOperator::Eq, EngineOperator::Eq) => right.value != left.value,
+                (EngineOperator::Eq, EngineOperator::Ne)
+                | (EngineOperator::Ne, EngineOperator::Eq) => right.value == left.value,
+                _ => false,
+            };
+            if contradicts {
+                return Some(Contradiction {
+                    path: left.path.clone(),
+                    left: (left.op.clone(), left.value.clone()),
+                    right: (right.op.clone(), right.value.clone()),
+                });
+            }
+        }
+    }
+    None
 }
 
 pub fn evaluate(
     plan: &QueryPlan,
     objects: &[DynamicObject],
 ) -> Vec<DynamicObject> {
+    evaluate_iter(plan, objects)
+}
+
+/// Как `evaluate`, но для противоречивого плана не просматривает объекты.
+pub fn evaluate_iter<'a>(
+    plan: &QueryPlan,
+    objects: impl IntoIterator<Item = &'a DynamicObject>,
+) -> Vec<DynamicObject> {
+    evaluate_at(plan, objects, SystemTime::now())
+}
+
+/// Записывает в каждую строку поле `alias` со значением условия колонки,
+/// чтобы проекция `select` брала его как обычный путь.
+pub fn compute_columns(plan: &QueryPlan, rows: &mut [DynamicObject]) {
+    compute_columns_at(plan, rows, SystemTime::now());
+}
+
+/// Как `compute_columns`, но `now()` в условиях равно `now`.
+pub fn compute_columns_at(plan: &QueryPlan, rows: &mut [DynamicObject], now: SystemTime) {
+    for row in rows {
+        for column in &plan.computed {
+            let matched = matches_expr(row, &column.expr, now);
+            row.fields
+                .insert(column.alias.clone(), Value::Bool(matched));
+        }
+    }
+}
+
+/// Как `evaluate_iter`, но `now()` в predicates равно `now`.
+pub fn evaluate_at<'a>(
+    plan: &QueryPlan,
+    objects: impl IntoIterator<Item = &'a DynamicObject>,
+    now: SystemTime,
+) -> Vec<DynamicObject> {
+    if find_contradiction(plan).is_some() {
+        return Vec::new();
+    }
     objects
-        .iter()
-        .filter(|object| matches_all(object, &plan.predicates))
+        .into_iter()
+        .filter(|object| {
+            plan.filter
+                .as_ref()
+                .is_none_or(|filter| matches_expr(object, filter, now))
+        })
         .cloned()
         .collect()
 }
 
+pub fn predicate_match_counts(plan: &QueryPlan, objects: &[DynamicObject]) -> Vec<usize> {
+    let now = SystemTime::now();
+    plan.predicates()
+        .into_iter()
+        .map(|predicate| {
+            objects
+                .iter()
+                .filter(|object| matches_predicate(object, predicate, now))
+                .count()
+        })
+        .collect()
+}
+
 pub fn sort_objects(
     plan: &QueryPlan,
     objects: &[DynamicObject],
+    options: &SortOptions,
 ) -> Vec<DynamicObject> {
     let mut sorted = objects.to_vec();
 
@@ -79,7 +346,8 @@ pub fn sort_objects(
         return sorted;
     };
 
-    sorted.sort_by(|left, right| compare_objects(left, right, sort_keys));
+    let now = SystemTime::now();
+    sorted.sort_by(|left, right| compare_objects(left, right, sort_keys, options, now));
     sorted
 }
 
@@ -91,26 +359,188 @@ pub fn aggregate(
         return Ok(objects.to_vec());
     };
 
-    let mut row = BTreeMap::new();
+    let Some(group_by) = plan.group_by.as_deref() else {
+        return Ok(vec![aggregate_row(expressions, objects, BTreeMap::new())?]);
+    };
+
+    group_objects(group_by, objects)
+        .into_iter()
+        .map(|(key, members)| {
+            let fields = group_by.iter().cloned().zip(key).collect();
+            aggregate_row(expressions, &members, fields)
+        })
+        .collect()
+}
+
+/// Оставляет строки aggregation, для которых выполняется `having`; колонки
+/// адресуются по имени (`count(*)`, ключ `group by`).
+pub fn filter_groups(plan: &QueryPlan, rows: Vec<DynamicObject>) -> Vec<DynamicObject> {
+    let Some(having) = plan.having.as_ref() else {
+        return rows;
+    };
+    let now = SystemTime::now();
+    rows.into_iter()
+        .filter(|row| matches_expr(row, having, now))
+        .collect()
+}
+
+/// `select distinct`: из строк с одинаковой проекцией остается первая, так
+/// что порядок `order by` сохраняется. Проекция сравнивается по значениям
+/// путей без приведения `::type`; вычисляемые колонки — по значению условия.
+pub fn distinct_rows(plan: &QueryPlan, rows: Vec<DynamicObject>) -> Vec<DynamicObject> {
+    let Some(EngineSelection::Paths(paths)) = plan.selection.as_ref().filter(|_| plan.distinct)
+    else {
+        return rows;
+    };
+    let now = SystemTime::now();
+    let mut seen = HashSet::new();
+    rows.into_iter()
+        .filter(|row| {
+            let projected = project_row(plan, paths, row, now);
+            seen.insert(Value::Object(projected.fields.into_iter().collect()).to_string())
+        })
+        .collect()
+}
+
+fn project_row(
+    plan: &QueryPlan,
+    paths: &[String],
+    row: &DynamicObject,
+    now: SystemTime,
+) -> DynamicObject {
+    let fields = paths
+        .iter()
+        .map(|path| {
+            let value = match plan.computed.iter().find(|column| &column.alias == path) {
+                Some(column) => Value::Bool(matches_expr(row, &column.expr, now)),
+                None => {
+                    let path = path
+                        .split_once("::")
+                        .map_or(path.as_str(), |(path, _)| path);
+                    crate::path::select_path_value(&row.fields, path).unwrap_or(Value::Null)
+                }
+            };
+            (path.clone(), value)
+        })
+        .collect();
+    DynamicObject { fields }
+}
+
+fn aggregate_row(
+    expressions: &[EngineAggregationExpr],
+    objects: &[DynamicObject],
+    mut row: BTreeMap<String, Value>,
+) -> Result<DynamicObject, EngineError> {
     for expression in expressions {
         let key = aggregation_key(expression);
         let value = evaluate_aggregation(expression, objects)?;
         row.insert(key, value);
     }
+    Ok(DynamicObject { fields: row })
+}
+
+/// Группы в порядке первого появления; отсутствующий ключ и `null`
+/// попадают в одну группу со значением `null`.
+fn group_objects(
+    group_by: &[String],
+    objects: &[DynamicObject],
+) -> Vec<(Vec<Value>, Vec<DynamicObject>)> {
+    let mut groups: Vec<(Vec<Value>, Vec<DynamicObject>)> = Vec::new();
+    let mut index = HashMap::new();
+    for object in objects {
+        let key: Vec<Value> = group_by
+            .iter()
+            .map(|path| object.get(path).cloned().unwrap_or(Value::Null))
+            .collect();
+        let position = *index
+            .entry(Value::Array(key.clone()).to_string())
+            .or_insert_with(|| {
+                groups.push((key, Vec::new()));
+                groups.len() - 1
+            });
+        groups[position].1.push(object.clone());
+    }
+    groups
+}
+
+/// Выборка состоит только из `count(*)` без `group by`: результат зависит
+/// лишь от числа объектов, поэтому их можно не материализовывать.
+pub fn is_count_only(plan: &QueryPlan) -> bool {
+    let Some(EngineSelection::Aggregations(expressions)) = &plan.selection else {
+        return false;
+    };
+    if plan.group_by.is_some() {
+        return false;
+    }
+    matches!(
+        expressions.as_slice(),
+        [EngineAggregationExpr {
+            function: EngineAggregationFunction::Count,
+            path: None,
+            field_function: None,
+        }]
+    )
+}
 
-    Ok(vec![DynamicObject { fields: row }])
+/// Строка результата `count(*)` по уже посчитанному числу объектов.
+pub fn count_rows(plan: &QueryPlan, count: usize) -> Vec<DynamicObject> {
+    let Some(EngineSelection::Aggregations(expressions)) = &plan.selection else {
+        return Vec::new();
+    };
+    let row = expressions
+        .iter()
+        .map(|expression| (aggregation_key(expression), Value::from(count as u64)))
+        .collect();
+    vec![DynamicObject { fields: row }]
 }
 
 fn evaluate_aggregation(
     expression: &EngineAggregationExpr,
     objects: &[DynamicObject],
 ) -> Result<Value, EngineError> {
+    if let (Some(field_function), Some(path)) = (expression.field_function, &expression.path) {
+        let key = field_function_key(field_function, path);
+        let now = SystemTime::now();
+        let derived: Vec<DynamicObject> = objects
+            .iter()
+            .map(|object| {
+                let mut fields = BTreeMap::new();
+                if let Some(value) = apply_field_function(field_function, object, path, now) {
+                    fields.insert(key.clone(), value);
+                }
+                DynamicObject { fields }
+            })
+            .collect();
+        let derived_expression = EngineAggregationExpr {
+            function: expression.function.clone(),
+            path: Some(key),
+            field_function: None,
+        };
+        return evaluate_aggregation(&derived_expression, &derived);
+    }
+
     match expression.function {
         EngineAggregationFunction::Count => count_aggregation(expression.path.as_deref(), objects),
         EngineAggregationFunction::Sum => sum_aggregation(required_path(expression)?, objects),
         EngineAggregationFunction::Min => min_max_aggregation(required_path(expression)?, objects, true),
         EngineAggregationFunction::Max => min_max_aggregation(required_path(expression)?, objects, false),
         EngineAggregationFunction::Avg => avg_aggregation(required_path(expression)?, objects),
+        EngineAggregationFunction::Range => range_aggregation(required_path(expression)?, objects),
+        EngineAggregationFunction::Median => {
+            median_aggregation(required_path(expression)?, objects)
+        }
+        EngineAggregationFunction::Stddev => {
+            stddev_aggregation(required_path(expression)?, objects)
+        }
+        EngineAggregationFunction::Percentile(ref rank) => {
+            percentile_aggregation(required_path(expression)?, rank, objects)
+        }
+        EngineAggregationFunction::Mode => mode_aggregation(required_path(expression)?, objects),
+        EngineAggregationFunction::GroupConcat(ref separator) => Ok(group_concat_aggregation(
+            required_path(expression)?,
+            separator,
+            objects,
+        )),
     }
 }
 
@@ -126,14 +556,38 @@ fn required_path(expression: &EngineAggregationExpr) -> Result<&str, EngineError
         })
 }
 
-fn aggregation_key(expression: &EngineAggregationExpr) -> String {
+pub fn aggregation_key(expression: &EngineAggregationExpr) -> String {
     let function = aggregation_function_name(&expression.function);
-    match expression.path.as_deref() {
-        Some(path) => format!("{function}({path})"),
-        None => format!("{function}(*)"),
+    let argument = match (expression.field_function, expression.path.as_deref()) {
+        (Some(field_function), Some(path)) => field_function_key(field_function, path),
+        (None, Some(path)) => path.to_string(),
+        (_, None) => "*".to_string(),
+    };
+    match &expression.function {
+        EngineAggregationFunction::Percentile(rank) => format!("{function}({argument}, {rank})"),
+        EngineAggregationFunction::GroupConcat(separator) if separator != "," => {
+            let separator = separator.replace('\\', "\\\\").replace('\'', "\\'");
+            format!("{function}({argument}, '{separator}')")
+        }
+        _ => format!("{function}({argument})"),
     }
 }
 
+fn field_function_key(function: EngineFieldFunction, path: &str) -> String {
+    let name = match function {
+        EngineFieldFunction::Len => "len",
+        EngineFieldFunction::StrLen => "strlen",
+        EngineFieldFunction::Age => "age",
+        EngineFieldFunction::ImageRepo => "image_repo",
+        EngineFieldFunction::ImageTag => "image_tag",
+        EngineFieldFunction::ArraySum => "sum",
+        EngineFieldFunction::ArrayMin => "min",
+        EngineFieldFunction::ArrayMax => "max",
+        EngineFieldFunction::ArrayAvg => "avg",
+    };
+    format!("{name}({path})")
+}
+
 fn aggregation_function_name(function: &EngineAggregationFunction) -> &'static str {
     match function {
         EngineAggregationFunction::Count => "count",
@@ -141,6 +595,12 @@ fn aggregation_function_name(function: &EngineAggregationFunction) -> &'static s
         EngineAggregationFunction::Min => "min",
         EngineAggregationFunction::Max => "max",
         EngineAggregationFunction::Avg => "avg",
+        EngineAggregationFunction::Range => "range",
+        EngineAggregationFunction::Median => "median",
+        EngineAggregationFunction::Stddev => "stddev",
+        EngineAggregationFunction::Percentile(_) => "percentile",
+        EngineAggregationFunction::Mode => "mode",
+        EngineAggregationFunction::GroupConcat(_) => "group_concat",
     }
 }
 
@@ -333,6 +793,188 @@ fn min_max_aggregation(
     Ok(best.cloned().unwrap_or(Value::Null))
 }
 
+/// `min-max` числовых значений за один проход; пустой набор -> `null`.
+fn range_aggregation(path: &str, objects: &[DynamicObject]) -> Result<Value, EngineError> {
+    let mut bounds: Option<(&serde_json::Number, &serde_json::Number)> = None;
+
+    for object in objects {
+        let Some(value) = object.get(path) else {
+            continue;
+        };
+        if value.is_null() {
+            continue;
+        }
+        let Some(number) = value.as_number() else {
+            return Err(non_numeric_aggregation_error("range", path, value));
+        };
+
+        bounds = Some(match bounds {
+            None => (number, number),
+            Some((min, max)) => (
+                if compare_number_values(number, min)? == Ordering::Less {
+                    number
+                } else {
+                    min
+                },
+                if compare_number_values(number, max)? == Ordering::Greater {
+                    number
+                } else {
+                    max
+                },
+            ),
+        });
+    }
+
+    Ok(bounds.map_or(Value::Null, |(min, max)| {
+        Value::String(format!("{min}-{max}"))
+    }))
+}
+
+/// Медиана числовых значений: для четного числа — среднее двух средних
+/// значений; пустой набор -> `null`.
+fn median_aggregation(path: &str, objects: &[DynamicObject]) -> Result<Value, EngineError> {
+    let mut values = numeric_values("median", path, objects)?;
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+    values.sort_by(f64::total_cmp);
+    finite_aggregation_result("median", path, interpolate_sorted(&values, 0.5))
+}
+
+/// Перцентиль с линейной интерполяцией между соседними значениями
+/// отсортированной выборки (`p = 50` совпадает с `median`).
+fn percentile_aggregation(
+    path: &str,
+    rank: &serde_json::Number,
+    objects: &[DynamicObject],
+) -> Result<Value, EngineError> {
+    let Some(rank) = rank.as_f64().filter(|rank| (0.0..=100.0).contains(rank)) else {
+        return Err(EngineError::InvalidAggregation {
+            function: "percentile".to_string(),
+            path: path.to_string(),
+            expected: "percentile between 0 and 100",
+            actual: rank.to_string(),
+        });
+    };
+    let mut values = numeric_values("percentile", path, objects)?;
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+    values.sort_by(f64::total_cmp);
+    finite_aggregation_result(
+        "percentile",
+        path,
+        interpolate_sorted(&values, rank / 100.0),
+    )
+}
+
+/// Самое частое значение; при равенстве частот берется первое в порядке
+/// сортировки `order by`. Пустой набор -> `null`.
+fn mode_aggregation(path: &str, objects: &[DynamicObject]) -> Result<Value, EngineError> {
+    let mut counts: HashMap<String, (&Value, usize)> = HashMap::new();
+    for object in objects {
+        let Some(value) = object.get(path).filter(|value| !value.is_null()) else {
+            continue;
+        };
+        if comparable_type(value).is_none() {
+            return Err(EngineError::InvalidAggregation {
+                function: "mode".to_string(),
+                path: path.to_string(),
+                expected: "bool, number, or string",
+                actual: value_type_name(value).to_string(),
+            });
+        }
+        counts.entry(value.to_string()).or_insert((value, 0)).1 += 1;
+    }
+
+    let options = SortOptions::default();
+    let mode = counts
+        .into_values()
+        .min_by(|(left, left_count), (right, right_count)| {
+            right_count.cmp(left_count).then_with(|| {
+                compare_non_null_values(left, right, EngineSortDirection::Asc, &options)
+            })
+        });
+    Ok(mode.map(|(value, _)| value.clone()).unwrap_or(Value::Null))
+}
+
+/// Не-`null` значения в порядке входа через `separator`; строки без
+/// кавычек, остальное как JSON. Пустой набор -> пустая строка.
+fn group_concat_aggregation(path: &str, separator: &str, objects: &[DynamicObject]) -> Value {
+    let parts: Vec<String> = objects
+        .iter()
+        .filter_map(|object| object.get(path))
+        .filter(|value| !value.is_null())
+        .map(|value| match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        })
+        .collect();
+    Value::String(parts.join(separator))
+}
+
+/// Значение на доле `fraction` от первого до последнего элемента
+/// непустой отсортированной выборки.
+fn interpolate_sorted(sorted: &[f64], fraction: f64) -> f64 {
+    let position = fraction * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// Стандартное отклонение генеральной совокупности (деление на `n`);
+/// пустой набор -> `null`.
+fn stddev_aggregation(path: &str, objects: &[DynamicObject]) -> Result<Value, EngineError> {
+    let values = numeric_values("stddev", path, objects)?;
+    if values.is_empty() {
+        return Ok(Value::Null);
+    }
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    finite_aggregation_result("stddev", path, variance.sqrt())
+}
+
+/// Числовые значения `path`; отсутствующие поля и `null` пропускаются.
+fn numeric_values(
+    function: &str,
+    path: &str,
+    objects: &[DynamicObject],
+) -> Result<Vec<f64>, EngineError> {
+    let mut values = Vec::new();
+    for object in objects {
+        let Some(value) = object.get(path) else {
+            continue;
+        };
+        if value.is_null() {
+            continue;
+        }
+        let Some(number) = numeric_from_json(value) else {
+            return Err(non_numeric_aggregation_error(function, path, value));
+        };
+        values.push(match number {
+            NumericValue::Int(value) => value as f64,
+            NumericValue::Float(value) => value,
+        });
+    }
+    Ok(values)
+}
+
+fn finite_aggregation_result(function: &str, path: &str, value: f64) -> Result<Value, EngineError> {
+    serde_json::Number::from_f64(value)
+        .map(Value::Number)
+        .ok_or_else(|| EngineError::InvalidAggregation {
+            function: function.to_string(),
+            path: path.to_string(),
+            expected: "finite numeric result",
+            actual: "non-finite".to_string(),
+        })
+}
+
 fn compare_same_type_values(
     left: &Value,
     right: &Value,
@@ -467,22 +1109,117 @@ fn compare_objects(
     left: &DynamicObject,
     right: &DynamicObject,
     sort_keys: &[EngineSortKey],
+    options: &SortOptions,
+    now: SystemTime,
 ) -> Ordering {
     for key in sort_keys {
-        let ordering = compare_values(left.get(&key.path), right.get(&key.path), key.direction);
+        let ordering = compare_values(
+            sort_key_value(left, key, now).as_deref(),
+            sort_key_value(right, key, now).as_deref(),
+            key.direction,
+            options,
+        );
 
         if ordering != Ordering::Equal {
             return ordering;
         }
     }
 
-    Ordering::Equal
+    match &options.tie_break {
+        TieBreak::InputOrder => Ordering::Equal,
+        TieBreak::Path(path) => compare_values(
+            left.get(path),
+            right.get(path),
+            EngineSortDirection::Asc,
+            options,
+        ),
+    }
+}
+
+fn sort_key_value<'a>(
+    object: &'a DynamicObject,
+    key: &EngineSortKey,
+    now: SystemTime,
+) -> Option<Cow<'a, Value>> {
+    let Some(function) = key.function else {
+        return object.get(&key.path).map(Cow::Borrowed);
+    };
+
+    apply_field_function(function, object, &key.path, now).map(Cow::Owned)
+}
+
+fn apply_field_function(
+    function: EngineFieldFunction,
+    object: &DynamicObject,
+    path: &str,
+    now: SystemTime,
+) -> Option<Value> {
+    match function {
+        EngineFieldFunction::Len => match crate::path::select_path_value(&object.fields, path)? {
+            Value::Array(items) => Some(Value::from(items.len())),
+            Value::Object(entries) => Some(Value::from(entries.len())),
+            _ => None,
+        },
+        EngineFieldFunction::StrLen => match object.get(path)? {
+            Value::String(text) => Some(Value::from(text.chars().count())),
+            _ => None,
+        },
+        EngineFieldFunction::Age => match object.get(path)? {
+            Value::String(timestamp) => age_seconds(timestamp, now).map(Value::from),
+            _ => None,
+        },
+        EngineFieldFunction::ImageRepo => match object.get(path)? {
+            Value::String(reference) => Some(Value::from(image::image_repo(reference))),
+            _ => None,
+        },
+        EngineFieldFunction::ImageTag => match object.get(path)? {
+            Value::String(reference) => image::image_tag(reference).map(Value::from),
+            _ => None,
+        },
+        EngineFieldFunction::ArraySum => array_aggregate(object, path, sum_aggregation),
+        EngineFieldFunction::ArrayMin => array_aggregate(object, path, |path, items| {
+            min_max_aggregation(path, items, true)
+        }),
+        EngineFieldFunction::ArrayMax => array_aggregate(object, path, |path, items| {
+            min_max_aggregation(path, items, false)
+        }),
+        EngineFieldFunction::ArrayAvg => array_aggregate(object, path, avg_aggregation),
+    }
+}
+
+/// Агрегат по числам под путем с `*` на месте индексов массивов с той же
+/// семантикой, что у глобальной агрегации; нечисловое значение -> `None`.
+fn array_aggregate(
+    object: &DynamicObject,
+    pattern: &str,
+    aggregate: impl Fn(&str, &[DynamicObject]) -> Result<Value, EngineError>,
+) -> Option<Value> {
+    let items: Vec<DynamicObject> = object
+        .values_matching_indices(pattern)
+        .filter(|value| !value.is_null())
+        .map(|value| {
+            numeric_from_json(value)?;
+            Some(DynamicObject {
+                fields: BTreeMap::from([(pattern.to_string(), value.clone())]),
+            })
+        })
+        .collect::<Option<_>>()?;
+    aggregate(pattern, &items)
+        .ok()
+        .filter(|value| !value.is_null())
+}
+
+fn age_seconds(timestamp: &str, now: SystemTime) -> Option<i64> {
+    let created = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?;
+    let now = chrono::DateTime::<chrono::Utc>::from(now);
+    Some(now.signed_duration_since(created).num_seconds())
 }
 
 fn compare_values(
     left: Option<&Value>,
     right: Option<&Value>,
     direction: EngineSortDirection,
+    options: &SortOptions,
 ) -> Ordering {
     match (to_sort_value(left), to_sort_value(right)) {
         (SortValue::Nullish, SortValue::Nullish) => Ordering::Equal,
@@ -495,7 +1232,7 @@ fn compare_values(
             EngineSortDirection::Desc => Ordering::Less,
         },
         (SortValue::Concrete(left), SortValue::Concrete(right)) => {
-            compare_non_null_values(left, right, direction)
+            compare_non_null_values(left, right, direction, options)
         }
     }
 }
@@ -504,13 +1241,14 @@ fn compare_non_null_values(
     left: &Value,
     right: &Value,
     direction: EngineSortDirection,
+    options: &SortOptions,
 ) -> Ordering {
     let left_rank = value_rank(left);
     let right_rank = value_rank(right);
 
     let mut ordering = left_rank.cmp(&right_rank);
     if ordering == Ordering::Equal {
-        ordering = compare_same_rank(left, right);
+        ordering = compare_same_rank(left, right, options);
     }
 
     match direction {
@@ -519,14 +1257,19 @@ fn compare_non_null_values(
     }
 }
 
-fn compare_same_rank(
-    left: &Value,
-    right: &Value,
-) -> Ordering {
+fn compare_same_rank(left: &Value, right: &Value, options: &SortOptions) -> Ordering {
     match (left, right) {
         (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
         (Value::Number(left), Value::Number(right)) => compare_numbers(left, right),
-        (Value::String(left), Value::String(right)) => left.cmp(right),
+        (Value::String(left), Value::String(right)) => {
+            quantity::compare_strings_by_quantity(left, right, |left, right| {
+                if options.ignore_case {
+                    left.to_lowercase().cmp(&right.to_lowercase())
+                } else {
+                    left.cmp(right)
+                }
+            })
+        }
         _ => Ordering::Equal,
     }
 }
@@ -570,32 +1313,238 @@ fn to_sort_value(value: Option<&Value>) -> SortValue<'_> {
     }
 }
 
-fn matches_all(
-    object: &DynamicObject,
-    predicates: &[EnginePredicate],
-) -> bool {
-    predicates.iter().all(|predicate| {
-        let value = object
-            .get(&predicate.path)
-            .and_then(|value| comparable_eq(value, &predicate.value));
-
-        match predicate.op {
-            EngineOperator::Eq => value == Some(true),
-            EngineOperator::Ne => value == Some(false),
-        }
-    })
-}
-
-fn comparable_eq(
-    actual: &Value,
-    expected: &Value,
-) -> Option<bool> {
-    match (actual, expected) {
-        (Value::String(left), Value::String(right)) => Some(left == right),
-        (Value::Number(left), Value::Number(right)) => Some(left == right),
-        (Value::Bool(left), Value::Bool(right)) => Some(left == right),
-        (Value::Null, _) | (_, Value::Null) => None,
-        _ => None,
+fn matches_expr(object: &DynamicObject, expr: &EngineExpr, now: SystemTime) -> bool {
+    match expr {
+        EngineExpr::And(terms) => terms.iter().all(|term| matches_expr(object, term, now)),
+        EngineExpr::Or(terms) => terms.iter().any(|term| matches_expr(object, term, now)),
+        EngineExpr::Not(inner) => !matches_expr(object, inner, now),
+        EngineExpr::Leaf(predicate) => matches_predicate(object, predicate, now),
+    }
+}
+
+fn matches_predicate(object: &DynamicObject, predicate: &EnginePredicate, now: SystemTime) -> bool {
+    if predicate.op == EngineOperator::HasKey {
+        let pattern = predicate.value.as_str().unwrap_or_default();
+        return object
+            .child_keys(&predicate.path)
+            .any(|key| glob_matches(pattern, &key));
+    }
+    if matches!(
+        predicate.op,
+        EngineOperator::IsNull | EngineOperator::IsNotNull
+    ) {
+        return is_present(object, predicate, now) == (predicate.op == EngineOperator::IsNotNull);
+    }
+    if predicate.function.is_none()
+        && let Some(prefix) = predicate.path.strip_suffix('*')
+    {
+        return matches_wildcard(object, prefix, predicate, now);
+    }
+    if predicate.op == EngineOperator::Exists {
+        return object.has_path(&predicate.path);
+    }
+
+    let actual = match predicate.function {
+        Some(function) => {
+            apply_field_function(function, object, &predicate.path, now).map(Cow::Owned)
+        }
+        None => object.get(&predicate.path).map(Cow::Borrowed),
+    };
+    if predicate.value_function == Some(EngineValueFunction::Now) {
+        return actual.is_some_and(|actual| compare_with_instant(&predicate.op, &actual, now));
+    }
+    let expected = match predicate.value_path.as_deref() {
+        Some(path) => object.get(path),
+        None => Some(&predicate.value),
+    };
+    actual
+        .zip(expected)
+        .is_some_and(|(actual, expected)| compare_matches(&predicate.op, &actual, expected))
+}
+
+/// Есть ли non-null значение: для вложенного объекта достаточно любого
+/// поля под ним, как в `exists`.
+fn is_present(object: &DynamicObject, predicate: &EnginePredicate, now: SystemTime) -> bool {
+    if let Some(function) = predicate.function {
+        return apply_field_function(function, object, &predicate.path, now)
+            .is_some_and(|value| !value.is_null());
+    }
+    match predicate.path.strip_suffix('*') {
+        Some(prefix) => object
+            .values_with_path_prefix(prefix)
+            .any(|value| !value.is_null()),
+        None => object.has_path(&predicate.path),
+    }
+}
+
+/// Предикат по пути с `*` выполняется, если ему удовлетворяет хотя бы одно
+/// поле под префиксом.
+fn matches_wildcard(
+    object: &DynamicObject,
+    prefix: &str,
+    predicate: &EnginePredicate,
+    now: SystemTime,
+) -> bool {
+    let mut values = object
+        .values_with_path_prefix(prefix)
+        .filter(|value| !value.is_null());
+    if predicate.op == EngineOperator::Exists {
+        return values.next().is_some();
+    }
+    if predicate.value_function == Some(EngineValueFunction::Now) {
+        return values.any(|actual| compare_with_instant(&predicate.op, actual, now));
+    }
+
+    let expected = match predicate.value_path.as_deref() {
+        Some(path) => object.get(path),
+        None => Some(&predicate.value),
+    };
+    let Some(expected) = expected else {
+        return false;
+    };
+    values.any(|actual| compare_matches(&predicate.op, actual, expected))
+}
+
+/// `now()` справа: RFC3339-строка сравнивается с моментом вычисления
+/// хронологически, остальные значения не матчат.
+fn compare_with_instant(op: &EngineOperator, actual: &Value, now: SystemTime) -> bool {
+    let Some(timestamp) = actual
+        .as_str()
+        .and_then(|text| chrono::DateTime::parse_from_rfc3339(text).ok())
+    else {
+        return false;
+    };
+    let ordering = timestamp
+        .with_timezone(&chrono::Utc)
+        .cmp(&chrono::DateTime::<chrono::Utc>::from(now));
+    match op {
+        EngineOperator::Eq => ordering.is_eq(),
+        EngineOperator::Ne => ordering.is_ne(),
+        EngineOperator::Gt => ordering.is_gt(),
+        EngineOperator::Ge => ordering.is_ge(),
+        EngineOperator::Lt => ordering.is_lt(),
+        EngineOperator::Le => ordering.is_le(),
+        _ => false,
+    }
+}
+
+/// Сравнение по оператору; несравнимые значения (null, разные типы)
+/// не матчат ни один оператор.
+fn compare_matches(op: &EngineOperator, actual: &Value, expected: &Value) -> bool {
+    match op {
+        EngineOperator::Eq => comparable_eq(actual, expected) == Some(true),
+        EngineOperator::Ne => comparable_eq(actual, expected) == Some(false),
+        EngineOperator::Gt => comparable_cmp(actual, expected) == Some(Ordering::Greater),
+        EngineOperator::Ge => comparable_cmp(actual, expected).is_some_and(Ordering::is_ge),
+        EngineOperator::Lt => comparable_cmp(actual, expected) == Some(Ordering::Less),
+        EngineOperator::Le => comparable_cmp(actual, expected).is_some_and(Ordering::is_le),
+        EngineOperator::In { values } => values
+            .iter()
+            .any(|member| comparable_eq(actual, member) == Some(true)),
+        EngineOperator::NotIn { values } => values
+            .iter()
+            .all(|member| comparable_eq(actual, member) == Some(false)),
+        EngineOperator::Like(pattern) => actual
+            .as_str()
+            .is_some_and(|text| glob_matches(pattern, text)),
+        EngineOperator::Regex(regex) => actual.as_str().is_some_and(|text| regex.0.is_match(text)),
+        EngineOperator::NotRegex(regex) => {
+            actual.as_str().is_some_and(|text| !regex.0.is_match(text))
+        }
+        EngineOperator::IsNull
+        | EngineOperator::IsNotNull
+        | EngineOperator::Exists
+        | EngineOperator::HasKey => {
+            unreachable!("presence operators are evaluated before value comparison")
+        }
+    }
+}
+
+/// Числа сравниваются по величине, строки — лексикографически. Строка
+/// против числового литерала (значение label) приводится к числу.
+fn comparable_cmp(actual: &Value, expected: &Value) -> Option<Ordering> {
+    match (actual, expected) {
+        (Value::Number(left), Value::Number(right)) => Some(compare_numbers(left, right)),
+        (Value::String(left), Value::String(right)) => {
+            Some(quantity::compare_quantity_strings(left, right).unwrap_or_else(|| left.cmp(right)))
+        }
+        (Value::String(left), Value::Number(right)) => left
+            .parse::<serde_json::Number>()
+            .ok()
+            .map(|left| compare_numbers(&left, right))
+            .or_else(|| quantity::compare_quantity_strings(left, &right.to_string())),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GlobToken {
+    Any,
+    One,
+    Literal(char),
+}
+
+/// `\` экранирует следующий символ: `\*` — буквальная `*`.
+fn glob_tokens(pattern: &str) -> Vec<GlobToken> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        tokens.push(match ch {
+            '*' => GlobToken::Any,
+            '?' => GlobToken::One,
+            '\\' => GlobToken::Literal(chars.next().unwrap_or('\\')),
+            other => GlobToken::Literal(other),
+        });
+    }
+    tokens
+}
+
+/// Glob с `*` (любая подстрока) и `?` (один символ), якорный с обеих
+/// сторон; пустой pattern матчит только пустую строку.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = glob_tokens(pattern);
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(GlobToken::Any) => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            }
+            Some(GlobToken::One) => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            Some(GlobToken::Literal(ch)) if *ch == text[t] => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let Some((star, matched)) = backtrack else {
+            return false;
+        };
+        p = star + 1;
+        t = matched + 1;
+        backtrack = Some((star, matched + 1));
+    }
+    pattern[p..].iter().all(|token| *token == GlobToken::Any)
+}
+
+fn comparable_eq(
+    actual: &Value,
+    expected: &Value,
+) -> Option<bool> {
+    match (actual, expected) {
+        (Value::String(left), Value::String(right)) => Some(left == right),
+        (Value::Number(left), Value::Number(right)) => Some(left == right),
+        (Value::Bool(left), Value::Bool(right)) => Some(left == right),
+        (Value::Null, _) | (_, Value::Null) => None,
+        _ => None,
     }
 }
 
@@ -607,9 +1556,12 @@ mod tests {
     use crate::dynamic_object::DynamicObject;
 
     use super::{
-        EngineAggregationExpr, EngineAggregationFunction, EngineOperator, EnginePredicate,
-        EngineSelection, EngineSortDirection, EngineSortKey, QueryPlan, aggregate, evaluate,
-        sort_objects,
+        EngineAggregationExpr, EngineAggregationFunction, EngineComputedColumn, EngineExpr,
+        EngineFieldFunction, EngineLimit, EngineOperator, EnginePredicate, EngineSelection,
+        EngineSortDirection, EngineSortKey, EngineValueFunction, PredicateRegex, QueryPlan,
+        SortOptions, TieBreak, age_seconds, aggregate, aggregation_key, compute_columns,
+        count_rows, distinct_rows, evaluate, evaluate_at, evaluate_iter, filter_groups,
+        find_contradiction, is_count_only, predicate_match_counts, sort_objects,
     };
 
     #[test]
@@ -627,135 +1579,1685 @@ mod tests {
         );
 
         let plan = QueryPlan {
-            predicates: vec![EnginePredicate {
+            filter: EngineExpr::all(vec![EnginePredicate {
                 path: "metadata.namespace".to_string(),
+                function: None,
                 op: EngineOperator::Eq,
                 value: Value::String("default".to_string()),
-            }],
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let result = evaluate(
+            &plan,
+            &[
+                DynamicObject { fields: fields_ok },
+                DynamicObject { fields: fields_bad },
+            ],
+        );
+
+        assert_eq!(result.len(), 1);
+    }
+
+    #[test]
+    fn filters_by_numeric_aggregate_over_nested_array_wildcards() {
+        let pod = |name: &str, containers: Value| DynamicObject {
+            fields: crate::path::flatten_json_to_fields(&serde_json::json!({
+                "metadata": { "name": name },
+                "spec": { "containers": containers },
+            })),
+        };
+        let objects = [
+            pod(
+                "web",
+                serde_json::json!([
+                    { "ports": [{ "containerPort": 80 }, { "containerPort": 443 }] },
+                    { "ports": [{ "containerPort": 9090 }] },
+                ]),
+            ),
+            pod("worker", serde_json::json!([{ "name": "worker" }])),
+            pod(
+                "odd",
+                serde_json::json!([{ "ports": [{ "containerPort": "http" }] }]),
+            ),
+        ];
+        let matching = |function, op, value: Value| {
+            let plan = QueryPlan {
+                filter: EngineExpr::all(vec![EnginePredicate {
+                    path: "spec.containers.*.ports.*.containerPort".to_string(),
+                    function: Some(function),
+                    op,
+                    value,
+                    value_path: None,
+                    value_function: None,
+                }]),
+                selection: None,
+                sort_keys: None,
+                limit: None,
+                computed: Vec::new(),
+                group_by: None,
+                having: None,
+                distinct: false,
+            };
+            evaluate(&plan, &objects)
+                .iter()
+                .filter_map(|object| object.get("metadata.name").cloned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matching(
+                EngineFieldFunction::ArraySum,
+                EngineOperator::Eq,
+                Value::from(9613)
+            ),
+            vec![Value::from("web")]
+        );
+        assert_eq!(
+            matching(
+                EngineFieldFunction::ArraySum,
+                EngineOperator::Ge,
+                Value::from(0)
+            ),
+            vec![Value::from("web"), Value::from("worker")]
+        );
+        assert_eq!(
+            matching(
+                EngineFieldFunction::ArrayMin,
+                EngineOperator::Eq,
+                Value::from(80)
+            ),
+            vec![Value::from("web")]
+        );
+        assert_eq!(
+            matching(
+                EngineFieldFunction::ArrayAvg,
+                EngineOperator::Gt,
+                Value::from(3000)
+            ),
+            vec![Value::from("web")]
+        );
+        assert_eq!(
+            matching(
+                EngineFieldFunction::ArrayMax,
+                EngineOperator::Ge,
+                Value::from(0)
+            ),
+            vec![Value::from("web")]
+        );
+    }
+
+    #[test]
+    fn filters_by_image_repo_and_implicit_tag() {
+        let image_object = |image: &str| {
+            let mut fields = BTreeMap::new();
+            fields.insert(
+                "spec.containers.0.image".to_string(),
+                Value::String(image.to_string()),
+            );
+            DynamicObject { fields }
+        };
+        let objects = [
+            image_object("docker.io/library/nginx:1.25"),
+            image_object("nginx"),
+            image_object("ghcr.io/org/api:v3"),
+        ];
+        let plan = |function, value: &str| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.containers.0.image".to_string(),
+                function: Some(function),
+                op: EngineOperator::Eq,
+                value: Value::String(value.to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let result = evaluate(&plan(EngineFieldFunction::ImageRepo, "nginx"), &objects);
+        assert_eq!(result, vec![objects[0].clone(), objects[1].clone()]);
+
+        let result = evaluate(&plan(EngineFieldFunction::ImageTag, "latest"), &objects);
+        assert_eq!(result, vec![objects[1].clone()]);
+    }
+
+    #[test]
+    fn compares_two_fields_of_the_same_object() {
+        let object = |generation: u64, observed: u64, desired: &str, current: &str| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.generation".to_string(), Value::from(generation));
+            fields.insert(
+                "status.observedGeneration".to_string(),
+                Value::from(observed),
+            );
+            fields.insert("spec.image".to_string(), Value::String(desired.to_string()));
+            fields.insert(
+                "status.image".to_string(),
+                Value::String(current.to_string()),
+            );
+            DynamicObject { fields }
+        };
+        let objects = [
+            object(3, 3, "api:v2", "api:v2"),
+            object(4, 3, "api:v3", "api:v2"),
+        ];
+        let matching = |path: &str, op: EngineOperator, value_path: &str| {
+            let plan = QueryPlan {
+                filter: EngineExpr::all(vec![EnginePredicate {
+                    path: path.to_string(),
+                    function: None,
+                    op,
+                    value: Value::Null,
+                    value_path: Some(value_path.to_string()),
+                    value_function: None,
+                }]),
+                selection: None,
+                sort_keys: None,
+                limit: None,
+                computed: Vec::new(),
+                group_by: None,
+                having: None,
+                distinct: false,
+            };
+            evaluate(&plan, &objects)
+        };
+
+        assert_eq!(
+            matching(
+                "metadata.generation",
+                EngineOperator::Ne,
+                "status.observedGeneration"
+            ),
+            vec![objects[1].clone()]
+        );
+        assert_eq!(
+            matching("spec.image", EngineOperator::Eq, "status.image"),
+            vec![objects[0].clone()]
+        );
+        assert!(matching("spec.image", EngineOperator::Eq, "metadata.generation").is_empty());
+        assert!(matching("spec.image", EngineOperator::Ne, "status.missing").is_empty());
+    }
+
+    #[test]
+    fn missing_field_does_not_match_eq_or_ne() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.namespace".to_string(),
+            Value::String("default".to_string()),
+        );
+
+        let object = DynamicObject { fields };
+
+        let eq_plan = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.nodeName".to_string(),
+                function: None,
+                op: EngineOperator::Eq,
+                value: Value::String("worker-1".to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let ne_plan = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.nodeName".to_string(),
+                function: None,
+                op: EngineOperator::Ne,
+                value: Value::String("worker-1".to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert!(evaluate(&eq_plan, std::slice::from_ref(&object)).is_empty());
+        assert!(evaluate(&ne_plan, &[object]).is_empty());
+    }
+
+    #[test]
+    fn type_mismatch_does_not_match_eq_or_ne() {
+        let mut fields = BTreeMap::new();
+        fields.insert("spec.replicas".to_string(), Value::from(2));
+        let object = DynamicObject { fields };
+
+        let eq_plan = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.replicas".to_string(),
+                function: None,
+                op: EngineOperator::Eq,
+                value: Value::String("2".to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let ne_plan = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.replicas".to_string(),
+                function: None,
+                op: EngineOperator::Ne,
+                value: Value::String("2".to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert!(evaluate(&eq_plan, std::slice::from_ref(&object)).is_empty());
+        assert!(evaluate(&ne_plan, &[object]).is_empty());
+    }
+
+    #[test]
+    fn exists_matches_array_field_present_only_as_indexed_subkeys() {
+        let objects = vec![
+            object(&[
+                (
+                    "metadata.name",
+                    Value::String("with-conditions".to_string()),
+                ),
+                (
+                    "status.conditions.0.type",
+                    Value::String("Ready".to_string()),
+                ),
+                (
+                    "status.conditions.1.type",
+                    Value::String("Scheduled".to_string()),
+                ),
+            ]),
+            object(&[
+                (
+                    "metadata.name",
+                    Value::String("without-conditions".to_string()),
+                ),
+                ("status.phase", Value::String("Pending".to_string())),
+            ]),
+        ];
+        let plan = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "status.conditions".to_string(),
+                function: None,
+                op: EngineOperator::Exists,
+                value: Value::Null,
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert_eq!(names(&evaluate(&plan, &objects)), vec!["with-conditions"]);
+    }
+
+    #[test]
+    fn range_operators_compare_numbers_and_strings() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("small".to_string())),
+                ("spec.replicas", Value::from(2)),
+                ("metadata.labels.tier", Value::String("2".to_string())),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("large".to_string())),
+                ("spec.replicas", Value::from(u64::MAX)),
+                ("metadata.labels.tier", Value::String("10".to_string())),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("empty".to_string())),
+                ("spec.replicas", Value::Null),
+            ]),
+        ];
+        let plan = |path: &str, op: EngineOperator, value: Value| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: path.to_string(),
+                function: None,
+                op,
+                value,
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let matching = |path: &str, op: EngineOperator, value: Value| {
+            names(&evaluate(&plan(path, op, value), &objects))
+        };
+
+        assert_eq!(
+            matching("spec.replicas", EngineOperator::Gt, Value::from(2)),
+            vec!["large"]
+        );
+        assert_eq!(
+            matching("spec.replicas", EngineOperator::Ge, Value::from(2)),
+            vec!["small", "large"]
+        );
+        assert_eq!(
+            matching("spec.replicas", EngineOperator::Lt, Value::from(u64::MAX)),
+            vec!["small"]
+        );
+        assert_eq!(
+            matching(
+                "spec.replicas",
+                EngineOperator::Le,
+                Value::from(u64::MAX - 1)
+            ),
+            vec!["small"]
+        );
+        assert_eq!(
+            matching(
+                "metadata.name",
+                EngineOperator::Lt,
+                Value::String("m".to_string())
+            ),
+            vec!["large", "empty"]
+        );
+        assert_eq!(
+            matching("metadata.labels.tier", EngineOperator::Gt, Value::from(5)),
+            vec!["large"]
+        );
+        assert_eq!(
+            matching(
+                "metadata.labels.tier",
+                EngineOperator::Gt,
+                Value::String("5".to_string())
+            ),
+            vec!["large"]
+        );
+    }
+
+    #[test]
+    fn compares_string_label_values_numerically_client_side() {
+        let objects: Vec<_> = [
+            ("one", "1"),
+            ("three", "3"),
+            ("twelve", "12"),
+            ("text", "many"),
+        ]
+        .into_iter()
+        .map(|(name, replicas)| {
+            object(&[
+                ("metadata.name", Value::String(name.to_string())),
+                (
+                    "metadata.labels.replicas",
+                    Value::String(replicas.to_string()),
+                ),
+            ])
+        })
+        .collect();
+        let plan = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "metadata.labels.replicas".to_string(),
+                function: None,
+                op: EngineOperator::Gt,
+                value: Value::from(2),
+                value_path: None,
+                value_function: None,
+            }]),
             selection: None,
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert_eq!(names(&evaluate(&plan, &objects)), vec!["three", "twelve"]);
+    }
+
+    #[test]
+    fn range_operators_agree_with_order_by_on_quantity_strings() {
+        let objects: Vec<_> = ["10", "2500m", "2", "1Ki"]
+            .into_iter()
+            .map(|value| {
+                object(&[
+                    ("metadata.name", Value::String(value.to_string())),
+                    ("spec.cpu", Value::String(value.to_string())),
+                ])
+            })
+            .collect();
+        let plan = |op: EngineOperator| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.cpu".to_string(),
+                function: None,
+                op,
+                value: Value::String("2500m".to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: Some(vec![EngineSortKey {
+                path: "spec.cpu".to_string(),
+                function: None,
+                direction: EngineSortDirection::Asc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let sorted = |op: EngineOperator| {
+            let plan = plan(op);
+            names(&sort_objects(
+                &plan,
+                &evaluate(&plan, &objects),
+                &SortOptions::default(),
+            ))
+        };
+
+        assert_eq!(sorted(EngineOperator::Lt), vec!["2"]);
+        assert_eq!(sorted(EngineOperator::Ge), vec!["2500m", "10", "1Ki"]);
+        assert_eq!(sorted(EngineOperator::Gt), vec!["10", "1Ki"]);
+    }
+
+    #[test]
+    fn set_membership_matches_any_member_and_skips_missing_fields() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("a".to_string())),
+                ("metadata.namespace", Value::String("demo-a".to_string())),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("b".to_string())),
+                ("metadata.namespace", Value::String("demo-b".to_string())),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("c".to_string())),
+                (
+                    "metadata.namespace",
+                    Value::String("kube-system".to_string()),
+                ),
+            ]),
+            object(&[("metadata.name", Value::String("cluster".to_string()))]),
+        ];
+        let members = vec![
+            Value::String("demo-a".to_string()),
+            Value::String("demo-b".to_string()),
+        ];
+        let plan = |op: EngineOperator| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "metadata.namespace".to_string(),
+                function: None,
+                op,
+                value: Value::Null,
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let in_plan = plan(EngineOperator::In {
+            values: members.clone(),
+        });
+        assert_eq!(names(&evaluate(&in_plan, &objects)), vec!["a", "b"]);
+        let not_in_plan = plan(EngineOperator::NotIn { values: members });
+        assert_eq!(names(&evaluate(&not_in_plan, &objects)), vec!["c"]);
+    }
+
+    #[test]
+    fn like_matches_string_fields_by_anchored_glob() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("api-1".to_string())),
+                ("spec.note", Value::String("50*".to_string())),
+                ("spec.replicas", Value::from(1)),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("web-api".to_string())),
+                ("spec.note", Value::String("500".to_string())),
+                ("spec.empty", Value::String(String::new())),
+            ]),
+        ];
+        let matching = |path: &str, pattern: &str| {
+            let plan = QueryPlan {
+                filter: EngineExpr::all(vec![EnginePredicate {
+                    path: path.to_string(),
+                    function: None,
+                    op: EngineOperator::Like(pattern.to_string()),
+                    value: Value::Null,
+                    value_path: None,
+                    value_function: None,
+                }]),
+                selection: None,
+                sort_keys: None,
+                limit: None,
+                computed: Vec::new(),
+                group_by: None,
+                having: None,
+                distinct: false,
+            };
+            names(&evaluate(&plan, &objects))
+        };
+
+        assert_eq!(matching("metadata.name", "api-*"), vec!["api-1"]);
+        assert_eq!(matching("metadata.name", "*api*"), vec!["api-1", "web-api"]);
+        assert_eq!(matching("metadata.name", "api-?"), vec!["api-1"]);
+        assert_eq!(matching("spec.note", "50\\*"), vec!["api-1"]);
+        assert_eq!(matching("spec.note", "50*"), vec!["api-1", "web-api"]);
+        assert_eq!(matching("spec.replicas", "*"), Vec::<String>::new());
+        assert_eq!(matching("spec.empty", ""), vec!["web-api"]);
+        assert_eq!(matching("metadata.name", ""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn regex_matches_only_string_fields() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("api-12".to_string())),
+                ("spec.replicas", Value::from(12)),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("api-x".to_string())),
+                ("spec.replicas", Value::Bool(true)),
+            ]),
+        ];
+        let matching = |path: &str, op: fn(PredicateRegex) -> EngineOperator, pattern: &str| {
+            let plan = QueryPlan {
+                filter: EngineExpr::all(vec![EnginePredicate {
+                    path: path.to_string(),
+                    function: None,
+                    op: op(PredicateRegex(
+                        regex::Regex::new(pattern).expect("valid regex"),
+                    )),
+                    value: Value::String(pattern.to_string()),
+                    value_path: None,
+                    value_function: None,
+                }]),
+                selection: None,
+                sort_keys: None,
+                limit: None,
+                computed: Vec::new(),
+                group_by: None,
+                having: None,
+                distinct: false,
+            };
+            names(&evaluate(&plan, &objects))
+        };
+
+        let pattern = "^api-[0-9]+$";
+        assert_eq!(
+            matching("metadata.name", EngineOperator::Regex, pattern),
+            vec!["api-12"]
+        );
+        assert_eq!(
+            matching("metadata.name", EngineOperator::NotRegex, pattern),
+            vec!["api-x"]
+        );
+        assert!(matching("spec.replicas", EngineOperator::Regex, ".*").is_empty());
+        assert!(matching("spec.replicas", EngineOperator::NotRegex, "^$").is_empty());
+    }
+
+    #[test]
+    fn is_null_matches_missing_fields_unlike_eq_and_ne() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("scheduled".to_string())),
+                ("spec.nodeName", Value::String("worker-1".to_string())),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("null".to_string())),
+                ("spec.nodeName", Value::Null),
+            ]),
+            object(&[("metadata.name", Value::String("missing".to_string()))]),
+            object(&[
+                ("metadata.name", Value::String("nested".to_string())),
+                ("spec.nodeName.zone", Value::String("a".to_string())),
+            ]),
+        ];
+        let matching = |op: EngineOperator, value: Value| {
+            let plan = QueryPlan {
+                filter: EngineExpr::all(vec![EnginePredicate {
+                    path: "spec.nodeName".to_string(),
+                    function: None,
+                    op,
+                    value,
+                    value_path: None,
+                    value_function: None,
+                }]),
+                selection: None,
+                sort_keys: None,
+                limit: None,
+                computed: Vec::new(),
+                group_by: None,
+                having: None,
+                distinct: false,
+            };
+            names(&evaluate(&plan, &objects))
+        };
+
+        assert_eq!(
+            matching(EngineOperator::IsNull, Value::Null),
+            vec!["null", "missing"]
+        );
+        assert_eq!(
+            matching(EngineOperator::IsNotNull, Value::Null),
+            vec!["scheduled", "nested"]
+        );
+        let other = Value::String("worker-2".to_string());
+        assert_eq!(
+            matching(EngineOperator::Ne, other.clone()),
+            vec!["scheduled"]
+        );
+        assert!(matching(EngineOperator::Eq, other).is_empty());
+    }
+
+    #[test]
+    fn or_and_not_expressions_evaluate_recursively() {
+        let pod = |name: &str, phase: &str, node: &str| {
+            object(&[
+                ("metadata.name", Value::String(name.to_string())),
+                ("status.phase", Value::String(phase.to_string())),
+                ("spec.nodeName", Value::String(node.to_string())),
+            ])
+        };
+        let objects = vec![
+            pod("api", "Running", "a"),
+            pod("db", "Pending", "a"),
+            pod("web", "Failed", "b"),
+        ];
+        let leaf = |path: &str, value: &str| {
+            EngineExpr::Leaf(EnginePredicate {
+                path: path.to_string(),
+                function: None,
+                op: EngineOperator::Eq,
+                value: Value::String(value.to_string()),
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let plan = |filter| QueryPlan {
+            filter: Some(filter),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let either_phase = EngineExpr::Or(vec![
+            leaf("status.phase", "Running"),
+            leaf("status.phase", "Failed"),
+        ]);
+        assert_eq!(
+            names(&evaluate(&plan(either_phase.clone()), &objects)),
+            vec!["api", "web"]
+        );
+        let on_node = EngineExpr::And(vec![either_phase, leaf("spec.nodeName", "a")]);
+        assert_eq!(names(&evaluate(&plan(on_node), &objects)), vec!["api"]);
+        let negated = EngineExpr::Not(Box::new(leaf("spec.nodeName", "a")));
+        assert_eq!(names(&evaluate(&plan(negated), &objects)), vec!["web"]);
+
+        let branches = plan(EngineExpr::Or(vec![
+            leaf("metadata.name", "api"),
+            leaf("metadata.name", "db"),
+        ]));
+        assert_eq!(find_contradiction(&branches), None);
+        assert_eq!(names(&evaluate(&branches, &objects)), vec!["api", "db"]);
+        assert_eq!(predicate_match_counts(&branches, &objects), vec![1, 1]);
+    }
+
+    #[test]
+    fn not_negates_whole_group_by_de_morgan() {
+        let objects: Vec<DynamicObject> = [("both", 1, 2), ("only-a", 1, 0), ("none", 0, 0)]
+            .into_iter()
+            .map(|(name, a, b)| {
+                object(&[
+                    ("metadata.name", Value::String(name.to_string())),
+                    ("a", Value::from(a)),
+                    ("b", Value::from(b)),
+                ])
+            })
+            .collect();
+        let leaf = |path: &str, value: i64| {
+            EngineExpr::Leaf(EnginePredicate {
+                path: path.to_string(),
+                function: None,
+                op: EngineOperator::Eq,
+                value: Value::from(value),
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let plan = |filter| QueryPlan {
+            filter: Some(filter),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let group = EngineExpr::Not(Box::new(EngineExpr::And(vec![leaf("a", 1), leaf("b", 2)])));
+        assert_eq!(
+            names(&evaluate(&plan(group), &objects)),
+            vec!["only-a", "none"]
+        );
+        let separate = EngineExpr::And(vec![
+            EngineExpr::Not(Box::new(leaf("a", 1))),
+            EngineExpr::Not(Box::new(leaf("b", 2))),
+        ]);
+        assert_eq!(names(&evaluate(&plan(separate), &objects)), vec!["none"]);
+    }
+
+    #[test]
+    fn haskey_matches_label_keys_by_glob() {
+        let labeled = |name: &str, labels: Value| DynamicObject {
+            fields: crate::path::flatten_json_to_fields(&serde_json::json!({
+                "metadata": {"name": name, "labels": labels},
+            })),
+        };
+        let objects = vec![
+            labeled(
+                "team",
+                serde_json::json!({"team-payments": "yes", "app": "api"}),
+            ),
+            labeled(
+                "dotted",
+                serde_json::json!({"team.example.com/owner": "ops"}),
+            ),
+            labeled("other", serde_json::json!({"app": "web", "teams": "x"})),
+        ];
+        let plan = |pattern: &str| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "metadata.labels".to_string(),
+                function: None,
+                op: EngineOperator::HasKey,
+                value: Value::String(pattern.to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert_eq!(names(&evaluate(&plan("team-*"), &objects)), vec!["team"]);
+        assert_eq!(
+            names(&evaluate(&plan("team.*/owner"), &objects)),
+            vec!["dotted"]
+        );
+        assert_eq!(names(&evaluate(&plan("tea?s"), &objects)), vec!["other"]);
+        assert!(evaluate(&plan("owner*"), &objects).is_empty());
+    }
+
+    #[test]
+    fn wildcard_path_matches_any_encoded_annotation_under_prefix() {
+        let annotated = |name: &str, component: &str| DynamicObject {
+            fields: crate::path::flatten_json_to_fields(&serde_json::json!({
+                "metadata": {
+                    "name": name,
+                    "annotations": {
+                        "app.kubernetes.io/name": "web",
+                        "app.kubernetes.io/component": component,
+                        "example.com/owner": "api",
+                    },
+                },
+            })),
+        };
+        let objects = vec![annotated("match", "api"), annotated("other", "worker")];
+        let plan = |op: EngineOperator, value: Value| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "metadata.annotations.app.kubernetes.io/*".to_string(),
+                function: None,
+                op,
+                value,
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let eq_plan = plan(EngineOperator::Eq, Value::String("api".to_string()));
+        assert_eq!(names(&evaluate(&eq_plan, &objects)), vec!["match"]);
+        let exists_plan = plan(EngineOperator::Exists, Value::Null);
+        assert_eq!(
+            names(&evaluate(&exists_plan, &objects)),
+            vec!["match", "other"]
+        );
+        let missing_plan = plan(EngineOperator::Eq, Value::String("db".to_string()));
+        assert!(evaluate(&missing_plan, &objects).is_empty());
+
+        let web_plan = plan(EngineOperator::Eq, Value::String("web".to_string()));
+        let both = QueryPlan {
+            filter: EngineExpr::all(
+                [eq_plan.predicates(), web_plan.predicates()]
+                    .concat()
+                    .into_iter()
+                    .cloned()
+                    .collect(),
+            ),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        assert_eq!(find_contradiction(&both), None);
+        assert_eq!(names(&evaluate(&both, &objects)), vec!["match"]);
+    }
+
+    #[test]
+    fn exists_matches_exact_scalar_key_but_not_null() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("scalar".to_string())),
+                ("spec.nodeName", Value::String("worker-1".to_string())),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("null".to_string())),
+                ("spec.nodeName", Value::Null),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("prefix-only".to_string())),
+                (
+                    "spec.nodeNameOverride",
+                    Value::String("worker-2".to_string()),
+                ),
+            ]),
+        ];
+        let plan = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.nodeName".to_string(),
+                function: None,
+                op: EngineOperator::Exists,
+                value: Value::Null,
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert_eq!(names(&evaluate(&plan, &objects)), vec!["scalar"]);
+    }
+
+    #[test]
+    fn counts_matches_per_predicate_independently() {
+        let objects = vec![
+            object(&[
+                ("metadata.namespace", Value::String("demo-a".to_string())),
+                ("spec.nodeName", Value::String("worker-1".to_string())),
+            ]),
+            object(&[
+                ("metadata.namespace", Value::String("demo-a".to_string())),
+                ("spec.nodeName", Value::String("worker-2".to_string())),
+            ]),
+            object(&[
+                ("metadata.namespace", Value::String("demo-b".to_string())),
+                ("spec.nodeName", Value::String("worker-1".to_string())),
+            ]),
+            object(&[("metadata.namespace", Value::String("demo-a".to_string()))]),
+        ];
+
+        let plan = QueryPlan {
+            filter: EngineExpr::all(vec![
+                EnginePredicate {
+                    path: "metadata.namespace".to_string(),
+                    function: None,
+                    op: EngineOperator::Eq,
+                    value: Value::String("demo-a".to_string()),
+                    value_path: None,
+                    value_function: None,
+                },
+                EnginePredicate {
+                    path: "spec.nodeName".to_string(),
+                    function: None,
+                    op: EngineOperator::Eq,
+                    value: Value::String("worker-1".to_string()),
+                    value_path: None,
+                    value_function: None,
+                },
+                EnginePredicate {
+                    path: "spec.nodeName".to_string(),
+                    function: None,
+                    op: EngineOperator::Exists,
+                    value: Value::Null,
+                    value_path: None,
+                    value_function: None,
+                },
+            ]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert_eq!(predicate_match_counts(&plan, &objects), vec![3, 2, 3]);
+        assert_eq!(evaluate(&plan, &objects).len(), 1);
+    }
+
+    #[test]
+    fn distinguishes_empty_string_label_from_missing_label() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("non-empty".to_string())),
+                ("metadata.labels.team", Value::String("core".to_string())),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("empty".to_string())),
+                ("metadata.labels.team", Value::String(String::new())),
+            ]),
+            object(&[("metadata.name", Value::String("absent".to_string()))]),
+        ];
+
+        let matching = |op: EngineOperator, value: Value| {
+            let plan = QueryPlan {
+                filter: EngineExpr::all(vec![EnginePredicate {
+                    path: "metadata.labels.team".to_string(),
+                    function: None,
+                    op,
+                    value,
+                    value_path: None,
+                    value_function: None,
+                }]),
+                selection: None,
+                sort_keys: None,
+                limit: None,
+                computed: Vec::new(),
+                group_by: None,
+                having: None,
+                distinct: false,
+            };
+            names(&evaluate(&plan, &objects))
+        };
+
+        assert_eq!(
+            matching(EngineOperator::Eq, Value::String(String::new())),
+            vec!["empty"]
+        );
+        assert_eq!(
+            matching(EngineOperator::Ne, Value::String(String::new())),
+            vec!["non-empty"]
+        );
+        assert_eq!(
+            matching(EngineOperator::Exists, Value::Null),
+            vec!["non-empty", "empty"]
+        );
+    }
+
+    #[test]
+    fn now_compares_timestamps_chronologically_with_frozen_clock() {
+        let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_767_225_600);
+        let started = |name: &str, timestamp: Value| {
+            object(&[
+                ("metadata.name", Value::String(name.to_string())),
+                ("status.startTime", timestamp),
+            ])
+        };
+        let objects = vec![
+            started("before", Value::String("2025-12-31T23:59:59Z".to_string())),
+            started(
+                "offset",
+                Value::String("2026-01-01T02:00:00+03:00".to_string()),
+            ),
+            started("after", Value::String("2026-01-01T00:00:01Z".to_string())),
+            started(
+                "exact",
+                Value::String("2026-01-01T00:00:00.000Z".to_string()),
+            ),
+            started("garbage", Value::String("yesterday".to_string())),
+            started("number", Value::from(0)),
+        ];
+        let plan = |op: EngineOperator| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "status.startTime".to_string(),
+                function: None,
+                op,
+                value: Value::Null,
+                value_path: None,
+                value_function: Some(EngineValueFunction::Now),
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let matched = |op| names(&evaluate_at(&plan(op), &objects, now));
+        assert_eq!(matched(EngineOperator::Lt), vec!["before", "offset"]);
+        assert_eq!(matched(EngineOperator::Ge), vec!["after", "exact"]);
+        assert_eq!(matched(EngineOperator::Eq), vec!["exact"]);
+        assert_eq!(
+            matched(EngineOperator::Ne),
+            vec!["before", "offset", "after"]
+        );
+    }
+
+    #[test]
+    fn age_predicate_uses_frozen_clock() {
+        let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_767_225_600);
+        let objects = vec![object(&[
+            ("metadata.name", Value::String("api".to_string())),
+            (
+                "metadata.creationTimestamp",
+                Value::String("2025-12-31T23:00:00Z".to_string()),
+            ),
+        ])];
+        let plan = |op: EngineOperator| QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "metadata.creationTimestamp".to_string(),
+                function: Some(EngineFieldFunction::Age),
+                op,
+                value: Value::from(3600),
+                value_path: None,
+                value_function: None,
+            }]),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        assert_eq!(
+            names(&evaluate_at(&plan(EngineOperator::Eq), &objects, now)),
+            vec!["api"]
+        );
+        assert!(evaluate_at(&plan(EngineOperator::Gt), &objects, now).is_empty());
+    }
+
+    #[test]
+    fn computes_age_seconds_from_rfc3339_timestamp() {
+        let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_767_225_600);
+        assert_eq!(age_seconds("2026-01-01T00:00:00Z", now), Some(0));
+        assert_eq!(age_seconds("2025-12-24T21:00:00Z", now), Some(615_600));
+        assert_eq!(age_seconds("2026-01-01T03:00:00+03:00", now), Some(0));
+        assert_eq!(age_seconds("yesterday", now), None);
+    }
+
+    #[test]
+    fn aggregates_over_age_of_timestamps() {
+        let objects = vec![
+            object(&[(
+                "metadata.creationTimestamp",
+                Value::String("2020-01-01T00:00:00Z".to_string()),
+            )]),
+            object(&[(
+                "metadata.creationTimestamp",
+                Value::String("2020-01-08T03:00:00Z".to_string()),
+            )]),
+            object(&[("metadata.name", Value::String("no-timestamp".to_string()))]),
+        ];
+        let age = |function| EngineAggregationExpr {
+            function,
+            path: Some("metadata.creationTimestamp".to_string()),
+            field_function: Some(EngineFieldFunction::Age),
+        };
+        let plan = QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Aggregations(vec![
+                age(EngineAggregationFunction::Max),
+                age(EngineAggregationFunction::Min),
+                age(EngineAggregationFunction::Count),
+            ])),
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let rows = aggregate(&plan, &objects).expect("aggregation must succeed");
+        let fields = &rows[0].fields;
+        let max = fields["max(age(metadata.creationTimestamp))"]
+            .as_i64()
+            .expect("max must be integer");
+        let min = fields["min(age(metadata.creationTimestamp))"]
+            .as_i64()
+            .expect("min must be integer");
+        assert_eq!(max - min, 615_600);
+        assert_eq!(
+            fields["count(age(metadata.creationTimestamp))"],
+            Value::from(2)
+        );
+    }
+
+    #[test]
+    fn sorts_by_single_key_asc() {
+        let objects = vec![
+            object(&[("metadata.name", Value::String("pod-c".to_string()))]),
+            object(&[("metadata.name", Value::String("pod-a".to_string()))]),
+            object(&[("metadata.name", Value::String("pod-b".to_string()))]),
+        ];
+
+        let plan = QueryPlan {
+            filter: None,
+            selection: None,
+            sort_keys: Some(vec![EngineSortKey {
+                path: "metadata.name".to_string(),
+                function: None,
+                direction: EngineSortDirection::Asc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let sorted = sort_objects(&plan, &objects, &SortOptions::default());
+        let names = names(&sorted);
+        assert_eq!(names, vec!["pod-a", "pod-b", "pod-c"]);
+    }
+
+    #[test]
+    fn sorts_by_single_key_desc() {
+        let objects = vec![
+            object(&[("spec.priority", Value::from(1))]),
+            object(&[("spec.priority", Value::from(3))]),
+            object(&[("spec.priority", Value::from(2))]),
+        ];
+
+        let plan = QueryPlan {
+            filter: None,
+            selection: None,
+            sort_keys: Some(vec![EngineSortKey {
+                path: "spec.priority".to_string(),
+                function: None,
+                direction: EngineSortDirection::Desc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let sorted = sort_objects(&plan, &objects, &SortOptions::default());
+        let priorities = values(&sorted, "spec.priority");
+        assert_eq!(
+            priorities,
+            vec![Value::from(3), Value::from(2), Value::from(1)]
+        );
+    }
+
+    #[test]
+    fn sorts_by_container_count_desc() {
+        let objects = vec![
+            object(&[
+                ("metadata.name", Value::String("one".to_string())),
+                ("spec.containers", serde_json::json!([{"name": "app"}])),
+            ]),
+            object(&[("metadata.name", Value::String("none".to_string()))]),
+            object(&[
+                ("metadata.name", Value::String("three".to_string())),
+                (
+                    "spec.containers",
+                    serde_json::json!([{"name": "app"}, {"name": "proxy"}, {"name": "log"}]),
+                ),
+            ]),
+            object(&[
+                ("metadata.name", Value::String("two".to_string())),
+                (
+                    "spec.containers",
+                    serde_json::json!([{"name": "app"}, {"name": "proxy"}]),
+                ),
+            ]),
+        ];
+
+        let plan = QueryPlan {
+            filter: None,
+            selection: None,
+            sort_keys: Some(vec![EngineSortKey {
+                path: "spec.containers".to_string(),
+                function: Some(EngineFieldFunction::Len),
+                direction: EngineSortDirection::Desc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let sorted = names(&sort_objects(&plan, &objects, &SortOptions::default()));
+        assert_eq!(sorted, vec!["three", "two", "one", "none"]);
+    }
+
+    #[test]
+    fn sorts_by_string_length_with_non_strings_as_nullish() {
+        let objects = vec![
+            object(&[("metadata.name", Value::String("ccc".to_string()))]),
+            object(&[("metadata.name", Value::from(7))]),
+            object(&[("metadata.name", Value::String("a".to_string()))]),
+        ];
+
+        let plan = QueryPlan {
+            filter: None,
+            selection: None,
+            sort_keys: Some(vec![EngineSortKey {
+                path: "metadata.name".to_string(),
+                function: Some(EngineFieldFunction::StrLen),
+                direction: EngineSortDirection::Asc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let sorted = sort_objects(&plan, &objects, &SortOptions::default());
+        let sorted = values(&sorted, "metadata.name");
+        assert_eq!(
+            sorted,
+            vec![
+                Value::from(7),
+                Value::String("a".to_string()),
+                Value::String("ccc".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn sorts_strings_ignoring_case_when_requested() {
+        let objects = vec![
+            object(&[("metadata.name", Value::String("cherry".to_string()))]),
+            object(&[("metadata.name", Value::String("Banana".to_string()))]),
+            object(&[("metadata.name", Value::String("apple".to_string()))]),
+        ];
+
+        let plan = QueryPlan {
+            filter: None,
+            selection: None,
+            sort_keys: Some(vec![EngineSortKey {
+                path: "metadata.name".to_string(),
+                function: None,
+                direction: EngineSortDirection::Asc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+
+        let sensitive = names(&sort_objects(&plan, &objects, &SortOptions::default()));
+        let insensitive = names(&sort_objects(
+            &plan,
+            &objects,
+            &SortOptions {
+                ignore_case: true,
+                ..SortOptions::default()
+            },
+        ));
+
+        assert_eq!(sensitive, vec!["Banana", "apple", "cherry"]);
+        assert_eq!(insensitive, vec!["apple", "Banana", "cherry"]);
+    }
+
+    #[test]
+    fn distinct_keeps_first_row_per_projection_in_sort_order() {
+        let pod = |name: &str, owner: &str, namespace: Option<&str>| {
+            let mut entries = vec![
+                ("metadata.name", Value::String(name.to_string())),
+                ("spec.owner", Value::String(owner.to_string())),
+            ];
+            if let Some(namespace) = namespace {
+                entries.push(("metadata.namespace", Value::String(namespace.to_string())));
+            }
+            object(&entries)
+        };
+        let objects = vec![
+            pod("e", "ops", Some("demo")),
+            pod("d", "dev", Some("demo")),
+            pod("c", "ops", Some("demo")),
+            pod("b", "ops", None),
+            pod("a", "dev", Some("prod")),
+            pod("f", "ops", None),
+        ];
+        let mut plan = QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Paths(vec![
+                "spec.owner".to_string(),
+                "metadata.namespace".to_string(),
+            ])),
+            sort_keys: Some(vec![EngineSortKey {
+                path: "metadata.name".to_string(),
+                function: None,
+                direction: EngineSortDirection::Asc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: true,
+        };
+        let names = |rows: Vec<DynamicObject>| -> Vec<String> {
+            rows.iter()
+                .map(|row| {
+                    row.fields["metadata.name"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string()
+                })
+                .collect()
         };
 
-        let result = evaluate(
-            &plan,
-            &[
-                DynamicObject { fields: fields_ok },
-                DynamicObject { fields: fields_bad },
-            ],
+        let sorted = sort_objects(&plan, &objects, &SortOptions::default());
+        assert_eq!(
+            names(distinct_rows(&plan, sorted.clone())),
+            ["a", "b", "c", "d"]
         );
 
-        assert_eq!(result.len(), 1);
+        plan.selection = Some(EngineSelection::Paths(vec![
+            "spec.owner::string".to_string(),
+        ]));
+        assert_eq!(names(distinct_rows(&plan, sorted.clone())), ["a", "b"]);
+
+        plan.distinct = false;
+        assert_eq!(distinct_rows(&plan, sorted.clone()), sorted);
     }
 
     #[test]
-    fn missing_field_does_not_match_eq_or_ne() {
-        let mut fields = BTreeMap::new();
-        fields.insert(
-            "metadata.namespace".to_string(),
-            Value::String("default".to_string()),
-        );
+    fn computed_columns_reflect_predicate_per_row() {
+        let pod = |name: &str, phase: Option<&str>| {
+            let mut entries = vec![("metadata.name", Value::String(name.to_string()))];
+            if let Some(phase) = phase {
+                entries.push(("status.phase", Value::String(phase.to_string())));
+            }
+            object(&entries)
+        };
+        let mut rows = vec![
+            pod("api", Some("Running")),
+            pod("db", Some("Pending")),
+            pod("web", None),
+        ];
+        let running = EngineExpr::Leaf(EnginePredicate {
+            path: "status.phase".to_string(),
+            function: None,
+            op: EngineOperator::Eq,
+            value: Value::String("Running".to_string()),
+            value_path: None,
+            value_function: None,
+        });
+        let plan = QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Paths(vec![
+                "metadata.name".to_string(),
+                "ready".to_string(),
+                "stuck".to_string(),
+            ])),
+            sort_keys: None,
+            limit: None,
+            computed: vec![
+                EngineComputedColumn {
+                    alias: "ready".to_string(),
+                    expr: running.clone(),
+                },
+                EngineComputedColumn {
+                    alias: "stuck".to_string(),
+                    expr: EngineExpr::Not(Box::new(running)),
+                },
+            ],
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
 
-        let object = DynamicObject { fields };
+        compute_columns(&plan, &mut rows);
 
-        let eq_plan = QueryPlan {
-            predicates: vec![EnginePredicate {
-                path: "spec.nodeName".to_string(),
-                op: EngineOperator::Eq,
-                value: Value::String("worker-1".to_string()),
-            }],
-            selection: None,
-            sort_keys: None,
+        let column = |alias: &str| {
+            rows.iter()
+                .map(|row| row.fields.get(alias).cloned())
+                .collect::<Vec<_>>()
         };
+        assert_eq!(
+            column("ready"),
+            vec![
+                Some(Value::Bool(true)),
+                Some(Value::Bool(false)),
+                Some(Value::Bool(false))
+            ]
+        );
+        assert_eq!(
+            column("stuck"),
+            vec![
+                Some(Value::Bool(false)),
+                Some(Value::Bool(true)),
+                Some(Value::Bool(true))
+            ]
+        );
+    }
 
-        let ne_plan = QueryPlan {
-            predicates: vec![EnginePredicate {
-                path: "spec.nodeName".to_string(),
-                op: EngineOperator::Ne,
-                value: Value::String("worker-1".to_string()),
-            }],
-            selection: None,
-            sort_keys: None,
+    #[test]
+    fn limit_window_skips_offset_and_tolerates_overrun() {
+        let window = |count, offset| {
+            let mut rows: Vec<u32> = (1..=5).collect();
+            EngineLimit { count, offset }.apply(&mut rows);
+            rows
         };
 
-        assert!(evaluate(&eq_plan, std::slice::from_ref(&object)).is_empty());
-        assert!(evaluate(&ne_plan, &[object]).is_empty());
+        assert_eq!(window(2, 0), vec![1, 2]);
+        assert_eq!(window(2, 3), vec![4, 5]);
+        assert_eq!(window(10, 4), vec![5]);
+        assert!(window(2, 5).is_empty());
+        assert!(window(2, usize::MAX).is_empty());
+        assert_eq!(
+            EngineLimit {
+                count: 2,
+                offset: usize::MAX
+            }
+            .end(),
+            usize::MAX
+        );
     }
 
     #[test]
-    fn type_mismatch_does_not_match_eq_or_ne() {
-        let mut fields = BTreeMap::new();
-        fields.insert("spec.replicas".to_string(), Value::from(2));
-        let object = DynamicObject { fields };
-
-        let eq_plan = QueryPlan {
-            predicates: vec![EnginePredicate {
-                path: "spec.replicas".to_string(),
-                op: EngineOperator::Eq,
-                value: Value::String("2".to_string()),
-            }],
-            selection: None,
-            sort_keys: None,
+    fn breaks_ties_by_input_order_or_extra_path() {
+        let pod = |name: &str, phase: &str, node: &str| {
+            object(&[
+                ("metadata.name", Value::String(name.to_string())),
+                ("status.phase", Value::String(phase.to_string())),
+                ("spec.nodeName", Value::String(node.to_string())),
+            ])
         };
-
-        let ne_plan = QueryPlan {
-            predicates: vec![EnginePredicate {
-                path: "spec.replicas".to_string(),
-                op: EngineOperator::Ne,
-                value: Value::String("2".to_string()),
-            }],
+        let objects = vec![
+            pod("c", "Running", "node-2"),
+            pod("a", "Running", "node-3"),
+            pod("x", "Failed", "node-9"),
+            pod("b", "Running", "node-1"),
+        ];
+        let plan = QueryPlan {
+            filter: None,
             selection: None,
-            sort_keys: None,
+            sort_keys: Some(vec![EngineSortKey {
+                path: "status.phase".to_string(),
+                function: None,
+                direction: EngineSortDirection::Desc,
+            }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let sorted = |tie_break| {
+            names(&sort_objects(
+                &plan,
+                &objects,
+                &SortOptions {
+                    tie_break,
+                    ..SortOptions::default()
+                },
+            ))
         };
 
-        assert!(evaluate(&eq_plan, std::slice::from_ref(&object)).is_empty());
-        assert!(evaluate(&ne_plan, &[object]).is_empty());
+        assert_eq!(sorted(TieBreak::InputOrder), vec!["c", "a", "b", "x"]);
+        assert_eq!(
+            sorted(TieBreak::Path("spec.nodeName".to_string())),
+            vec!["b", "c", "a", "x"]
+        );
     }
 
     #[test]
-    fn sorts_by_single_key_asc() {
-        let objects = vec![
-            object(&[("metadata.name", Value::String("pod-c".to_string()))]),
-            object(&[("metadata.name", Value::String("pod-a".to_string()))]),
-            object(&[("metadata.name", Value::String("pod-b".to_string()))]),
-        ];
+    fn sorts_persistent_volumes_by_capacity_quantity() {
+        let pv = |name: &str, capacity: &str| {
+            object(&[
+                ("metadata.name", Value::String(name.to_string())),
+                ("spec.capacity.storage", Value::String(capacity.to_string())),
+            ])
+        };
+        let objects = vec![pv("pv-1t", "1Ti"), pv("pv-5g", "5Gi"), pv("pv-10g", "10Gi")];
 
-        let plan = QueryPlan {
-            predicates: Vec::new(),
+        let plan = |direction| QueryPlan {
+            filter: None,
             selection: None,
             sort_keys: Some(vec![EngineSortKey {
-                path: "metadata.name".to_string(),
-                direction: EngineSortDirection::Asc,
+                path: "spec.capacity.storage".to_string(),
+                function: None,
+                direction,
             }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
-        let sorted = sort_objects(&plan, &objects);
-        let names = names(&sorted);
-        assert_eq!(names, vec!["pod-a", "pod-b", "pod-c"]);
+        let asc = names(&sort_objects(
+            &plan(EngineSortDirection::Asc),
+            &objects,
+            &SortOptions::default(),
+        ));
+        let desc = names(&sort_objects(
+            &plan(EngineSortDirection::Desc),
+            &objects,
+            &SortOptions::default(),
+        ));
+
+        assert_eq!(asc, vec!["pv-5g", "pv-10g", "pv-1t"]);
+        assert_eq!(desc, vec!["pv-1t", "pv-10g", "pv-5g"]);
+
+        let filter = QueryPlan {
+            filter: EngineExpr::all(vec![EnginePredicate {
+                path: "spec.capacity.storage".to_string(),
+                function: None,
+                op: EngineOperator::Gt,
+                value: Value::String("10Gi".to_string()),
+                value_path: None,
+                value_function: None,
+            }]),
+            ..plan(EngineSortDirection::Asc)
+        };
+        assert_eq!(names(&evaluate(&filter, &objects)), vec!["pv-1t"]);
     }
 
     #[test]
-    fn sorts_by_single_key_desc() {
-        let objects = vec![
-            object(&[("spec.priority", Value::from(1))]),
-            object(&[("spec.priority", Value::from(3))]),
-            object(&[("spec.priority", Value::from(2))]),
-        ];
-
+    fn sorts_mixed_suffixed_and_bare_quantities_by_magnitude() {
+        let objects: Vec<_> = ["10", "2500m", "2", "1Ki", "n/a"]
+            .into_iter()
+            .map(|value| {
+                object(&[
+                    ("metadata.name", Value::String(value.to_string())),
+                    ("spec.cpu", Value::String(value.to_string())),
+                ])
+            })
+            .collect();
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: None,
             sort_keys: Some(vec![EngineSortKey {
-                path: "spec.priority".to_string(),
-                direction: EngineSortDirection::Desc,
+                path: "spec.cpu".to_string(),
+                function: None,
+                direction: EngineSortDirection::Asc,
             }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
-        let sorted = sort_objects(&plan, &objects);
-        let priorities = values(&sorted, "spec.priority");
-        assert_eq!(
-            priorities,
-            vec![Value::from(3), Value::from(2), Value::from(1)]
-        );
+        let sorted = names(&sort_objects(&plan, &objects, &SortOptions::default()));
+        assert_eq!(sorted, vec!["2", "2500m", "10", "1Ki", "n/a"]);
     }
 
     #[test]
@@ -777,25 +3279,37 @@ mod tests {
         ];
 
         let asc_plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: None,
             sort_keys: Some(vec![EngineSortKey {
                 path: "spec.rank".to_string(),
+                function: None,
                 direction: EngineSortDirection::Asc,
             }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let desc_plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: None,
             sort_keys: Some(vec![EngineSortKey {
                 path: "spec.rank".to_string(),
+                function: None,
                 direction: EngineSortDirection::Desc,
             }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
-        let asc = names(&sort_objects(&asc_plan, &objects));
-        let desc = names(&sort_objects(&desc_plan, &objects));
+        let asc = names(&sort_objects(&asc_plan, &objects, &SortOptions::default()));
+        let desc = names(&sort_objects(&desc_plan, &objects, &SortOptions::default()));
 
         assert_eq!(asc, vec!["a", "b", "d", "c"]);
         assert_eq!(desc, vec!["c", "d", "a", "b"]);
@@ -823,15 +3337,21 @@ mod tests {
         ];
 
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: None,
             sort_keys: Some(vec![EngineSortKey {
                 path: "spec.value".to_string(),
+                function: None,
                 direction: EngineSortDirection::Asc,
             }]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
-        let sorted = names(&sort_objects(&plan, &objects));
+        let sorted = names(&sort_objects(&plan, &objects, &SortOptions::default()));
         assert_eq!(sorted, vec!["b", "n", "s", "o"]);
     }
 
@@ -854,21 +3374,28 @@ mod tests {
         ];
 
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: None,
             sort_keys: Some(vec![
                 EngineSortKey {
                     path: "spec.rank".to_string(),
+                    function: None,
                     direction: EngineSortDirection::Asc,
                 },
                 EngineSortKey {
                     path: "metadata.name".to_string(),
+                    function: None,
                     direction: EngineSortDirection::Asc,
                 },
             ]),
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
-        let sorted = sort_objects(&plan, &objects);
+        let sorted = sort_objects(&plan, &objects, &SortOptions::default());
         let names = names(&sorted);
 
         assert_eq!(names, vec!["-", "alpha", "beta", "gamma"]);
@@ -882,30 +3409,40 @@ mod tests {
             object(&[("spec.replicas", Value::from(2))]),
         ];
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Count,
                     path: None,
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Sum,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Min,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Max,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Avg,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
             ])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let rows = aggregate(&plan, &objects).expect("must aggregate");
@@ -918,37 +3455,158 @@ mod tests {
         assert_eq!(row.get("avg(spec.replicas)"), Some(&Value::from(2.0)));
     }
 
+    #[test]
+    fn aggregates_per_group_with_null_bucket() {
+        let owned = |owner: Option<Value>, replicas: i64| {
+            let mut entries = vec![("spec.replicas", Value::from(replicas))];
+            if let Some(owner) = owner {
+                entries.push(("spec.owner", owner));
+            }
+            object(&entries)
+        };
+        let objects = vec![
+            owned(Some(Value::from("team-a")), 1),
+            owned(None, 2),
+            owned(Some(Value::from("team-b")), 3),
+            owned(Some(Value::from("team-a")), 4),
+            owned(Some(Value::Null), 5),
+        ];
+        let plan = QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Aggregations(vec![
+                EngineAggregationExpr {
+                    function: EngineAggregationFunction::Count,
+                    path: None,
+                    field_function: None,
+                },
+                EngineAggregationExpr {
+                    function: EngineAggregationFunction::Sum,
+                    path: Some("spec.replicas".to_string()),
+                    field_function: None,
+                },
+            ])),
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: Some(vec!["spec.owner".to_string()]),
+            having: None,
+            distinct: false,
+        };
+
+        let rows = aggregate(&plan, &objects).expect("must aggregate");
+        let summary: Vec<(Value, Value, Value)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.fields["spec.owner"].clone(),
+                    row.fields["count(*)"].clone(),
+                    row.fields["sum(spec.replicas)"].clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Value::from("team-a"), Value::from(2), Value::from(5)),
+                (Value::Null, Value::from(2), Value::from(7)),
+                (Value::from("team-b"), Value::from(1), Value::from(3)),
+            ]
+        );
+        assert!(!is_count_only(&plan));
+        assert!(aggregate(&plan, &[]).expect("must aggregate").is_empty());
+    }
+
+    #[test]
+    fn having_filters_aggregated_rows_by_column_name() {
+        let group = |owner: &str, count: u64| {
+            object(&[
+                ("spec.owner", Value::from(owner)),
+                ("count(*)", Value::from(count)),
+            ])
+        };
+        let rows = vec![group("team-a", 3), group("team-b", 1), group("team-c", 2)];
+        let compare = |path: &str, op, value: Value| {
+            EngineExpr::Leaf(EnginePredicate {
+                path: path.to_string(),
+                function: None,
+                op,
+                value,
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let plan = |having| QueryPlan {
+            filter: None,
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: Some(vec!["spec.owner".to_string()]),
+            having,
+            distinct: false,
+        };
+
+        let singletons_dropped = compare("count(*)", EngineOperator::Gt, Value::from(1));
+        let kept = filter_groups(&plan(Some(singletons_dropped.clone())), rows.clone());
+        assert_eq!(
+            kept.iter()
+                .map(|row| row.fields["spec.owner"].clone())
+                .collect::<Vec<_>>(),
+            vec![Value::from("team-a"), Value::from("team-c")]
+        );
+        let not_team_a = EngineExpr::And(vec![
+            singletons_dropped,
+            compare("spec.owner", EngineOperator::Ne, Value::from("team-a")),
+        ]);
+        assert_eq!(
+            filter_groups(&plan(Some(not_team_a)), rows.clone()).len(),
+            1
+        );
+        assert_eq!(filter_groups(&plan(None), rows).len(), 3);
+    }
+
     #[test]
     fn aggregates_empty_set_sql_like() {
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Count,
                     path: None,
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Count,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Sum,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Avg,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Min,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Max,
                     path: Some("spec.replicas".to_string()),
+                    field_function: None,
                 },
             ])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let rows = aggregate(&plan, &[]).expect("must aggregate");
@@ -961,16 +3619,48 @@ mod tests {
         assert_eq!(row.get("max(spec.replicas)"), Some(&Value::Null));
     }
 
+    #[test]
+    fn count_rows_match_aggregate_for_count_only_plans() {
+        let count = |path: Option<&str>| QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
+                function: EngineAggregationFunction::Count,
+                path: path.map(str::to_string),
+                field_function: None,
+            }])),
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let objects = vec![object(&[]), object(&[]), object(&[])];
+
+        assert!(is_count_only(&count(None)));
+        assert_eq!(
+            count_rows(&count(None), objects.len()),
+            aggregate(&count(None), &objects).expect("must aggregate")
+        );
+        assert!(!is_count_only(&count(Some("spec.replicas"))));
+    }
+
     #[test]
     fn aggregate_sum_errors_on_non_numeric_values() {
         let objects = vec![object(&[("spec.replicas", Value::String("bad".to_string()))])];
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
                 function: EngineAggregationFunction::Sum,
                 path: Some("spec.replicas".to_string()),
+                field_function: None,
             }])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let err = aggregate(&plan, &objects).expect_err("must fail");
@@ -984,12 +3674,18 @@ mod tests {
             object(&[("spec.value", Value::String("x".to_string()))]),
         ];
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
                 function: EngineAggregationFunction::Min,
                 path: Some("spec.value".to_string()),
+                field_function: None,
             }])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let err = aggregate(&plan, &objects).expect_err("must fail");
@@ -1004,12 +3700,18 @@ mod tests {
             object(&[]),
         ];
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
                 function: EngineAggregationFunction::Count,
                 path: Some("spec.replicas".to_string()),
+                field_function: None,
             }])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let rows = aggregate(&plan, &objects).expect("must aggregate");
@@ -1024,12 +3726,18 @@ mod tests {
             object(&[("spec.value", Value::from(2u64))]),
         ];
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
                 function: EngineAggregationFunction::Sum,
                 path: Some("spec.value".to_string()),
+                field_function: None,
             }])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let rows = aggregate(&plan, &objects).expect("must aggregate");
@@ -1047,18 +3755,25 @@ mod tests {
             object(&[("spec.value", Value::from(9_007_199_254_740_992u64))]),
         ];
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Min,
                     path: Some("spec.value".to_string()),
+                    field_function: None,
                 },
                 EngineAggregationExpr {
                     function: EngineAggregationFunction::Max,
                     path: Some("spec.value".to_string()),
+                    field_function: None,
                 },
             ])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let rows = aggregate(&plan, &objects).expect("must aggregate");
@@ -1080,12 +3795,18 @@ mod tests {
             object(&[("spec.value", Value::from(2.5))]),
         ];
         let plan = QueryPlan {
-            predicates: Vec::new(),
+            filter: None,
             selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
                 function: EngineAggregationFunction::Avg,
                 path: Some("spec.value".to_string()),
+                field_function: None,
             }])),
             sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
         };
 
         let rows = aggregate(&plan, &objects).expect("must aggregate");
@@ -1093,6 +3814,283 @@ mod tests {
         assert_eq!(row.get("avg(spec.value)"), Some(&Value::from(2.0)));
     }
 
+    #[test]
+    fn aggregate_median_and_stddev_over_numeric_values() {
+        let plan = |function: EngineAggregationFunction| QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
+                function,
+                path: Some("spec.replicas".to_string()),
+                field_function: None,
+            }])),
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let replicas = |values: &[Value]| -> Vec<DynamicObject> {
+            values
+                .iter()
+                .map(|value| object(&[("spec.replicas", value.clone())]))
+                .collect()
+        };
+        let single = |function: EngineAggregationFunction, objects: &[DynamicObject]| {
+            aggregate(&plan(function), objects)
+                .ok()
+                .and_then(|rows| rows[0].fields.values().next().cloned())
+        };
+        let (median, stddev) = (
+            EngineAggregationFunction::Median,
+            EngineAggregationFunction::Stddev,
+        );
+
+        let odd = replicas(&[Value::from(7), Value::from(1), Value::Null, Value::from(3)]);
+        assert_eq!(single(median.clone(), &odd), Some(Value::from(3.0)));
+        let even = replicas(&[
+            Value::from(4),
+            Value::from(1),
+            Value::from(2.5),
+            Value::from(10),
+        ]);
+        assert_eq!(single(median.clone(), &even), Some(Value::from(3.25)));
+
+        let spread = replicas(&[2, 4, 4, 4, 5, 5, 7, 9].map(Value::from));
+        assert_eq!(single(stddev.clone(), &spread), Some(Value::from(2.0)));
+        assert_eq!(
+            single(stddev.clone(), &replicas(&[Value::from(5)])),
+            Some(Value::from(0.0))
+        );
+
+        for function in [median, stddev] {
+            assert_eq!(single(function.clone(), &[]), Some(Value::Null));
+            assert!(matches!(
+                aggregate(
+                    &plan(function),
+                    &replicas(&[Value::from(1), Value::from("two")])
+                ),
+                Err(crate::error::EngineError::InvalidAggregation {
+                    expected: "number",
+                    ..
+                })
+            ));
+        }
+
+        let percentile = |rank: u64| EngineAggregationFunction::Percentile(rank.into());
+        let sample = replicas(&[40, 10, 30, 20, 50].map(Value::from));
+        assert_eq!(single(percentile(0), &sample), Some(Value::from(10.0)));
+        assert_eq!(single(percentile(50), &sample), Some(Value::from(30.0)));
+        assert_eq!(single(percentile(90), &sample), Some(Value::from(46.0)));
+        assert_eq!(single(percentile(100), &sample), Some(Value::from(50.0)));
+        assert_eq!(single(percentile(95), &[]), Some(Value::Null));
+        assert_eq!(
+            aggregation_key(&EngineAggregationExpr {
+                function: percentile(95),
+                path: Some("spec.replicas".to_string()),
+                field_function: None,
+            }),
+            "percentile(spec.replicas, 95)"
+        );
+    }
+
+    #[test]
+    fn aggregate_mode_picks_most_common_value_and_breaks_ties_in_sort_order() {
+        let mode_plan = |path: &str| QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
+                function: EngineAggregationFunction::Mode,
+                path: Some(path.to_string()),
+                field_function: None,
+            }])),
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let mode = |values: &[Value]| {
+            let objects: Vec<DynamicObject> = values
+                .iter()
+                .map(|value| object(&[("status.phase", value.clone())]))
+                .collect();
+            aggregate(&mode_plan("status.phase"), &objects)
+                .ok()
+                .and_then(|rows| rows[0].fields.get("mode(status.phase)").cloned())
+        };
+
+        let phases = [
+            "Running", "Pending", "Running", "Failed", "Running", "Pending",
+        ];
+        assert_eq!(mode(&phases.map(Value::from)), Some(Value::from("Running")));
+        assert_eq!(
+            mode(&[Value::from(3), Value::Null, Value::from(3), Value::from(1)]),
+            Some(Value::from(3))
+        );
+        assert_eq!(
+            mode(&[
+                Value::from("b"),
+                Value::from("a"),
+                Value::from("b"),
+                Value::from("a")
+            ]),
+            Some(Value::from("a"))
+        );
+        assert_eq!(
+            mode(&[Value::from(true), Value::from(false)]),
+            Some(Value::from(false))
+        );
+        assert_eq!(mode(&[]), Some(Value::Null));
+        assert!(mode(&[Value::from(vec![1])]).is_none());
+    }
+
+    #[test]
+    fn aggregate_group_concat_joins_values_in_input_order() {
+        let concat_plan = |separator: &str| QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
+                function: EngineAggregationFunction::GroupConcat(separator.to_string()),
+                path: Some("metadata.name".to_string()),
+                field_function: None,
+            }])),
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let objects = [
+            object(&[("metadata.name", Value::from("web"))]),
+            object(&[("metadata.name", Value::Null)]),
+            object(&[]),
+            object(&[("metadata.name", Value::from(7))]),
+            object(&[("metadata.name", Value::from("api"))]),
+        ];
+
+        let rows = aggregate(&concat_plan(","), &objects).expect("aggregate must succeed");
+        assert_eq!(
+            rows[0].fields.get("group_concat(metadata.name)"),
+            Some(&Value::from("web,7,api"))
+        );
+        let rows = aggregate(&concat_plan("; "), &objects).expect("aggregate must succeed");
+        assert_eq!(
+            rows[0].fields.get("group_concat(metadata.name, '; ')"),
+            Some(&Value::from("web; 7; api"))
+        );
+        let rows = aggregate(&concat_plan(","), &[]).expect("aggregate must succeed");
+        assert_eq!(
+            rows[0].fields.get("group_concat(metadata.name)"),
+            Some(&Value::from(""))
+        );
+    }
+
+    #[test]
+    fn aggregate_range_reports_integer_and_float_bounds() {
+        let range_plan = |path: &str| QueryPlan {
+            filter: None,
+            selection: Some(EngineSelection::Aggregations(vec![EngineAggregationExpr {
+                function: EngineAggregationFunction::Range,
+                path: Some(path.to_string()),
+                field_function: None,
+            }])),
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let objects = vec![
+            object(&[
+                ("spec.replicas", Value::from(3)),
+                ("spec.ratio", Value::from(0.5)),
+            ]),
+            object(&[
+                ("spec.replicas", Value::from(1)),
+                ("spec.ratio", Value::from(2.25)),
+            ]),
+            object(&[
+                ("spec.replicas", Value::from(5)),
+                ("spec.ratio", Value::Null),
+            ]),
+            object(&[]),
+        ];
+
+        let rows = aggregate(&range_plan("spec.replicas"), &objects).expect("must aggregate");
+        assert_eq!(
+            rows[0].fields.get("range(spec.replicas)"),
+            Some(&Value::String("1-5".to_string()))
+        );
+
+        let rows = aggregate(&range_plan("spec.ratio"), &objects).expect("must aggregate");
+        assert_eq!(
+            rows[0].fields.get("range(spec.ratio)"),
+            Some(&Value::String("0.5-2.25".to_string()))
+        );
+
+        let rows = aggregate(&range_plan("spec.replicas"), &[]).expect("must aggregate");
+        assert_eq!(
+            rows[0].fields.get("range(spec.replicas)"),
+            Some(&Value::Null)
+        );
+    }
+
+    #[test]
+    fn contradictory_equalities_short_circuit_without_scanning() {
+        let predicate = |op: EngineOperator, value: &str| EnginePredicate {
+            path: "metadata.namespace".to_string(),
+            function: None,
+            op,
+            value: Value::String(value.to_string()),
+            value_path: None,
+            value_function: None,
+        };
+        let plan = |predicates: Vec<EnginePredicate>| QueryPlan {
+            filter: EngineExpr::all(predicates),
+            selection: None,
+            sort_keys: None,
+            limit: None,
+            computed: Vec::new(),
+            group_by: None,
+            having: None,
+            distinct: false,
+        };
+        let objects = [object(&[(
+            "metadata.namespace",
+            Value::String("a".to_string()),
+        )])];
+
+        let contradictory = plan(vec![
+            predicate(EngineOperator::Eq, "a"),
+            predicate(EngineOperator::Eq, "b"),
+        ]);
+        let contradiction = find_contradiction(&contradictory).expect("must be contradictory");
+        assert_eq!(contradiction.path, "metadata.namespace");
+        let mut scanned = 0;
+        let matched = evaluate_iter(&contradictory, objects.iter().inspect(|_| scanned += 1));
+        assert!(matched.is_empty());
+        assert_eq!(scanned, 0);
+
+        let negated = plan(vec![
+            predicate(EngineOperator::Ne, "a"),
+            predicate(EngineOperator::Eq, "a"),
+        ]);
+        assert!(find_contradiction(&negated).is_some());
+
+        let consistent = plan(vec![
+            predicate(EngineOperator::Eq, "a"),
+            predicate(EngineOperator::Eq, "a"),
+            predicate(EngineOperator::Ne, "b"),
+        ]);
+        assert_eq!(find_contradiction(&consistent), None);
+        let mut scanned = 0;
+        let matched = evaluate_iter(&consistent, objects.iter().inspect(|_| scanned += 1));
+        assert_eq!(matched.len(), 1);
+        assert_eq!(scanned, 1);
+    }
+
     fn object(entries: &[(&str, Value)]) -> DynamicObject {
         let mut fields = BTreeMap::new();
         for (path, value) in entries {
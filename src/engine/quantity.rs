@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+
+const NANOS_PER_UNIT: i128 = 1_000_000_000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Quantity {
+    nanos: i128,
+}
+
+impl PartialEq for Quantity {
+    fn eq(&self, other: &Self) -> bool {
+        self.nanos == other.nanos
+    }
+}
+
+impl Eq for Quantity {}
+
+impl PartialOrd for Quantity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Quantity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.nanos.cmp(&other.nanos)
+    }
+}
+
+pub fn parse_quantity(input: &str) -> Option<Quantity> {
+    let (negative, unsigned) = match input.as_bytes().first()? {
+        b'-' => (true, &input[1..]),
+        b'+' => (false, &input[1..]),
+        _ => (false, input),
+    };
+
+    let number_len = unsigned
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(unsigned.len());
+    let (number, suffix) = unsigned.split_at(number_len);
+    let nanos_per_suffix = suffix_nanos(suffix)?;
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    if fraction.contains('.') {
+        return None;
+    }
+
+    let mut mantissa: i128 = 0;
+    for digit in whole.bytes().chain(fraction.bytes()) {
+        mantissa = mantissa
+            .checked_mul(10)?
+            .checked_add(i128::from(digit - b'0'))?;
+    }
+    let scale = 10_i128.checked_pow(u32::try_from(fraction.len()).ok()?)?;
+    let nanos = mantissa.checked_mul(nanos_per_suffix)? / scale;
+
+    Some(Quantity {
+        nanos: if negative { -nanos } else { nanos },
+    })
+}
+
+/// Сравнение по величине, если обе строки — quantity (в том числе числа
+/// без суффикса); иначе `None`.
+pub fn compare_quantity_strings(left: &str, right: &str) -> Option<Ordering> {
+    Some(parse_quantity(left)?.cmp(&parse_quantity(right)?))
+}
+
+/// Полный порядок строк для сортировки: quantity идут первыми и
+/// сравниваются по величине, остальные строки — после них через
+/// `fallback`. Так порядок транзитивен и на смеси `2`, `10`, `2500m`.
+pub fn compare_strings_by_quantity(
+    left: &str,
+    right: &str,
+    fallback: impl FnOnce(&str, &str) -> Ordering,
+) -> Ordering {
+    match (parse_quantity(left), parse_quantity(right)) {
+        (Some(left), Some(right)) => left.cmp(&right),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => fallback(left, right),
+    }
+}
+
+fn suffix_nanos(suffix: &str) -> Option<i128> {
+    let nanos = match suffix {
+        "" => NANOS_PER_UNIT,
+        "n" => 1,
+        "u" => 1_000,
+        "m" => 1_000_000,
+        "k" => 1_000 * NANOS_PER_UNIT,
+        "M" => 1_000_000 * NANOS_PER_UNIT,
+        "G" => 1_000_000_000 * NANOS_PER_UNIT,
+        "T" => 1_000_000_000_000 * NANOS_PER_UNIT,
+        "P" => 1_000_000_000_000_000 * NANOS_PER_UNIT,
+        "E" => 1_000_000_000_000_000_000 * NANOS_PER_UNIT,
+        "Ki" => (1 << 10) * NANOS_PER_UNIT,
+        "Mi" => (1 << 20) * NANOS_PER_UNIT,
+        "Gi" => (1 << 30) * NANOS_PER_UNIT,
+        "Ti" => (1 << 40) * NANOS_PER_UNIT,
+        "Pi" => (1 << 50) * NANOS_PER_UNIT,
+        "Ei" => (1 << 60) * NANOS_PER_UNIT,
+        _ => return None,
+    };
+    Some(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{compare_quantity_strings, compare_strings_by_quantity, parse_quantity};
+
+    #[test]
+    fn parses_binary_decimal_and_fractional_quantities() {
+        assert_eq!(parse_quantity("1Ki"), parse_quantity("1024"));
+        assert_eq!(parse_quantity("1.5Gi"), parse_quantity("1536Mi"));
+        assert_eq!(parse_quantity("500m"), parse_quantity("0.5"));
+        assert_eq!(parse_quantity("1k"), parse_quantity("1000"));
+        assert!(parse_quantity("-1Gi") < parse_quantity("0"));
+    }
+
+    #[test]
+    fn rejects_non_quantities() {
+        assert_eq!(parse_quantity(""), None);
+        assert_eq!(parse_quantity("Gi"), None);
+        assert_eq!(parse_quantity("10GB"), None);
+        assert_eq!(parse_quantity("1.2.3"), None);
+        assert_eq!(parse_quantity("worker-1"), None);
+    }
+
+    #[test]
+    fn compares_quantity_strings_including_bare_numbers() {
+        assert_eq!(
+            compare_quantity_strings("10Gi", "5Gi"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_quantity_strings("1Ti", "10Gi"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(compare_quantity_strings("500", "1Ki"), Some(Ordering::Less));
+        assert_eq!(compare_quantity_strings("10", "9"), Some(Ordering::Greater));
+        assert_eq!(
+            compare_quantity_strings("2500m", "2"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(compare_quantity_strings("10Gi", "large"), None);
+    }
+
+    #[test]
+    fn string_order_with_quantities_is_total() {
+        let mut values = vec!["10GB", "large", "10", "2500m", "2", "10Gi"];
+        values.sort_by(|left, right| compare_strings_by_quantity(left, right, |l, r| l.cmp(r)));
+        assert_eq!(values, vec!["2", "2500m", "10", "10Gi", "10GB", "large"]);
+    }
+}
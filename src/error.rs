@@ -37,6 +37,7 @@ pub enum RetryErrorKind {
     ResourceResolutionStale,
     ListFailed,
     DiscoveryRun,
+    Tls,
     Other,
 }
 
@@ -52,6 +53,7 @@ impl std::fmt::Display for RetryErrorKind {
             Self::ResourceResolutionStale => write!(f, "stale resource resolution"),
             Self::ListFailed => write!(f, "list failed"),
             Self::DiscoveryRun => write!(f, "discovery failed"),
+            Self::Tls => write!(f, "tls failure"),
             Self::Other => write!(f, "other"),
         }
     }
@@ -71,6 +73,25 @@ pub enum K8sError {
         #[source]
         source: BoxError,
     },
+    #[error("failed to read resource aliases from '{path}': {message}")]
+    AliasConfig { path: String, message: String },
+    #[error("failed to load kube config for context '{context}': {source}")]
+    ContextConfig {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("failed to read kubeconfig '{path}': {source}")]
+    KubeconfigRead {
+        path: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("kube context '{context}' not found in kubeconfig (available: {})", available.join(", "))]
+    ContextNotFound {
+        context: String,
+        available: Vec<String>,
+    },
     #[error("failed to build kube client: {source}")]
     ClientBuild {
         #[source]
@@ -87,6 +108,12 @@ pub enum K8sError {
         #[source]
         source: BoxError,
     },
+    #[error("tls/certificate verification failed during {stage}: {source}")]
+    TlsError {
+        stage: &'static str,
+        #[source]
+        source: BoxError,
+    },
     #[error("request timed out during {stage} after {timeout_ms}ms: {source}")]
     RequestTimeout {
         stage: &'static str,
@@ -108,6 +135,12 @@ pub enum K8sError {
         #[source]
         source: BoxError,
     },
+    #[error("server does not support paginated list for resource '{resource}': {source}")]
+    PaginationUnsupported {
+        resource: String,
+        #[source]
+        source: BoxError,
+    },
     #[error("server rejected selectors for resource '{resource}': {source}")]
     SelectorRejected {
         resource: String,
@@ -141,6 +174,29 @@ pub enum OutputError {
         #[source]
         source: serde_yaml::Error,
     },
+    #[error("failed to write output")]
+    Write {
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(
+        "`-o value` expects exactly one row with one column, got {rows} row(s) and {columns} column(s)"
+    )]
+    NotSingleValue { rows: usize, columns: usize },
+    #[error("`-o value` expects a scalar, but column `{column}` holds a nested value")]
+    NonScalarValue { column: String },
+    #[error("`--histogram` expects exactly two selected columns (label, count), got {columns}")]
+    HistogramShape { columns: usize },
+    #[error("`--histogram` expects a non-negative number in `{column}` for row `{label}`")]
+    HistogramCount { label: String, column: String },
+    #[error("`-o prometheus` expects an aggregation query such as `select count(*)`")]
+    PrometheusShape,
+    #[error("`-o prometheus` expects numeric values in aggregation column `{column}`")]
+    PrometheusValue { column: String },
+    #[error("`-o env` expects a single-row aggregation result")]
+    EnvShape,
+    #[error("failed to load column names `{path}`: {message}")]
+    ColumnNames { path: String, message: String },
 }
 
 #[derive(Debug, Error)]
@@ -163,6 +219,24 @@ pub enum EngineError {
     },
 }
 
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("failed to read snapshot `{path}`")]
+    Read {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse snapshot `{path}` as json")]
+    Parse {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("snapshot `{path}` must be a json array of objects or a list with `items`")]
+    Shape { path: String },
+}
+
 #[derive(Debug)]
 pub enum CliError {
     InvalidArgs(String),
@@ -170,6 +244,8 @@ pub enum CliError {
     Engine(EngineError),
     K8s(K8sError),
     Output(OutputError),
+    Snapshot(SnapshotError),
+    DiagnosticsReported(usize),
 }
 
 impl std::fmt::Display for CliError {
@@ -188,9 +264,14 @@ impl std::fmt::Display for CliError {
             ),
             Self::Engine(error) => write!(f, "engine error: {error}"),
             Self::K8s(error) => write!(f, "k8s error: {error}\n\n{}", k8s_tip(error)),
-            Self::Output(error) => write!(
+            Self::Output(error) => write!(f, "output error: {error}\n\n{}", output_tip(error)),
+            Self::Snapshot(error) => write!(
+                f,
+                "snapshot error: {error}\n\nTip: create a snapshot with `kubiq <resource> where ... --describe -o json > snapshot.json`."
+            ),
+            Self::DiagnosticsReported(count) => write!(
                 f,
-                "output error: {error}\n\nTip: supported formats are `table`, `json`, `yaml`."
+                "{count} diagnostic(s) reported and `--fail-on-any-diagnostic` is set\n\nTip: see `[pushdown]`/`[retry]` lines in stderr above; drop the flag to ignore diagnostics."
             ),
         }
     }
@@ -202,28 +283,67 @@ impl std::error::Error for CliError {
             Self::Engine(error) => Some(error),
             Self::K8s(error) => Some(error),
             Self::Output(error) => Some(error),
+            Self::Snapshot(error) => Some(error),
             _ => None,
         }
     }
 }
 
+fn output_tip(error: &OutputError) -> &'static str {
+    match error {
+        OutputError::NotSingleValue { .. } | OutputError::NonScalarValue { .. } => {
+            "Tip: use `-o value` with a single aggregation such as `select count(*)` or a single scalar path for one object."
+        }
+        OutputError::HistogramShape { .. } | OutputError::HistogramCount { .. } => {
+            "Tip: select a label path followed by a numeric path, e.g. `select metadata.name,spec.replicas`."
+        }
+        OutputError::PrometheusShape | OutputError::PrometheusValue { .. } => {
+            "Tip: use `-o prometheus` with numeric aggregations, e.g. `select count(*)` or `select sum(spec.replicas)`."
+        }
+        OutputError::EnvShape => {
+            "Tip: use `-o env` with aggregations only, e.g. `select count(*), sum(spec.replicas)`."
+        }
+        OutputError::ColumnNames { .. } => {
+            "Tip: each line of the --rename-cols file must look like:\n  \"metadata.name\" = \"Name\""
+        }
+        _ => {
+            "Tip: supported formats are `table`, `json`, `yaml`, `value`, `prometheus`, `env`, `tsv`, `ndjson`, `kv`, `name`, `custom-columns=HEADER:path,...`."
+        }
+    }
+}
+
 fn k8s_tip(error: &K8sError) -> &'static str {
     match error {
         K8sError::ResourceNotFound { .. } => {
             "Tip: resource was not found. Check plural name via:\n  kubectl api-resources"
         }
+        K8sError::AliasConfig { .. } => {
+            "Tip: fix or remove the aliases file; each line must look like:\n  wg = \"widgets.example.com\""
+        }
+        K8sError::KubeconfigRead { .. } => {
+            "Tip: check that the --kubeconfig path exists and is a valid kubeconfig:\n  kubectl --kubeconfig <path> config view"
+        }
+        K8sError::ContextConfig { .. } | K8sError::ContextNotFound { .. } => {
+            "Tip: kube context was not loaded. Check available contexts via:\n  kubectl config get-contexts"
+        }
         K8sError::ApiUnreachable { .. } => {
             "Tip: Kubernetes API is unreachable. Check context/cluster:\n  kubectl config current-context\n  kubectl cluster-info"
         }
         K8sError::DiscoveryRun { .. } => {
             "Tip: discovery failed. Verify API groups/resources visibility:\n  kubectl api-resources"
         }
+        K8sError::TlsError { .. } => {
+            "Tip: TLS handshake with the API server failed. Check the CA bundle and server name in kubeconfig:\n  kubectl config view --minify\nFor dev clusters with self-signed certs, retry with `--insecure-skip-tls-verify`."
+        }
         K8sError::RequestTimeout { .. } => {
             "Tip: request timed out. Check cluster/API latency and retry."
         }
         K8sError::ListFailed { .. } => {
             "Tip: list request failed. Check resource name, RBAC, and API server response details."
         }
+        K8sError::PaginationUnsupported { .. } => {
+            "Tip: API server rejected paginated list; kubiq retries once without `limit`/`continue`."
+        }
         K8sError::SelectorRejected { .. } => {
             "Tip: API server rejected selectors; kubiq can retry without selectors and continue with client-side filtering."
         }
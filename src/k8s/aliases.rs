@@ -0,0 +1,144 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::error::K8sError;
+
+pub type ResourceAliases = BTreeMap<String, String>;
+
+/// `$XDG_CONFIG_HOME/kubiq/aliases.toml`, иначе `~/.config/kubiq/aliases.toml`.
+pub fn aliases_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("kubiq").join("aliases.toml"))
+}
+
+pub fn load_aliases() -> Result<ResourceAliases, K8sError> {
+    let Some(path) = aliases_path() else {
+        return Ok(ResourceAliases::new());
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(ResourceAliases::new());
+        }
+        Err(error) => {
+            return Err(K8sError::AliasConfig {
+                path: path.display().to_string(),
+                message: error.to_string(),
+            });
+        }
+    };
+
+    parse_aliases(&content).map_err(|message| K8sError::AliasConfig {
+        path: path.display().to_string(),
+        message,
+    })
+}
+
+/// Разбирает подмножество TOML: строки `alias = "resource.group"`,
+/// пустые строки и комментарии `#`.
+pub fn parse_aliases(content: &str) -> Result<ResourceAliases, String> {
+    let mut aliases = ResourceAliases::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_number = index + 1;
+        let Some((alias, target)) = line.split_once('=') else {
+            return Err(format!(
+                "line {line_number}: expected `alias = \"resource.group\"`"
+            ));
+        };
+
+        let alias = unquote(alias.trim()).to_ascii_lowercase();
+        let target = unquote(strip_trailing_comment(target.trim()))
+            .trim()
+            .to_ascii_lowercase();
+        if alias.is_empty() || target.is_empty() {
+            return Err(format!(
+                "line {line_number}: alias and target must not be empty"
+            ));
+        }
+        if aliases.insert(alias.clone(), target).is_some() {
+            return Err(format!("line {line_number}: duplicate alias `{alias}`"));
+        }
+    }
+    Ok(aliases)
+}
+
+pub fn expand_alias<'a>(resource: &'a str, aliases: &'a ResourceAliases) -> &'a str {
+    aliases.get(resource).map_or(resource, String::as_str)
+}
+
+fn strip_trailing_comment(value: &str) -> &str {
+    if let Some(quoted) = value.strip_prefix('"') {
+        return match quoted.find('"') {
+            Some(end) => &value[..end + 2],
+            None => value,
+        };
+    }
+    value
+        .split_once('#')
+        .map_or(value, |(value, _)| value)
+        .trim()
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::k8s::resolution::{ResolutionCandidate, ResolutionMatch, trace_resolution};
+
+    use super::{expand_alias, parse_aliases};
+
+    #[test]
+    fn parses_aliases_with_comments_and_quotes() {
+        let aliases = parse_aliases(
+            "# team aliases\nwg = \"widgets.example.com\"  # widgets\n\"GW\" = gateways.networking.k8s.io\n",
+        )
+        .expect("aliases must parse");
+
+        assert_eq!(expand_alias("wg", &aliases), "widgets.example.com");
+        assert_eq!(expand_alias("gw", &aliases), "gateways.networking.k8s.io");
+        assert_eq!(expand_alias("pods", &aliases), "pods");
+    }
+
+    #[test]
+    fn rejects_malformed_alias_lines() {
+        assert!(parse_aliases("wg widgets.example.com").is_err());
+        assert!(parse_aliases("wg = \"\"").is_err());
+        assert!(parse_aliases("wg = a.b\nwg = c.d").is_err());
+    }
+
+    #[test]
+    fn alias_resolves_to_configured_group_via_discovery() {
+        let aliases = parse_aliases("wg = \"widgets.example.com\"").expect("aliases must parse");
+        let candidate = |group: &str| ResolutionCandidate {
+            group: group.to_string(),
+            version: "v1".to_string(),
+            plural: "widgets".to_string(),
+            kind: "Widget".to_string(),
+            matched_by: None,
+        };
+
+        let trace = trace_resolution(
+            expand_alias("wg", &aliases),
+            [candidate("other.io"), candidate("example.com")],
+        );
+
+        let chosen = trace.chosen().expect("qualified alias must resolve");
+        assert_eq!(chosen.group_version(), "example.com/v1");
+        assert_eq!(chosen.matched_by, Some(ResolutionMatch::Qualified));
+        let resolved = chosen.to_api_resource();
+        assert_eq!(resolved.api_version, "example.com/v1");
+        assert_eq!(resolved.kind, "Widget");
+        assert_eq!(resolved.plural, "widgets");
+    }
+}
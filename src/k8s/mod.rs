@@ -1,24 +1,35 @@
+pub mod aliases;
 pub mod planner;
+pub mod resolution;
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     future::Future,
-    sync::{OnceLock, RwLock},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex, OnceLock, RwLock,
+        atomic::{AtomicUsize, Ordering},
+    },
     time::{Duration, Instant},
 };
 
+use futures::{StreamExt, TryStreamExt, stream};
+use http::{HeaderMap, HeaderName, HeaderValue};
 use kube::{
     Client,
-    api::{Api, DynamicObject, ListParams},
-    config::Config,
-    core::{ApiResource, GroupVersionKind},
-    discovery,
+    api::{Api, DynamicObject, ListParams, ObjectList},
+    client::ClientBuilder,
+    config::{Config, KubeConfigOptions, Kubeconfig, KubeconfigError},
+    core::ApiResource,
+    discovery::{self, ApiCapabilities, Scope},
+    error::DiscoveryError,
 };
 use serde_json::Value;
 use tokio::{
     runtime::Runtime,
     time::{sleep, timeout},
 };
+use tower::util::{MapRequestLayer, MapResponseLayer};
 
 use crate::{
     dynamic_object::DynamicObject as EngineObject,
@@ -28,11 +39,52 @@ use crate::{
 
 const LIST_PAGE_SIZE: u32 = 500;
 const MAX_LIST_PAGES: usize = 10_000;
+const SHARD_CONCURRENCY: usize = 8;
 const DISCOVERY_CACHE_TTL: Duration = Duration::from_secs(60);
 const RETRY_MAX_ATTEMPTS: usize = 3;
 const RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
 const RETRY_MAX_BACKOFF: Duration = Duration::from_millis(400);
+const DISCOVERY_MAX_BACKOFF: Duration = Duration::from_millis(1_600);
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const DISCOVERY_TIMEOUT: Duration = REQUEST_TIMEOUT;
+const DISCOVERY_STAGE: &str = "discovery";
+const RESTRICTED_HEADERS: &[&str] = &[
+    "authorization",
+    "connection",
+    "content-length",
+    "content-type",
+    "host",
+    "te",
+    "transfer-encoding",
+    "upgrade",
+];
+const RESTRICTED_HEADER_PREFIXES: &[&str] = &["impersonate-"];
+/// Фразы rustls/openssl в цепочке транспортной ошибки, указывающие на сбой TLS.
+const TLS_ERROR_MARKERS: &[&str] = &[
+    "invalid peer certificate",
+    "certificate not valid for name",
+    "certificate verify failed",
+    "self signed certificate",
+    "self-signed certificate",
+    "unknownissuer",
+    "notvalidforname",
+    "x509",
+    "tls handshake",
+    "ssl routines",
+];
+/// Переменная окружения с namespace по умолчанию для текущего shell.
+const NAMESPACE_ENV: &str = "KUBIQ_NAMESPACE";
+/// Фразы в тексте 400/405, которыми API отказывает в `limit`/`continue`.
+const PAGINATION_ERROR_MARKERS: &[&str] = &[
+    "pagination is not supported",
+    "paging is not supported",
+    "limit is not supported",
+    "continue is not supported",
+    "unsupported parameter: limit",
+    "unsupported parameter: continue",
+    "unknown query parameter \"limit\"",
+    "unknown query parameter \"continue\"",
+];
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct DiscoveryCacheKey {
@@ -54,22 +106,42 @@ impl DiscoveryCacheKey {
         }
     }
 
+    /// Identity кластера включает имя контекста: разные контексты с одним
+    /// server URL (другой user или права) не делят кэш.
     fn from_config(
         config: &Config,
+        context: Option<&str>,
+        namespace: Option<&str>,
         resource: &str,
     ) -> Self {
-        let namespace = if config.default_namespace.is_empty() {
-            "default".to_string()
-        } else {
-            config.default_namespace.clone()
+        let namespace = namespace.map_or_else(|| config_namespace(config), str::to_string);
+        let cluster_identity = match context {
+            Some(context) => format!("{context}@{}", config.cluster_url),
+            None => config.cluster_url.to_string(),
         };
-        Self::new(config.cluster_url.to_string(), namespace, resource)
+        Self::new(cluster_identity, namespace, resource)
+    }
+}
+
+fn config_namespace(config: &Config) -> String {
+    if config.default_namespace.is_empty() {
+        "default".to_string()
+    } else {
+        config.default_namespace.clone()
     }
 }
 
+/// Результат discovery: `ApiResource` и то, namespaced ли ресурс
+/// (`ApiResource` сам scope не несет).
+#[derive(Clone, Debug, PartialEq)]
+struct ResolvedResource {
+    api_resource: ApiResource,
+    namespaced: bool,
+}
+
 #[derive(Clone, Debug)]
 struct DiscoveryCacheEntry {
-    api_resource: ApiResource,
+    resource: ResolvedResource,
     expires_at: Instant,
 }
 
@@ -85,20 +157,99 @@ struct RetryPolicy {
     max_attempts: usize,
     initial_backoff: Duration,
     max_backoff: Duration,
+    discovery_max_backoff: Duration,
     request_timeout: Duration,
+    discovery_timeout: Duration,
+}
+
+impl RetryPolicy {
+    fn timeout_for(&self, stage: &str) -> Duration {
+        if stage == DISCOVERY_STAGE {
+            self.discovery_timeout
+        } else {
+            self.request_timeout
+        }
+    }
+
+    fn max_backoff_for(&self, stage: &str) -> Duration {
+        if stage == DISCOVERY_STAGE {
+            self.discovery_max_backoff
+        } else {
+            self.max_backoff
+        }
+    }
 }
 
 const DEFAULT_RETRY_POLICY: RetryPolicy = RetryPolicy {
     max_attempts: RETRY_MAX_ATTEMPTS,
     initial_backoff: RETRY_INITIAL_BACKOFF,
     max_backoff: RETRY_MAX_BACKOFF,
+    discovery_max_backoff: DISCOVERY_MAX_BACKOFF,
     request_timeout: REQUEST_TIMEOUT,
+    discovery_timeout: DISCOVERY_TIMEOUT,
 };
 
+/// `limit` ограничивает число объектов всего списка, а не размер страницы:
+/// листинг останавливается, как только набрано `limit` объектов.
+/// `shard_by` содержит ключ label, по значениям которого список
+/// разбивается на параллельные запросы. `name_fan_out` — field selectors
+/// `metadata.name=<value>` веток `or`: по list-запросу на каждый.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ListQueryOptions {
     pub field_selector: Option<String>,
     pub label_selector: Option<String>,
+    pub limit: Option<usize>,
+    pub shard_by: Option<String>,
+    pub name_fan_out: Vec<String>,
+}
+
+/// `CountOnly` считает объекты по страницам и не конвертирует их в
+/// `DynamicObject` движка; результат несет только `ListResult::counted`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListMode {
+    #[default]
+    Objects,
+    CountOnly,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RetryOn {
+    Transport,
+    TransportAnd429,
+    #[default]
+    All,
+}
+
+impl RetryOn {
+    fn retries_api_status(self, code: u16) -> bool {
+        match self {
+            Self::Transport => false,
+            Self::TransportAnd429 => code == 429,
+            Self::All => code == 408 || code == 429 || code >= 500,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionOptions {
+    pub context: Option<String>,
+    pub insecure_skip_tls_verify: bool,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    pub retry_on: RetryOn,
+    pub discovery_timeout: Option<Duration>,
+    pub namespace: Option<String>,
+    pub all_namespaces: bool,
+    pub kubeconfig: Option<PathBuf>,
+    pub array_mode: path::ArrayMode,
+}
+
+impl ConnectionOptions {
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            discovery_timeout: self.discovery_timeout.unwrap_or(DISCOVERY_TIMEOUT),
+            ..DEFAULT_RETRY_POLICY
+        }
+    }
 }
 
 impl ListQueryOptions {
@@ -111,6 +262,22 @@ impl ListQueryOptions {
 pub struct ListResult {
     pub objects: Vec<EngineObject>,
     pub diagnostics: Vec<K8sDiagnostic>,
+    pub pages_fetched: usize,
+    pub truncated: bool,
+    pub estimated_total: Option<u64>,
+    pub resolution: Option<resolution::ResolutionTrace>,
+    pub counted: Option<usize>,
+    pub request_count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PagedItems<T> {
+    items: Vec<T>,
+    item_count: usize,
+    mode: ListMode,
+    pages_fetched: usize,
+    truncated: bool,
+    estimated_total: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -125,6 +292,20 @@ pub enum K8sDiagnostic {
         reason: RetryStopReason,
         final_error: RetryErrorKind,
     },
+    ServerWarning {
+        message: String,
+    },
+    NamespaceIgnored {
+        resource: String,
+        namespace: String,
+    },
+    AllNamespacesIgnored {
+        resource: String,
+    },
+    /// API отклонил `limit`/`continue`; список прочитан одним запросом.
+    PaginationUnsupported {
+        resource: String,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -161,96 +342,747 @@ pub fn list(
 pub async fn list_async(
     resource: &str,
     options: &ListQueryOptions,
+) -> Result<ListResult, K8sError> {
+    list_with_connection_async(resource, options, &ConnectionOptions::default()).await
+}
+
+pub async fn list_with_connection_async(
+    resource: &str,
+    options: &ListQueryOptions,
+    connection: &ConnectionOptions,
+) -> Result<ListResult, K8sError> {
+    list_resource(resource, options, connection, ListMode::Objects).await
+}
+
+/// Считает объекты без материализации. Если API отклонил selectors и
+/// список пришлось получить без них, объекты возвращаются как обычно,
+/// а `counted` остается пустым.
+pub async fn count_with_connection_async(
+    resource: &str,
+    options: &ListQueryOptions,
+    connection: &ConnectionOptions,
+) -> Result<ListResult, K8sError> {
+    list_resource(resource, options, connection, ListMode::CountOnly).await
+}
+
+async fn list_resource(
+    resource: &str,
+    options: &ListQueryOptions,
+    connection: &ConnectionOptions,
+    mode: ListMode,
 ) -> Result<ListResult, K8sError> {
     let resource = normalize_resource(resource);
     if resource.is_empty() {
         return Err(K8sError::EmptyResourceName);
     }
+    let aliases = aliases::load_aliases()?;
+    let resource = aliases::expand_alias(&resource, &aliases).to_string();
 
-    let config = Config::infer()
-        .await
-        .map_err(|source| K8sError::ConfigInfer {
-            source: boxed_error(source),
-        })?;
+    let mut config = load_config(connection).await?;
+    apply_connection_options(&mut config, connection);
 
-    let cache_key = DiscoveryCacheKey::from_config(&config, &resource);
-    let client = Client::try_from(config).map_err(|source| K8sError::ClientBuild {
+    let namespace = list_namespace(&config, connection, env_namespace());
+    let namespace = namespace.as_deref();
+    let cache_key = DiscoveryCacheKey::from_config(
+        &config,
+        connection.context.as_deref(),
+        namespace,
+        &resource,
+    );
+    let warnings = WarningRecorder::default();
+    let requests = RequestCounter::default();
+    let client = build_client(config, &warnings, &requests)?;
+
+    let retry_on = connection.retry_on;
+    let policy = connection.retry_policy();
+    let (mut resolved, mut resolution) =
+        resolve_api_resource_cached(&client, &cache_key, retry_on, &policy).await?;
+    let mut api = scoped_api(client.clone(), &resolved, namespace);
+
+    let (paged, mut diagnostics) =
+        match list_objects(&resource, &api, options, retry_on, mode).await {
+            Ok(result) => result,
+            Err(error) if should_retry_with_fresh_discovery(&error) => {
+                invalidate_discovery_cache(&cache_key);
+                let (fresh, fresh_resolution) =
+                    resolve_api_resource_cached(&client, &cache_key, retry_on, &policy).await?;
+                resolution = fresh_resolution;
+                api = scoped_api(client.clone(), &fresh, namespace);
+                resolved = fresh;
+                list_objects(&resource, &api, options, retry_on, mode).await?
+            }
+            Err(error) => return Err(error),
+        };
+    diagnostics.extend(namespace_scope_diagnostic(&resource, &resolved, connection));
+    diagnostics.extend(warnings.diagnostics());
+
+    let (pages_fetched, truncated, estimated_total) =
+        (paged.pages_fetched, paged.truncated, paged.estimated_total);
+    let (objects, counted) = materialize_objects(paged, |object| {
+        dynamic_to_engine_object(object, connection.array_mode)
+    });
+    Ok(ListResult {
+        objects,
+        diagnostics,
+        pages_fetched,
+        truncated,
+        estimated_total,
+        resolution,
+        counted,
+        request_count: requests.total(),
+    })
+}
+
+/// `--namespace`/`-A` для cluster-scoped ресурса ничего не меняют;
+/// `resolved` — итоговое разрешение, после refresh discovery, если он был.
+fn namespace_scope_diagnostic(
+    resource: &str,
+    resolved: &ResolvedResource,
+    connection: &ConnectionOptions,
+) -> Option<K8sDiagnostic> {
+    if resolved.namespaced {
+        return None;
+    }
+    if let Some(namespace) = &connection.namespace {
+        Some(K8sDiagnostic::NamespaceIgnored {
+            resource: resource.to_string(),
+            namespace: namespace.clone(),
+        })
+    } else if connection.all_namespaces {
+        Some(K8sDiagnostic::AllNamespacesIgnored {
+            resource: resource.to_string(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Копит сообщения из заголовков `Warning` всех ответов API
+/// (например, об устаревших версиях API) без повторов.
+#[derive(Clone, Debug, Default)]
+struct WarningRecorder {
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl WarningRecorder {
+    fn record(&self, headers: &HeaderMap) {
+        let Ok(mut messages) = self.messages.lock() else {
+            return;
+        };
+        let warnings = headers
+            .get_all(http::header::WARNING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(parse_warning_header);
+        for warning in warnings {
+            if !messages.contains(&warning) {
+                messages.push(warning);
+            }
+        }
+    }
+
+    fn diagnostics(&self) -> Vec<K8sDiagnostic> {
+        let Ok(messages) = self.messages.lock() else {
+            return Vec::new();
+        };
+        messages
+            .iter()
+            .map(|message| K8sDiagnostic::ServerWarning {
+                message: message.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Считает HTTP-запросы к API: discovery, страницы list и повторы.
+#[derive(Clone, Debug, Default)]
+struct RequestCounter {
+    count: Arc<AtomicUsize>,
+}
+
+impl RequestCounter {
+    fn record(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn total(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+fn build_client(
+    config: Config,
+    warnings: &WarningRecorder,
+    requests: &RequestCounter,
+) -> Result<Client, K8sError> {
+    let builder = ClientBuilder::try_from(config).map_err(|source| K8sError::ClientBuild {
         source: boxed_error(source),
     })?;
+    Ok(builder
+        .with_layer(&warning_layer(warnings.clone()))
+        .with_layer(&request_count_layer(requests.clone()))
+        .build())
+}
 
-    let mut api_resource = resolve_api_resource_cached(&client, &cache_key).await?;
-    let mut api: Api<DynamicObject> = Api::all_with(client.clone(), &api_resource);
+fn request_count_layer<B>(
+    counter: RequestCounter,
+) -> MapRequestLayer<impl FnMut(http::Request<B>) -> http::Request<B> + Clone> {
+    MapRequestLayer::new(move |request: http::Request<B>| {
+        counter.record();
+        request
+    })
+}
 
-    let (items, diagnostics) = match list_with_selector_fallback(&resource, &api, options).await {
-        Ok(result) => result,
-        Err(error) if should_retry_with_fresh_discovery(&error) => {
-            invalidate_discovery_cache(&cache_key);
-            api_resource = resolve_api_resource_cached(&client, &cache_key).await?;
-            api = Api::all_with(client.clone(), &api_resource);
-            list_with_selector_fallback(&resource, &api, options).await?
+fn warning_layer<B>(
+    recorder: WarningRecorder,
+) -> MapResponseLayer<impl FnOnce(http::Response<B>) -> http::Response<B> + Clone> {
+    MapResponseLayer::new(move |response: http::Response<B>| {
+        recorder.record(response.headers());
+        response
+    })
+}
+
+/// Разбирает `Warning: 299 - "text"`: возвращает `text` без кавычек
+/// и экранирования.
+fn parse_warning_header(raw: &str) -> Option<String> {
+    let (_code, rest) = raw.trim().split_once(' ')?;
+    let (_agent, text) = rest.split_once(' ')?;
+    let mut chars = text.trim_start().strip_prefix('"')?.chars();
+    let mut message = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => message.push(chars.next()?),
+            '"' => return Some(message),
+            _ => message.push(c),
         }
-        Err(error) => return Err(error),
+    }
+    None
+}
+
+async fn load_config(connection: &ConnectionOptions) -> Result<Config, K8sError> {
+    let context = connection.context.as_deref();
+    if let Some(path) = connection.kubeconfig.as_deref() {
+        let read_error = |source| K8sError::KubeconfigRead {
+            path: path.display().to_string(),
+            source: boxed_error(source),
+        };
+        let kubeconfig = Kubeconfig::read_from(path).map_err(read_error)?;
+        return config_from_kubeconfig(kubeconfig, context, read_error).await;
+    }
+
+    let Some(context) = context else {
+        return Config::infer()
+            .await
+            .map_err(|source| K8sError::ConfigInfer {
+                source: boxed_error(source),
+            });
     };
+    let context_error = |source| K8sError::ContextConfig {
+        context: context.to_string(),
+        source: boxed_error(source),
+    };
+    let kubeconfig = Kubeconfig::read().map_err(context_error)?;
+    config_from_kubeconfig(kubeconfig, Some(context), context_error).await
+}
 
-    Ok(ListResult {
-        objects: items.into_iter().map(dynamic_to_engine_object).collect(),
-        diagnostics,
+async fn config_from_kubeconfig(
+    kubeconfig: Kubeconfig,
+    context: Option<&str>,
+    load_error: impl FnOnce(KubeconfigError) -> K8sError,
+) -> Result<Config, K8sError> {
+    let options = match context {
+        Some(context) => {
+            ensure_context_exists(&kubeconfig, context)?;
+            kubeconfig_options(context)
+        }
+        None => KubeConfigOptions::default(),
+    };
+    Config::from_custom_kubeconfig(kubeconfig, &options)
+        .await
+        .map_err(load_error)
+}
+
+fn ensure_context_exists(kubeconfig: &Kubeconfig, context: &str) -> Result<(), K8sError> {
+    if kubeconfig
+        .contexts
+        .iter()
+        .any(|named| named.name == context)
+    {
+        return Ok(());
+    }
+    Err(K8sError::ContextNotFound {
+        context: context.to_string(),
+        available: kubeconfig
+            .contexts
+            .iter()
+            .map(|named| named.name.clone())
+            .collect(),
+    })
+}
+
+fn apply_connection_options(config: &mut Config, connection: &ConnectionOptions) {
+    if connection.insecure_skip_tls_verify {
+        config.accept_invalid_certs = true;
+    }
+    config.headers.extend(connection.headers.iter().cloned());
+}
+
+pub fn parse_header(raw: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let Some((name, value)) = raw.split_once(':') else {
+        return Err(format!("header `{raw}` must have the form `Key: Value`"));
+    };
+
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|_| format!("invalid header name in `{raw}`"))?;
+    if RESTRICTED_HEADERS.contains(&name.as_str())
+        || RESTRICTED_HEADER_PREFIXES
+            .iter()
+            .any(|prefix| name.as_str().starts_with(prefix))
+    {
+        return Err(format!(
+            "header `{name}` is restricted and cannot be overridden"
+        ));
+    }
+
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|_| format!("invalid header value in `{raw}`"))?;
+    Ok((name, value))
+}
+
+fn kubeconfig_options(context: &str) -> KubeConfigOptions {
+    KubeConfigOptions {
+        context: Some(context.to_string()),
+        ..KubeConfigOptions::default()
+    }
+}
+
+/// Namespace для list: явный `--namespace`, иначе `$KUBIQ_NAMESPACE`,
+/// иначе namespace текущего контекста kubeconfig (`Config::infer`);
+/// `None` — list по всем namespace (`--all-namespaces`).
+fn list_namespace(
+    config: &Config,
+    connection: &ConnectionOptions,
+    env_namespace: Option<String>,
+) -> Option<String> {
+    if connection.all_namespaces {
+        return None;
+    }
+    Some(
+        connection
+            .namespace
+            .clone()
+            .or(env_namespace)
+            .unwrap_or_else(|| config_namespace(config)),
+    )
+}
+
+fn env_namespace() -> Option<String> {
+    std::env::var(NAMESPACE_ENV)
+        .ok()
+        .filter(|namespace| !namespace.is_empty())
+}
+
+/// Namespace применяется только к namespaced ресурсам; cluster-scoped
+/// ресурсы всегда читаются целиком.
+fn scoped_api(
+    client: Client,
+    resolved: &ResolvedResource,
+    namespace: Option<&str>,
+) -> Api<DynamicObject> {
+    match namespace {
+        Some(namespace) if resolved.namespaced => {
+            Api::namespaced_with(client, namespace, &resolved.api_resource)
+        }
+        _ => Api::all_with(client, &resolved.api_resource),
+    }
+}
+
+fn materialize_objects<T>(
+    paged: PagedItems<T>,
+    convert: impl FnMut(T) -> EngineObject,
+) -> (Vec<EngineObject>, Option<usize>) {
+    match paged.mode {
+        ListMode::Objects => (paged.items.into_iter().map(convert).collect(), None),
+        ListMode::CountOnly => (Vec::new(), Some(paged.item_count)),
+    }
+}
+
+/// Без `shard_by` это обычный список. С ним сначала дешевым metadata-списком
+/// собираются значения label, затем по одному списку на значение выполняются
+/// параллельно; объекты без label забирает последний шард `!key`.
+/// `name_fan_out` выполняется так же параллельно, по списку на имя.
+async fn list_objects(
+    resource: &str,
+    api: &Api<DynamicObject>,
+    options: &ListQueryOptions,
+    retry_on: RetryOn,
+    mode: ListMode,
+) -> Result<(PagedItems<DynamicObject>, Vec<K8sDiagnostic>), K8sError> {
+    if !options.name_fan_out.is_empty() {
+        let branches = plan_name_branches(options);
+        let results = stream::iter(&branches)
+            .map(|branch| {
+                list_with_selector_fallback(resource, api, branch, retry_on, ListMode::Objects)
+            })
+            .buffered(SHARD_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        return Ok(merge_shards(results, 0));
+    }
+    let Some(key) = options.shard_by.as_deref() else {
+        return list_with_selector_fallback(resource, api, options, retry_on, mode).await;
+    };
+    let (values, discovery_pages) =
+        match discover_shard_values(resource, api, options, key, retry_on).await {
+            Ok(discovered) => discovered,
+            Err(error) if should_retry_without_selectors(&error) => {
+                return list_with_selector_fallback(resource, api, options, retry_on, mode).await;
+            }
+            Err(error) => return Err(error),
+        };
+    let shards = plan_shards(key, values, options);
+    let results = stream::iter(&shards)
+        .map(|shard| list_with_selector_fallback(resource, api, shard, retry_on, ListMode::Objects))
+        .buffered(SHARD_CONCURRENCY)
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(merge_shards(results, discovery_pages))
+}
+
+async fn discover_shard_values(
+    resource: &str,
+    api: &Api<DynamicObject>,
+    options: &ListQueryOptions,
+    key: &str,
+    retry_on: RetryOn,
+) -> Result<(Vec<String>, usize), K8sError> {
+    let discovery = ListQueryOptions {
+        label_selector: Some(join_label_selector(options.label_selector.as_deref(), key)),
+        limit: None,
+        shard_by: None,
+        name_fan_out: Vec::new(),
+        ..options.clone()
+    };
+    let discovery = &discovery;
+    let paged = collect_pages(resource, ListMode::Objects, None, |continue_token| {
+        let params = build_list_params(LIST_PAGE_SIZE, continue_token.as_deref(), discovery);
+        async move {
+            run_with_retry(
+                "list",
+                &DEFAULT_RETRY_POLICY,
+                || api.list_metadata(&params),
+                |source| {
+                    map_list_error(
+                        resource,
+                        discovery.has_selectors(),
+                        sends_pagination(&params),
+                        source,
+                    )
+                },
+                |source| is_retryable_kube_error(source, retry_on),
+            )
+            .await
+        }
     })
+    .await?;
+    let values = paged
+        .items
+        .into_iter()
+        .filter_map(|item| item.metadata.labels?.remove(key))
+        .collect();
+    Ok((values, paged.pages_fetched))
+}
+
+/// Значения сортируются и дедуплицируются, поэтому порядок шардов и
+/// слияния результатов детерминирован.
+fn plan_shards(
+    key: &str,
+    values: impl IntoIterator<Item = String>,
+    base: &ListQueryOptions,
+) -> Vec<ListQueryOptions> {
+    let values: BTreeSet<String> = values.into_iter().collect();
+    values
+        .iter()
+        .map(|value| format!("{key}={value}"))
+        .chain([format!("!{key}")])
+        .map(|requirement| ListQueryOptions {
+            label_selector: Some(join_label_selector(
+                base.label_selector.as_deref(),
+                &requirement,
+            )),
+            limit: None,
+            shard_by: None,
+            name_fan_out: Vec::new(),
+            ..base.clone()
+        })
+        .collect()
+}
+
+/// Ветка на каждый selector из `name_fan_out`, дописанный к общему
+/// `field_selector` через `,`.
+fn plan_name_branches(base: &ListQueryOptions) -> Vec<ListQueryOptions> {
+    base.name_fan_out
+        .iter()
+        .map(|selector| ListQueryOptions {
+            field_selector: Some(match base.field_selector.as_deref() {
+                Some(common) => format!("{common},{selector}"),
+                None => selector.clone(),
+            }),
+            limit: None,
+            shard_by: None,
+            name_fan_out: Vec::new(),
+            ..base.clone()
+        })
+        .collect()
+}
+
+fn join_label_selector(base: Option<&str>, requirement: &str) -> String {
+    match base {
+        Some(base) => format!("{base},{requirement}"),
+        None => requirement.to_string(),
+    }
+}
+
+/// Объект, сменивший label между запросами, или полный список после
+/// selector fallback попадает в несколько шардов; дубликаты отсекаются по
+/// uid, а без uid — по `namespace/name`.
+fn merge_shards(
+    results: Vec<(PagedItems<DynamicObject>, Vec<K8sDiagnostic>)>,
+    discovery_pages: usize,
+) -> (PagedItems<DynamicObject>, Vec<K8sDiagnostic>) {
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut pages_fetched = discovery_pages;
+    let mut truncated = false;
+    for (paged, shard_diagnostics) in results {
+        pages_fetched += paged.pages_fetched;
+        truncated |= paged.truncated;
+        items.extend(paged.items.into_iter().filter(|item| {
+            let identity = match (&item.metadata.uid, &item.metadata.name) {
+                (Some(uid), _) => uid.clone(),
+                (None, Some(name)) => {
+                    format!(
+                        "{}/{name}",
+                        item.metadata.namespace.as_deref().unwrap_or_default()
+                    )
+                }
+                (None, None) => return true,
+            };
+            seen.insert(identity)
+        }));
+        for diagnostic in shard_diagnostics {
+            if !diagnostics.contains(&diagnostic) {
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+    let paged = PagedItems {
+        item_count: items.len(),
+        items,
+        mode: ListMode::Objects,
+        pages_fetched,
+        truncated,
+        estimated_total: None,
+    };
+    (paged, diagnostics)
+}
+
+/// `--shard-by` принимает путь `metadata.labels.<key>` и возвращает ключ label.
+pub fn parse_shard_by(raw: &str) -> Result<String, String> {
+    raw.strip_prefix("metadata.labels.")
+        .filter(|key| planner::is_label_key_safe(key) && !key.contains(['=', '!']))
+        .map(str::to_string)
+        .ok_or_else(|| format!("`--shard-by` expects `metadata.labels.<key>`, got `{raw}`"))
 }
 
 async fn list_with_selector_fallback(
     resource: &str,
     api: &Api<DynamicObject>,
     options: &ListQueryOptions,
-) -> Result<(Vec<DynamicObject>, Vec<K8sDiagnostic>), K8sError> {
+    retry_on: RetryOn,
+    mode: ListMode,
+) -> Result<(PagedItems<DynamicObject>, Vec<K8sDiagnostic>), K8sError> {
     let mut diagnostics = Vec::new();
-    let items = match list_pages(resource, api, options).await {
-        Ok(items) => items,
+    let paged = match list_with_pagination_fallback(
+        resource,
+        api,
+        options,
+        retry_on,
+        mode,
+        &mut diagnostics,
+    )
+    .await
+    {
+        Ok(paged) => paged,
         Err(error) if options.has_selectors() && should_retry_without_selectors(&error) => {
             diagnostics.push(K8sDiagnostic::SelectorFallback {
                 reason: SelectorFallbackReason::ApiRejectedBadRequest,
                 attempted: options.clone(),
             });
-            list_pages(resource, api, &ListQueryOptions::default()).await?
+            let unfiltered = ListQueryOptions::default();
+            list_with_pagination_fallback(
+                resource,
+                api,
+                &unfiltered,
+                retry_on,
+                ListMode::Objects,
+                &mut diagnostics,
+            )
+            .await?
         }
         Err(error) => return Err(error),
     };
 
-    Ok((items, diagnostics))
+    Ok((paged, diagnostics))
+}
+
+/// Если API отказал в `limit`/`continue`, list повторяется одним запросом;
+/// после первого отказа следующие list сразу идут без пагинации.
+async fn list_with_pagination_fallback(
+    resource: &str,
+    api: &Api<DynamicObject>,
+    options: &ListQueryOptions,
+    retry_on: RetryOn,
+    mode: ListMode,
+    diagnostics: &mut Vec<K8sDiagnostic>,
+) -> Result<PagedItems<DynamicObject>, K8sError> {
+    let unsupported = K8sDiagnostic::PaginationUnsupported {
+        resource: resource.to_string(),
+    };
+    if diagnostics.contains(&unsupported) {
+        return list_unpaginated(resource, api, options, retry_on, mode).await;
+    }
+    match list_pages(resource, api, options, retry_on, mode).await {
+        Err(error) if should_retry_without_pagination(&error) => {
+            diagnostics.push(unsupported);
+            list_unpaginated(resource, api, options, retry_on, mode).await
+        }
+        result => result,
+    }
 }
 
 async fn list_pages(
     resource: &str,
     api: &Api<DynamicObject>,
     options: &ListQueryOptions,
-) -> Result<Vec<DynamicObject>, K8sError> {
+    retry_on: RetryOn,
+    mode: ListMode,
+) -> Result<PagedItems<DynamicObject>, K8sError> {
+    let page_size = options
+        .limit
+        .and_then(|limit| u32::try_from(limit).ok())
+        .map_or(LIST_PAGE_SIZE, |limit| limit.min(LIST_PAGE_SIZE));
+    collect_pages(resource, mode, options.limit, |continue_token| {
+        let params = build_list_params(page_size, continue_token.as_deref(), options);
+        async move {
+            run_with_retry(
+                "list",
+                &DEFAULT_RETRY_POLICY,
+                || api.list(&params),
+                |source| {
+                    map_list_error(
+                        resource,
+                        options.has_selectors(),
+                        sends_pagination(&params),
+                        source,
+                    )
+                },
+                |source| is_retryable_kube_error(source, retry_on),
+            )
+            .await
+        }
+    })
+    .await
+}
+
+/// Тот же list без `limit` и `continue` для API, которые не умеют
+/// пагинацию: весь список приходит одним ответом.
+async fn list_unpaginated(
+    resource: &str,
+    api: &Api<DynamicObject>,
+    options: &ListQueryOptions,
+    retry_on: RetryOn,
+    mode: ListMode,
+) -> Result<PagedItems<DynamicObject>, K8sError> {
+    let mut params = build_list_params(LIST_PAGE_SIZE, None, options);
+    params.limit = None;
+    collect_pages(resource, mode, options.limit, |_continue_token| {
+        let params = params.clone();
+        async move {
+            run_with_retry(
+                "list",
+                &DEFAULT_RETRY_POLICY,
+                || api.list(&params),
+                |source| {
+                    map_list_error(
+                        resource,
+                        options.has_selectors(),
+                        sends_pagination(&params),
+                        source,
+                    )
+                },
+                |source| is_retryable_kube_error(source, retry_on),
+            )
+            .await
+        }
+    })
+    .await
+}
+
+async fn collect_pages<T, Fetch, Fut>(
+    resource: &str,
+    mode: ListMode,
+    limit: Option<usize>,
+    mut fetch_page: Fetch,
+) -> Result<PagedItems<T>, K8sError>
+where
+    T: Clone,
+    Fetch: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<ObjectList<T>, K8sError>>,
+{
     let mut all_items = Vec::new();
+    let mut item_count: usize = 0;
     let mut continue_token: Option<String> = None;
     let mut page_count: usize = 0;
+    let mut estimated_total = None;
 
     loop {
         page_count += 1;
         ensure_page_limit(resource, page_count)?;
 
-        let params = build_list_params(LIST_PAGE_SIZE, continue_token.as_deref(), options);
-        let mut page = run_with_retry(
-            "list",
-            &DEFAULT_RETRY_POLICY,
-            || api.list(&params),
-            |source| map_list_error(resource, options.has_selectors(), source),
-            is_retryable_kube_error,
-        )
-        .await?;
+        let mut page = fetch_page(continue_token.clone()).await?;
+        if page_count == 1 {
+            estimated_total = estimate_total_items(&page);
+        }
 
-        all_items.append(&mut page.items);
+        if let Some(limit) = limit {
+            page.items.truncate(limit.saturating_sub(item_count));
+        }
+        item_count += page.items.len();
+        if mode == ListMode::Objects {
+            all_items.append(&mut page.items);
+        }
         continue_token =
             next_continue_token(resource, continue_token.as_deref(), page.metadata.continue_)?;
-        if continue_token.is_none() {
+        if continue_token.is_none() || limit.is_some_and(|limit| item_count >= limit) {
             break;
         }
     }
 
-    Ok(all_items)
+    Ok(PagedItems {
+        items: all_items,
+        item_count,
+        mode,
+        pages_fetched: page_count,
+        truncated: continue_token.is_some(),
+        estimated_total,
+    })
+}
+
+fn estimate_total_items<T: Clone>(first_page: &ObjectList<T>) -> Option<u64> {
+    let remaining = u64::try_from(first_page.metadata.remaining_item_count?).ok()?;
+    Some(first_page.items.len() as u64 + remaining)
 }
 
 fn build_list_params(
@@ -271,13 +1103,10 @@ fn build_list_params(
     params
 }
 
-fn retry_backoff_for_attempt(
-    policy: &RetryPolicy,
-    attempt: usize,
-) -> Duration {
+fn retry_backoff_for_attempt(policy: &RetryPolicy, stage: &str, attempt: usize) -> Duration {
     let shift = attempt.saturating_sub(1).min(8);
     let base_millis = policy.initial_backoff.as_millis() as u64;
-    let cap_millis = policy.max_backoff.as_millis() as u64;
+    let cap_millis = policy.max_backoff_for(stage).as_millis() as u64;
     let next_millis = base_millis.saturating_mul(1_u64 << shift).min(cap_millis);
     Duration::from_millis(next_millis)
 }
@@ -290,14 +1119,16 @@ fn retry_error_kind(error: &K8sError) -> RetryErrorKind {
         K8sError::ResourceResolutionStale { .. } => RetryErrorKind::ResourceResolutionStale,
         K8sError::ListFailed { .. } => RetryErrorKind::ListFailed,
         K8sError::DiscoveryRun { .. } => RetryErrorKind::DiscoveryRun,
+        K8sError::TlsError { .. } => RetryErrorKind::Tls,
         _ => RetryErrorKind::Other,
     }
 }
 
-fn is_retryable_kube_error(source: &kube::Error) -> bool {
+fn is_retryable_kube_error(source: &kube::Error, retry_on: RetryOn) -> bool {
     match source {
+        _ if is_tls_error(source) => false,
         kube::Error::Service(_) | kube::Error::HyperError(_) => true,
-        kube::Error::Api(error) => is_api_transient(error),
+        kube::Error::Api(error) => retry_on.retries_api_status(error.code),
         _ => false,
     }
 }
@@ -318,7 +1149,7 @@ where
     let mut attempt: usize = 1;
 
     loop {
-        let result = timeout(policy.request_timeout, operation()).await;
+        let result = timeout(policy.timeout_for(stage), operation()).await;
         match result {
             Ok(Ok(value)) => return Ok(value),
             Ok(Err(source)) => {
@@ -326,7 +1157,7 @@ where
                 let mapped = map_error(source);
 
                 if retryable && attempt < policy.max_attempts {
-                    sleep(retry_backoff_for_attempt(policy, attempt)).await;
+                    sleep(retry_backoff_for_attempt(policy, stage, attempt)).await;
                     attempt += 1;
                     continue;
                 }
@@ -356,12 +1187,12 @@ where
             Err(source) => {
                 let timed_out = K8sError::RequestTimeout {
                     stage,
-                    timeout_ms: policy.request_timeout.as_millis() as u64,
+                    timeout_ms: policy.timeout_for(stage).as_millis() as u64,
                     source,
                 };
 
                 if attempt < policy.max_attempts {
-                    sleep(retry_backoff_for_attempt(policy, attempt)).await;
+                    sleep(retry_backoff_for_attempt(policy, stage, attempt)).await;
                     attempt += 1;
                     continue;
                 }
@@ -384,6 +1215,7 @@ enum ListErrorClass {
     ResourceResolutionStale,
     ApiTransient,
     ApiUnreachable,
+    Tls,
     Other,
 }
 
@@ -399,11 +1231,43 @@ fn classify_list_error(
             ListErrorClass::ResourceResolutionStale
         }
         kube::Error::Api(error) if is_api_transient(error) => ListErrorClass::ApiTransient,
+        _ if is_tls_error(source) => ListErrorClass::Tls,
         kube::Error::Service(_) | kube::Error::HyperError(_) => ListErrorClass::ApiUnreachable,
         _ => ListErrorClass::Other,
     }
 }
 
+/// Текст просматривается только у транспортных ошибок: ответ API
+/// (`kube::Error::Api`) про `certificates` или CSR — не сбой TLS.
+fn is_tls_error(source: &kube::Error) -> bool {
+    match source {
+        kube::Error::RustlsTls(_) => return true,
+        kube::Error::Service(_) | kube::Error::HyperError(_) => {}
+        _ => return false,
+    }
+
+    let mut current: Option<&(dyn std::error::Error + 'static)> = Some(source);
+    while let Some(error) = current {
+        let message = error.to_string().to_ascii_lowercase();
+        if TLS_ERROR_MARKERS
+            .iter()
+            .any(|marker| message.contains(marker))
+        {
+            return true;
+        }
+        current = error.source();
+    }
+    false
+}
+
+fn is_pagination_rejection(error: &kube::error::ErrorResponse) -> bool {
+    let message = error.message.to_ascii_lowercase();
+    matches!(error.code, 400 | 405)
+        && PAGINATION_ERROR_MARKERS
+            .iter()
+            .any(|marker| message.contains(marker))
+}
+
 fn is_selector_rejection(error: &kube::error::ErrorResponse) -> bool {
     error.code == 400
 }
@@ -413,14 +1277,26 @@ fn is_resource_resolution_stale(error: &kube::error::ErrorResponse) -> bool {
 }
 
 fn is_api_transient(error: &kube::error::ErrorResponse) -> bool {
-    error.code == 408 || error.code == 429 || error.code >= 500
+    RetryOn::All.retries_api_status(error.code)
 }
 
+/// Отказ в пагинации распознается, только если запрос действительно
+/// отправил `limit` или `continue`.
 fn map_list_error(
     resource: &str,
     had_selectors: bool,
+    had_pagination: bool,
     source: kube::Error,
 ) -> K8sError {
+    if had_pagination
+        && let kube::Error::Api(error) = &source
+        && is_pagination_rejection(error)
+    {
+        return K8sError::PaginationUnsupported {
+            resource: resource.to_string(),
+            source: boxed_error(source),
+        };
+    }
     match classify_list_error(&source, had_selectors) {
         ListErrorClass::SelectorRejected => K8sError::SelectorRejected {
             resource: resource.to_string(),
@@ -438,6 +1314,10 @@ fn map_list_error(
             stage: "list",
             source: boxed_error(source),
         },
+        ListErrorClass::Tls => K8sError::TlsError {
+            stage: "list",
+            source: boxed_error(source),
+        },
         ListErrorClass::Other => K8sError::ListFailed {
             resource: resource.to_string(),
             source: boxed_error(source),
@@ -445,6 +1325,14 @@ fn map_list_error(
     }
 }
 
+fn sends_pagination(params: &ListParams) -> bool {
+    params.limit.is_some() || params.continue_token.is_some()
+}
+
+fn should_retry_without_pagination(error: &K8sError) -> bool {
+    matches!(error, K8sError::PaginationUnsupported { .. })
+}
+
 fn should_retry_without_selectors(error: &K8sError) -> bool {
     matches!(error, K8sError::SelectorRejected { .. })
 }
@@ -496,50 +1384,95 @@ fn next_continue_token(
 async fn resolve_api_resource(
     client: &Client,
     resource: &str,
-) -> Result<ApiResource, K8sError> {
+    retry_on: RetryOn,
+    policy: &RetryPolicy,
+) -> Result<(ResolvedResource, resolution::ResolutionTrace), K8sError> {
+    let resources = discover_resources(client, resource, retry_on, policy).await?;
+    let candidates = resources
+        .iter()
+        .map(|(api_resource, _)| resolution::ResolutionCandidate::from_api_resource(api_resource));
+    let trace = resolution::trace_resolution(resource, candidates);
+    let Some(chosen) = trace.chosen() else {
+        return Err(K8sError::ResourceNotFound {
+            resource: resource.to_string(),
+        });
+    };
+    let namespaced = resources
+        .iter()
+        .find(|(api_resource, _)| {
+            api_resource.group == chosen.group
+                && api_resource.version == chosen.version
+                && api_resource.plural == chosen.plural
+        })
+        .is_none_or(|(_, capabilities)| capabilities.scope == Scope::Namespaced);
+
+    Ok((
+        ResolvedResource {
+            api_resource: chosen.to_api_resource(),
+            namespaced,
+        },
+        trace,
+    ))
+}
+
+/// Для `plural.group` опрашивается только эта группа; если API такой
+/// группы не знает, выполняется полный discovery.
+async fn discover_resources(
+    client: &Client,
+    resource: &str,
+    retry_on: RetryOn,
+    policy: &RetryPolicy,
+) -> Result<Vec<(ApiResource, ApiCapabilities)>, K8sError> {
+    if let Some(group) = resolution::qualified_group(resource) {
+        let narrowed = run_with_retry(
+            DISCOVERY_STAGE,
+            policy,
+            || async {
+                match discovery::group(client, &group).await {
+                    Ok(api_group) => Ok(Some(api_group)),
+                    Err(kube::Error::Discovery(DiscoveryError::MissingApiGroup(_))) => Ok(None),
+                    Err(error) => Err(error),
+                }
+            },
+            map_discovery_error,
+            |source| is_retryable_kube_error(source, retry_on),
+        )
+        .await?;
+        if let Some(api_group) = narrowed {
+            return Ok(api_group.recommended_resources());
+        }
+    }
+
     let discovery = run_with_retry(
-        "discovery",
-        &DEFAULT_RETRY_POLICY,
+        DISCOVERY_STAGE,
+        policy,
         || discovery::Discovery::new(client.clone()).run(),
         map_discovery_error,
-        is_retryable_kube_error,
+        |source| is_retryable_kube_error(source, retry_on),
     )
     .await?;
-
-    for group in discovery.groups() {
-        for (api_resource, capabilities) in group.recommended_resources() {
-            if api_resource.plural.eq_ignore_ascii_case(resource) {
-                let gvk = GroupVersionKind::gvk(
-                    &api_resource.group,
-                    &api_resource.version,
-                    &api_resource.kind,
-                );
-                let resolved = ApiResource::from_gvk_with_plural(&gvk, &api_resource.plural);
-                let _ = capabilities;
-                return Ok(resolved);
-            }
-        }
-    }
-
-    Err(K8sError::ResourceNotFound {
-        resource: resource.to_string(),
-    })
+    Ok(discovery
+        .groups()
+        .flat_map(|group| group.recommended_resources())
+        .collect())
 }
 
 async fn resolve_api_resource_cached(
     client: &Client,
     key: &DiscoveryCacheKey,
-) -> Result<ApiResource, K8sError> {
-    if let Some(api_resource) = cache_lookup(key) {
-        return Ok(api_resource);
+    retry_on: RetryOn,
+    policy: &RetryPolicy,
+) -> Result<(ResolvedResource, Option<resolution::ResolutionTrace>), K8sError> {
+    if let Some(resolved) = cache_lookup(key) {
+        return Ok((resolved, None));
     }
 
-    let api_resource = resolve_api_resource(client, &key.resource).await?;
-    cache_insert(key.clone(), api_resource.clone(), DISCOVERY_CACHE_TTL);
-    Ok(api_resource)
+    let (resolved, trace) = resolve_api_resource(client, &key.resource, retry_on, policy).await?;
+    cache_insert(key.clone(), resolved.clone(), DISCOVERY_CACHE_TTL);
+    Ok((resolved, Some(trace)))
 }
 
-fn cache_lookup(key: &DiscoveryCacheKey) -> Option<ApiResource> {
+fn cache_lookup(key: &DiscoveryCacheKey) -> Option<ResolvedResource> {
     let now = Instant::now();
     {
         let cache = discovery_cache()
@@ -547,7 +1480,7 @@ fn cache_lookup(key: &DiscoveryCacheKey) -> Option<ApiResource> {
             .expect("discovery cache read lock must not be poisoned");
         if let Some(entry) = cache.get(key) {
             if now <= entry.expires_at {
-                return Some(entry.api_resource.clone());
+                return Some(entry.resource.clone());
             }
         } else {
             return None;
@@ -566,13 +1499,9 @@ fn cache_lookup(key: &DiscoveryCacheKey) -> Option<ApiResource> {
     None
 }
 
-fn cache_insert(
-    key: DiscoveryCacheKey,
-    api_resource: ApiResource,
-    ttl: Duration,
-) {
+fn cache_insert(key: DiscoveryCacheKey, resource: ResolvedResource, ttl: Duration) {
     let entry = DiscoveryCacheEntry {
-        api_resource,
+        resource,
         expires_at: Instant::now() + ttl,
     };
     discovery_cache()
@@ -600,13 +1529,17 @@ fn map_discovery_error(source: kube::Error) -> K8sError {
                 source: boxed_error(source),
             }
         }
+        ListErrorClass::Tls => K8sError::TlsError {
+            stage: "discovery",
+            source: boxed_error(source),
+        },
         _ => K8sError::DiscoveryRun {
             source: boxed_error(source),
         },
     }
 }
 
-fn dynamic_to_engine_object(object: DynamicObject) -> EngineObject {
+fn dynamic_to_engine_object(object: DynamicObject, array_mode: path::ArrayMode) -> EngineObject {
     let mut root = serde_json::Map::new();
 
     root.insert(
@@ -622,7 +1555,7 @@ fn dynamic_to_engine_object(object: DynamicObject) -> EngineObject {
         root.insert("data".to_string(), object.data);
     }
 
-    let fields = path::flatten_json_to_fields(&Value::Object(root));
+    let fields = path::flatten_json_with_array_mode(&Value::Object(root), array_mode);
     EngineObject { fields }
 }
 
@@ -641,13 +1574,19 @@ mod tests {
     use serde_json::{Value, json};
 
     use super::{
-        DiscoveryCacheEntry, DiscoveryCacheKey, K8sDiagnostic, ListErrorClass, ListQueryOptions,
-        RetryPolicy, DEFAULT_RETRY_POLICY, MAX_LIST_PAGES, SelectorFallbackReason,
-        build_list_params, cache_insert, cache_lookup, classify_list_error, discovery_cache,
-        ensure_page_limit, invalidate_discovery_cache, is_api_transient,
-        is_retryable_kube_error, list_async, map_discovery_error, map_list_error,
-        next_continue_token, normalize_resource, retry_backoff_for_attempt, run_with_retry,
-        should_retry_with_fresh_discovery, should_retry_without_selectors,
+        ConnectionOptions, DEFAULT_RETRY_POLICY, DISCOVERY_STAGE, DiscoveryCacheEntry,
+        DiscoveryCacheKey, K8sDiagnostic, ListErrorClass, ListMode, ListQueryOptions,
+        MAX_LIST_PAGES, PagedItems, REQUEST_TIMEOUT, RequestCounter, ResolvedResource, RetryOn,
+        RetryPolicy, SelectorFallbackReason, WarningRecorder, apply_connection_options,
+        build_list_params, cache_insert, cache_lookup, classify_list_error, collect_pages,
+        discover_resources, discovery_cache, ensure_context_exists, ensure_page_limit,
+        invalidate_discovery_cache, is_api_transient, is_retryable_kube_error, kubeconfig_options,
+        list_async, list_namespace, list_objects, list_pages, list_with_connection_async,
+        list_with_selector_fallback, load_config, map_discovery_error, map_list_error,
+        materialize_objects, merge_shards, namespace_scope_diagnostic, next_continue_token,
+        normalize_resource, parse_header, parse_shard_by, plan_name_branches, plan_shards,
+        request_count_layer, retry_backoff_for_attempt, run_with_retry, scoped_api,
+        should_retry_with_fresh_discovery, should_retry_without_selectors, warning_layer,
     };
     use crate::error::{K8sError, RetryErrorKind, RetryStopReason};
 
@@ -666,62 +1605,726 @@ mod tests {
             .expect("cache test mutex must not be poisoned")
     }
 
-    fn dummy_api_resource() -> kube::core::ApiResource {
-        let gvk = GroupVersionKind::gvk("apps", "v1", "Deployment");
-        kube::core::ApiResource::from_gvk_with_plural(&gvk, "deployments")
+    fn stub_page(items: &[&str], continue_token: Option<&str>) -> kube::core::ObjectList<String> {
+        kube::core::ObjectList {
+            types: Default::default(),
+            metadata: k8s_openapi::apimachinery::pkg::apis::meta::v1::ListMeta {
+                continue_: continue_token.map(str::to_string),
+                ..Default::default()
+            },
+            items: items.iter().map(|item| (*item).to_string()).collect(),
+        }
+    }
+
+    fn dummy_resolved_resource() -> ResolvedResource {
+        let gvk = GroupVersionKind::gvk("apps", "v1", "Deployment");
+        ResolvedResource {
+            api_resource: kube::core::ApiResource::from_gvk_with_plural(&gvk, "deployments"),
+            namespaced: true,
+        }
+    }
+
+    #[test]
+    fn flattens_nested_objects_to_dot_paths() {
+        let value = json!({
+            "metadata": {
+                "namespace": "demo-a"
+            },
+            "spec": {
+                "replicas": 2,
+                "enabled": true
+            }
+        });
+
+        let out = crate::path::flatten_json_to_fields(&value);
+
+        assert_eq!(
+            out.get("metadata.namespace"),
+            Some(&Value::String("demo-a".to_string()))
+        );
+        assert_eq!(out.get("spec.replicas"), Some(&Value::from(2)));
+        assert_eq!(out.get("spec.enabled"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn builds_list_params_with_limit_and_continue_token() {
+        let params = build_list_params(250, Some("next-token"), &ListQueryOptions::default());
+        assert_eq!(params.limit, Some(250));
+        assert_eq!(params.continue_token.as_deref(), Some("next-token"));
+    }
+
+    #[test]
+    fn builds_list_params_with_limit_only() {
+        let params = build_list_params(250, None, &ListQueryOptions::default());
+        assert_eq!(params.limit, Some(250));
+        assert_eq!(params.continue_token, None);
+    }
+
+    #[test]
+    fn builds_list_params_with_selectors() {
+        let params = build_list_params(
+            250,
+            None,
+            &ListQueryOptions {
+                field_selector: Some("metadata.namespace=demo-a".to_string()),
+                label_selector: Some("app=api".to_string()),
+                limit: None,
+                shard_by: None,
+                name_fan_out: Vec::new(),
+            },
+        );
+        assert_eq!(
+            params.field_selector.as_deref(),
+            Some("metadata.namespace=demo-a")
+        );
+        assert_eq!(params.label_selector.as_deref(), Some("app=api"));
+    }
+
+    #[test]
+    fn plans_one_label_selected_shard_per_distinct_value() {
+        let base = ListQueryOptions {
+            field_selector: Some("metadata.namespace=demo-a".to_string()),
+            label_selector: Some("app=api".to_string()),
+            limit: Some(10),
+            shard_by: Some("shard".to_string()),
+            name_fan_out: Vec::new(),
+        };
+        let values = ["b", "a", "b", "c"].map(str::to_string);
+
+        let shards = plan_shards("shard", values, &base);
+        let selectors: Vec<_> = shards
+            .iter()
+            .map(|shard| shard.label_selector.as_deref())
+            .collect();
+        assert_eq!(
+            selectors,
+            vec![
+                Some("app=api,shard=a"),
+                Some("app=api,shard=b"),
+                Some("app=api,shard=c"),
+                Some("app=api,!shard"),
+            ]
+        );
+        assert!(
+            shards
+                .iter()
+                .all(|shard| shard.shard_by.is_none() && shard.limit.is_none())
+        );
+        assert!(
+            shards
+                .iter()
+                .all(|shard| shard.field_selector.as_deref() == Some("metadata.namespace=demo-a"))
+        );
+        assert_eq!(
+            plan_shards("shard", Vec::new(), &ListQueryOptions::default())
+                .into_iter()
+                .map(|shard| shard.label_selector)
+                .collect::<Vec<_>>(),
+            vec![Some("!shard".to_string())]
+        );
+    }
+
+    #[test]
+    fn merges_shards_dropping_duplicate_objects_by_uid() {
+        let object = |name: &str, uid: Option<&str>| {
+            let mut object = kube::api::DynamicObject {
+                types: None,
+                metadata: Default::default(),
+                data: json!({}),
+            };
+            object.metadata.name = Some(name.to_string());
+            object.metadata.uid = uid.map(str::to_string);
+            object
+        };
+        let shard = |items: Vec<kube::api::DynamicObject>, truncated: bool| {
+            let paged = PagedItems {
+                item_count: items.len(),
+                items,
+                mode: ListMode::Objects,
+                pages_fetched: 1,
+                truncated,
+                estimated_total: Some(10),
+            };
+            (paged, Vec::new())
+        };
+
+        let (merged, diagnostics) = merge_shards(
+            vec![
+                shard(
+                    vec![object("a", Some("uid-a")), object("b", Some("uid-b"))],
+                    false,
+                ),
+                shard(vec![object("a", Some("uid-a")), object("c", None)], true),
+                shard(vec![object("d", None)], false),
+            ],
+            2,
+        );
+        let names: Vec<_> = merged
+            .items
+            .iter()
+            .map(|item| item.metadata.name.as_deref())
+            .collect();
+        assert_eq!(names, vec![Some("a"), Some("b"), Some("c"), Some("d")]);
+        assert_eq!(merged.item_count, 4);
+        assert_eq!(merged.pages_fetched, 5);
+        assert!(merged.truncated);
+        assert_eq!(merged.estimated_total, None);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn plans_one_field_selected_branch_per_name() {
+        let base = ListQueryOptions {
+            field_selector: Some("metadata.namespace=demo-a".to_string()),
+            label_selector: Some("app=api".to_string()),
+            limit: Some(10),
+            shard_by: None,
+            name_fan_out: vec!["metadata.name=a".to_string(), "metadata.name=b".to_string()],
+        };
+
+        let branches = plan_name_branches(&base);
+        let selectors: Vec<_> = branches
+            .iter()
+            .map(|branch| branch.field_selector.as_deref())
+            .collect();
+        assert_eq!(
+            selectors,
+            vec![
+                Some("metadata.namespace=demo-a,metadata.name=a"),
+                Some("metadata.namespace=demo-a,metadata.name=b"),
+            ]
+        );
+        assert!(branches.iter().all(|branch| {
+            branch.label_selector.as_deref() == Some("app=api")
+                && branch.limit.is_none()
+                && branch.name_fan_out.is_empty()
+        }));
+
+        let unscoped = ListQueryOptions {
+            name_fan_out: vec!["metadata.name=a".to_string()],
+            ..ListQueryOptions::default()
+        };
+        assert_eq!(
+            plan_name_branches(&unscoped)[0].field_selector.as_deref(),
+            Some("metadata.name=a")
+        );
+    }
+
+    #[test]
+    fn merges_shards_dropping_uidless_duplicates_by_namespace_and_name() {
+        let object = |namespace: &str, name: &str| {
+            let mut object = kube::api::DynamicObject {
+                types: None,
+                metadata: Default::default(),
+                data: json!({}),
+            };
+            object.metadata.namespace = Some(namespace.to_string());
+            object.metadata.name = Some(name.to_string());
+            object
+        };
+        let shard = |items: Vec<kube::api::DynamicObject>| {
+            let paged = PagedItems {
+                item_count: items.len(),
+                items,
+                mode: ListMode::Objects,
+                pages_fetched: 1,
+                truncated: false,
+                estimated_total: None,
+            };
+            (paged, Vec::new())
+        };
+
+        let (merged, _) = merge_shards(
+            vec![
+                shard(vec![object("demo-a", "api"), object("demo-b", "api")]),
+                shard(vec![object("demo-a", "api"), object("demo-a", "db")]),
+            ],
+            0,
+        );
+        let keys: Vec<_> = merged
+            .items
+            .iter()
+            .map(|item| {
+                format!(
+                    "{}/{}",
+                    item.metadata.namespace.as_deref().unwrap_or_default(),
+                    item.metadata.name.as_deref().unwrap_or_default()
+                )
+            })
+            .collect();
+        assert_eq!(keys, vec!["demo-a/api", "demo-b/api", "demo-a/db"]);
+        assert_eq!(merged.item_count, 3);
+    }
+
+    #[test]
+    fn name_fan_out_lists_each_branch_and_merges_results() {
+        use tower::service_fn;
+
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let _entered = runtime.enter();
+        let queries = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = service_fn({
+            let queries = Arc::clone(&queries);
+            move |request: http::Request<kube::client::Body>| {
+                let query = request.uri().query().unwrap_or_default().to_string();
+                queries.lock().expect("queries lock").push(query.clone());
+                async move {
+                    let name = if query.contains("metadata.name%3Da") {
+                        "a"
+                    } else {
+                        "b"
+                    };
+                    let payload = json!({
+                        "metadata": {},
+                        "items": [
+                            {"metadata": {"name": name, "namespace": "demo-a", "uid": name}}
+                        ]
+                    });
+                    let body = serde_json::to_vec(&payload).expect("payload must serialize");
+                    Ok::<_, std::io::Error>(http::Response::new(kube::client::Body::from(body)))
+                }
+            }
+        });
+        let client = kube::Client::new(service, "default");
+        let api = scoped_api(client, &dummy_resolved_resource(), None);
+        let options = ListQueryOptions {
+            name_fan_out: vec!["metadata.name=a".to_string(), "metadata.name=b".to_string()],
+            ..ListQueryOptions::default()
+        };
+
+        let (paged, diagnostics) = runtime
+            .block_on(list_objects(
+                "widgets",
+                &api,
+                &options,
+                RetryOn::All,
+                ListMode::CountOnly,
+            ))
+            .expect("fan-out list must succeed");
+
+        let names: Vec<_> = paged
+            .items
+            .iter()
+            .map(|item| item.metadata.name.as_deref())
+            .collect();
+        assert_eq!(names, vec![Some("a"), Some("b")]);
+        assert_eq!(paged.mode, ListMode::Objects);
+        assert_eq!(paged.pages_fetched, 2);
+        assert!(diagnostics.is_empty());
+        let queries = queries.lock().expect("queries lock");
+        assert_eq!(queries.len(), 2);
+        assert!(
+            queries
+                .iter()
+                .any(|query| query.contains("fieldSelector=metadata.name%3Da"))
+        );
+        assert!(
+            queries
+                .iter()
+                .any(|query| query.contains("fieldSelector=metadata.name%3Db"))
+        );
+    }
+
+    #[test]
+    fn parses_shard_by_label_paths_only() {
+        assert_eq!(
+            parse_shard_by("metadata.labels.shard"),
+            Ok("shard".to_string())
+        );
+        assert_eq!(
+            parse_shard_by("metadata.labels.example.com/shard"),
+            Ok("example.com/shard".to_string())
+        );
+        assert!(parse_shard_by("metadata.labels.").is_err());
+        assert!(parse_shard_by("metadata.labels.a=b").is_err());
+        assert!(parse_shard_by("spec.shard").is_err());
+    }
+
+    #[test]
+    fn collect_pages_reports_page_count_for_multi_page_fetch() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let requested_tokens = Arc::new(Mutex::new(Vec::new()));
+
+        let result = runtime.block_on(collect_pages("pods", ListMode::Objects, None, {
+            let requested_tokens = Arc::clone(&requested_tokens);
+            move |token: Option<String>| {
+                requested_tokens
+                    .lock()
+                    .expect("token log must not be poisoned")
+                    .push(token.clone());
+                async move {
+                    Ok(match token.as_deref() {
+                        None => stub_page(&["pod-a", "pod-b"], Some("page-2")),
+                        Some("page-2") => stub_page(&["pod-c"], Some("page-3")),
+                        _ => stub_page(&["pod-d"], None),
+                    })
+                }
+            }
+        }));
+
+        let paged = result.expect("paged fetch must succeed");
+        assert_eq!(paged.items, vec!["pod-a", "pod-b", "pod-c", "pod-d"]);
+        assert_eq!(paged.pages_fetched, 3);
+        assert!(!paged.truncated);
+        assert_eq!(
+            *requested_tokens
+                .lock()
+                .expect("token log must not be poisoned"),
+            vec![None, Some("page-2".to_string()), Some("page-3".to_string())]
+        );
+    }
+
+    #[test]
+    fn collect_pages_stops_once_limit_is_reached() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let requested_tokens = Arc::new(Mutex::new(Vec::new()));
+
+        let result = runtime.block_on(collect_pages("pods", ListMode::Objects, Some(3), {
+            let requested_tokens = Arc::clone(&requested_tokens);
+            move |token: Option<String>| {
+                requested_tokens
+                    .lock()
+                    .expect("token log must not be poisoned")
+                    .push(token.clone());
+                async move {
+                    Ok(match token.as_deref() {
+                        None => stub_page(&["pod-a", "pod-b"], Some("page-2")),
+                        Some("page-2") => stub_page(&["pod-c", "pod-d"], Some("page-3")),
+                        _ => stub_page(&["pod-e"], None),
+                    })
+                }
+            }
+        }));
+
+        let paged = result.expect("paged fetch must succeed");
+        assert_eq!(paged.items, vec!["pod-a", "pod-b", "pod-c"]);
+        assert_eq!(paged.pages_fetched, 2);
+        assert!(paged.truncated);
+        assert_eq!(
+            *requested_tokens
+                .lock()
+                .expect("token log must not be poisoned"),
+            vec![None, Some("page-2".to_string())]
+        );
+    }
+
+    #[test]
+    fn request_count_includes_list_pages_and_retries() {
+        use tower::{Layer, service_fn};
+
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let _entered = runtime.enter();
+        let requests = RequestCounter::default();
+        let served = Arc::new(AtomicUsize::new(0));
+        let service = request_count_layer(requests.clone()).layer(service_fn({
+            let served = Arc::clone(&served);
+            move |_: http::Request<kube::client::Body>| {
+                let current = served.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    let page = match current {
+                        0 => return Err(std::io::Error::other("connection reset")),
+                        1 => json!({"metadata": {"continue": "page-2"}, "items": []}),
+                        _ => json!({"metadata": {}, "items": []}),
+                    };
+                    let body = serde_json::to_vec(&page).expect("page must serialize");
+                    Ok(http::Response::new(kube::client::Body::from(body)))
+                }
+            }
+        }));
+        let client = kube::Client::new(service, "default");
+        let api = scoped_api(client, &dummy_resolved_resource(), None);
+
+        let paged = runtime
+            .block_on(list_pages(
+                "deployments",
+                &api,
+                &ListQueryOptions::default(),
+                RetryOn::All,
+                ListMode::Objects,
+            ))
+            .expect("list must succeed after retry");
+
+        assert_eq!(paged.pages_fetched, 2);
+        assert_eq!(requests.total(), 3);
+    }
+
+    #[test]
+    fn pagination_rejection_falls_back_to_single_unpaginated_list() {
+        use tower::service_fn;
+
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let _entered = runtime.enter();
+        let queries = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = service_fn({
+            let queries = Arc::clone(&queries);
+            move |request: http::Request<kube::client::Body>| {
+                let query = request.uri().query().unwrap_or_default().to_string();
+                queries.lock().expect("queries lock").push(query.clone());
+                async move {
+                    let (status, payload) = if query.contains("limit=") {
+                        (
+                            http::StatusCode::BAD_REQUEST,
+                            json!({
+                                "kind": "Status",
+                                "apiVersion": "v1",
+                                "status": "Failure",
+                                "message": "pagination is not supported for this resource",
+                                "reason": "BadRequest",
+                                "code": 400
+                            }),
+                        )
+                    } else {
+                        (
+                            http::StatusCode::OK,
+                            json!({
+                                "metadata": {},
+                                "items": [
+                                    {"metadata": {"name": "a"}},
+                                    {"metadata": {"name": "b"}}
+                                ]
+                            }),
+                        )
+                    };
+                    let body = serde_json::to_vec(&payload).expect("payload must serialize");
+                    let mut response = http::Response::new(kube::client::Body::from(body));
+                    *response.status_mut() = status;
+                    Ok::<_, std::io::Error>(response)
+                }
+            }
+        });
+        let client = kube::Client::new(service, "default");
+        let api = scoped_api(client, &dummy_resolved_resource(), None);
+
+        let (paged, diagnostics) = runtime
+            .block_on(list_with_selector_fallback(
+                "widgets",
+                &api,
+                &ListQueryOptions::default(),
+                RetryOn::All,
+                ListMode::Objects,
+            ))
+            .expect("list must succeed without pagination");
+
+        assert_eq!(paged.item_count, 2);
+        assert_eq!(paged.pages_fetched, 1);
+        assert_eq!(
+            diagnostics,
+            vec![K8sDiagnostic::PaginationUnsupported {
+                resource: "widgets".to_string(),
+            }]
+        );
+        let queries = queries.lock().expect("queries lock");
+        assert_eq!(queries.len(), 2);
+        assert!(queries[0].contains("limit="));
+        assert!(!queries[1].contains("limit="));
+        assert!(!queries[1].contains("continue="));
+    }
+
+    #[test]
+    fn unpaginated_retry_falls_back_to_unfiltered_list_on_selector_rejection() {
+        use tower::service_fn;
+
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let _entered = runtime.enter();
+        let queries = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = service_fn({
+            let queries = Arc::clone(&queries);
+            move |request: http::Request<kube::client::Body>| {
+                let query = request.uri().query().unwrap_or_default().to_string();
+                queries.lock().expect("queries lock").push(query.clone());
+                async move {
+                    let rejection = |message: &str| {
+                        json!({
+                            "kind": "Status",
+                            "apiVersion": "v1",
+                            "status": "Failure",
+                            "message": message,
+                            "reason": "BadRequest",
+                            "code": 400
+                        })
+                    };
+                    let (status, payload) = if query.contains("limit=") {
+                        (
+                            http::StatusCode::BAD_REQUEST,
+                            rejection("limit is not supported"),
+                        )
+                    } else if query.contains("labelSelector=") {
+                        (
+                            http::StatusCode::BAD_REQUEST,
+                            rejection("unable to parse requirement"),
+                        )
+                    } else {
+                        (
+                            http::StatusCode::OK,
+                            json!({"metadata": {}, "items": [{"metadata": {"name": "a"}}]}),
+                        )
+                    };
+                    let body = serde_json::to_vec(&payload).expect("payload must serialize");
+                    let mut response = http::Response::new(kube::client::Body::from(body));
+                    *response.status_mut() = status;
+                    Ok::<_, std::io::Error>(response)
+                }
+            }
+        });
+        let client = kube::Client::new(service, "default");
+        let api = scoped_api(client, &dummy_resolved_resource(), None);
+        let options = ListQueryOptions {
+            label_selector: Some("app=api".to_string()),
+            ..ListQueryOptions::default()
+        };
+
+        let (paged, diagnostics) = runtime
+            .block_on(list_with_selector_fallback(
+                "widgets",
+                &api,
+                &options,
+                RetryOn::All,
+                ListMode::Objects,
+            ))
+            .expect("list must succeed without selectors and pagination");
+
+        assert_eq!(paged.item_count, 1);
+        assert_eq!(
+            diagnostics,
+            vec![
+                K8sDiagnostic::PaginationUnsupported {
+                    resource: "widgets".to_string(),
+                },
+                K8sDiagnostic::SelectorFallback {
+                    reason: SelectorFallbackReason::ApiRejectedBadRequest,
+                    attempted: options,
+                },
+            ]
+        );
+        let queries = queries.lock().expect("queries lock");
+        assert_eq!(queries.len(), 3);
+        assert!(!queries[2].contains("limit="));
+        assert!(!queries[2].contains("labelSelector="));
+    }
+
+    #[test]
+    fn pagination_rejection_needs_sent_pagination_and_specific_phrase() {
+        let bad_request = |message: &str| {
+            kube::Error::Api(kube::error::ErrorResponse {
+                status: "Failure".to_string(),
+                message: message.to_string(),
+                reason: "BadRequest".to_string(),
+                code: 400,
+            })
+        };
+
+        assert!(matches!(
+            map_list_error(
+                "widgets",
+                false,
+                true,
+                bad_request("limit is not supported")
+            ),
+            K8sError::PaginationUnsupported { .. }
+        ));
+        assert!(matches!(
+            map_list_error(
+                "widgets",
+                false,
+                false,
+                bad_request("limit is not supported")
+            ),
+            K8sError::ListFailed { .. }
+        ));
+        assert!(matches!(
+            map_list_error(
+                "widgets",
+                true,
+                true,
+                bad_request("unable to parse requirement: invalid label key \"rate-limit=\""),
+            ),
+            K8sError::SelectorRejected { .. }
+        ));
+    }
+
+    #[test]
+    fn collect_pages_reports_single_page_fetch() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let result = runtime.block_on(collect_pages(
+            "pods",
+            ListMode::Objects,
+            None,
+            |_token: Option<String>| async { Ok(stub_page(&["pod-a"], Some(""))) },
+        ));
+
+        let paged = result.expect("paged fetch must succeed");
+        assert_eq!(paged.pages_fetched, 1);
+        assert!(!paged.truncated);
     }
 
     #[test]
-    fn flattens_nested_objects_to_dot_paths() {
-        let value = json!({
-            "metadata": {
-                "namespace": "demo-a"
+    fn collect_pages_captures_remaining_item_count_from_first_page() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let result = runtime.block_on(collect_pages(
+            "pods",
+            ListMode::Objects,
+            None,
+            |token: Option<String>| async move {
+                let mut page = match token.as_deref() {
+                    None => stub_page(&["pod-a", "pod-b"], Some("page-2")),
+                    Some(_) => stub_page(&["pod-c"], None),
+                };
+                page.metadata.remaining_item_count = Some(if token.is_none() { 11_998 } else { 0 });
+                Ok(page)
             },
-            "spec": {
-                "replicas": 2,
-                "enabled": true
-            }
-        });
-
-        let out = crate::path::flatten_json_to_fields(&value);
+        ));
 
-        assert_eq!(
-            out.get("metadata.namespace"),
-            Some(&Value::String("demo-a".to_string()))
-        );
-        assert_eq!(out.get("spec.replicas"), Some(&Value::from(2)));
-        assert_eq!(out.get("spec.enabled"), Some(&Value::Bool(true)));
+        let paged = result.expect("paged fetch must succeed");
+        assert_eq!(paged.pages_fetched, 2);
+        assert_eq!(paged.estimated_total, Some(12_000));
     }
 
     #[test]
-    fn builds_list_params_with_limit_and_continue_token() {
-        let params = build_list_params(250, Some("next-token"), &ListQueryOptions::default());
-        assert_eq!(params.limit, Some(250));
-        assert_eq!(params.continue_token.as_deref(), Some("next-token"));
-    }
+    fn collect_pages_leaves_estimate_empty_without_remaining_item_count() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let result = runtime.block_on(collect_pages(
+            "pods",
+            ListMode::Objects,
+            None,
+            |_token: Option<String>| async { Ok(stub_page(&["pod-a"], None)) },
+        ));
 
-    #[test]
-    fn builds_list_params_with_limit_only() {
-        let params = build_list_params(250, None, &ListQueryOptions::default());
-        assert_eq!(params.limit, Some(250));
-        assert_eq!(params.continue_token, None);
+        let paged = result.expect("paged fetch must succeed");
+        assert_eq!(paged.estimated_total, None);
     }
 
     #[test]
-    fn builds_list_params_with_selectors() {
-        let params = build_list_params(
-            250,
-            None,
-            &ListQueryOptions {
-                field_selector: Some("metadata.namespace=demo-a".to_string()),
-                label_selector: Some("app=api".to_string()),
-            },
-        );
-        assert_eq!(
-            params.field_selector.as_deref(),
-            Some("metadata.namespace=demo-a")
-        );
-        assert_eq!(params.label_selector.as_deref(), Some("app=api"));
+    fn count_only_listing_skips_object_conversion() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let fetch = |token: Option<String>| async move {
+            Ok(match token.as_deref() {
+                None => stub_page(&["pod-a", "pod-b"], Some("page-2")),
+                _ => stub_page(&["pod-c"], None),
+            })
+        };
+        let converted = AtomicUsize::new(0);
+        let convert = |_: String| {
+            converted.fetch_add(1, Ordering::SeqCst);
+            crate::dynamic_object::DynamicObject::default()
+        };
+
+        let paged = runtime
+            .block_on(collect_pages("pods", ListMode::CountOnly, None, fetch))
+            .expect("paged fetch must succeed");
+        assert!(paged.items.is_empty());
+        assert_eq!(paged.pages_fetched, 2);
+        let (objects, counted) = materialize_objects(paged, convert);
+        assert!(objects.is_empty());
+        assert_eq!(counted, Some(3));
+        assert_eq!(converted.load(Ordering::SeqCst), 0);
+
+        let paged = runtime
+            .block_on(collect_pages("pods", ListMode::Objects, None, fetch))
+            .expect("paged fetch must succeed");
+        let (objects, counted) = materialize_objects(paged, convert);
+        assert_eq!(objects.len(), 3);
+        assert_eq!(counted, None);
+        assert_eq!(converted.load(Ordering::SeqCst), 3);
     }
 
     #[test]
@@ -772,6 +2375,74 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn kubeconfig_options_select_requested_context() {
+        let options = kubeconfig_options("prod-eu");
+        assert_eq!(options.context.as_deref(), Some("prod-eu"));
+        assert_eq!(options.cluster, None);
+        assert_eq!(options.user, None);
+    }
+
+    #[test]
+    fn insecure_skip_tls_verify_accepts_invalid_certs() {
+        let url = "https://127.0.0.1:6443".parse().expect("valid url");
+        let mut config = kube::config::Config::new(url);
+        apply_connection_options(&mut config, &ConnectionOptions::default());
+        assert!(!config.accept_invalid_certs);
+
+        apply_connection_options(
+            &mut config,
+            &ConnectionOptions {
+                insecure_skip_tls_verify: true,
+                ..ConnectionOptions::default()
+            },
+        );
+        assert!(config.accept_invalid_certs);
+    }
+
+    #[test]
+    fn custom_headers_are_applied_to_config() {
+        let url = "https://127.0.0.1:6443".parse().expect("valid url");
+        let mut config = kube::config::Config::new(url);
+        let header = parse_header("X-Audit-Reason: incident 4711").expect("valid header");
+
+        apply_connection_options(
+            &mut config,
+            &ConnectionOptions {
+                headers: vec![header],
+                ..ConnectionOptions::default()
+            },
+        );
+
+        assert_eq!(config.headers.len(), 1);
+        assert_eq!(config.headers[0].0.as_str(), "x-audit-reason");
+        assert_eq!(config.headers[0].1.to_str().ok(), Some("incident 4711"));
+    }
+
+    #[test]
+    fn parse_header_rejects_malformed_and_restricted_headers() {
+        assert!(parse_header("X-Audit-Reason").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+        assert!(parse_header("X-Audit-Reason: line\nbreak").is_err());
+        assert!(parse_header("Authorization: Bearer token").is_err());
+        assert!(parse_header("Impersonate-User: admin").is_err());
+        assert!(parse_header("X-Request-Id: abc").is_ok());
+    }
+
+    #[test]
+    fn empty_resource_name_is_typed_error_in_context() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let result = runtime.block_on(list_with_connection_async(
+            "  ",
+            &ListQueryOptions::default(),
+            &ConnectionOptions {
+                context: Some("prod-eu".to_string()),
+                ..ConnectionOptions::default()
+            },
+        ));
+        assert!(matches!(result, Err(K8sError::EmptyResourceName)));
+    }
+
     #[test]
     fn empty_resource_name_is_typed_error() {
         let result = super::list("  ", &ListQueryOptions::default());
@@ -833,6 +2504,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn classifies_certificate_service_error_as_tls() {
+        let error = kube::Error::Service(
+            std::io::Error::other("invalid peer certificate: UnknownIssuer").into(),
+        );
+        assert_eq!(classify_list_error(&error, false), ListErrorClass::Tls);
+        assert!(!is_retryable_kube_error(&error, RetryOn::All));
+    }
+
+    #[test]
+    fn api_errors_about_certificate_resources_are_not_tls() {
+        let forbidden = |message: &str| {
+            kube::Error::Api(kube::error::ErrorResponse {
+                status: "Failure".to_string(),
+                message: message.to_string(),
+                reason: "Forbidden".to_string(),
+                code: 403,
+            })
+        };
+        let errors = [
+            forbidden(
+                "certificatesigningrequests.certificates.k8s.io is forbidden: User \"dev\" \
+                 cannot list resource \"certificatesigningrequests\"",
+            ),
+            forbidden("certificates.cert-manager.io is forbidden: x509 access denied"),
+        ];
+        for error in &errors {
+            assert_eq!(classify_list_error(error, false), ListErrorClass::Other);
+        }
+        assert!(matches!(
+            map_discovery_error(forbidden("certificates is forbidden")),
+            K8sError::DiscoveryRun { .. }
+        ));
+
+        let unrelated = kube::Error::Service(std::io::Error::other("connection refused").into());
+        assert_eq!(
+            classify_list_error(&unrelated, false),
+            ListErrorClass::ApiUnreachable
+        );
+    }
+
+    #[test]
+    fn maps_tls_error_for_list_and_discovery_stages() {
+        let list_error = map_list_error(
+            "pods",
+            false,
+            false,
+            kube::Error::Service(
+                std::io::Error::other("certificate not valid for name \"api.internal\"").into(),
+            ),
+        );
+        assert!(matches!(
+            list_error,
+            K8sError::TlsError { stage: "list", .. }
+        ));
+
+        let discovery_error = map_discovery_error(kube::Error::Service(
+            std::io::Error::other("tls handshake eof").into(),
+        ));
+        assert!(matches!(
+            discovery_error,
+            K8sError::TlsError {
+                stage: "discovery",
+                ..
+            }
+        ));
+    }
+
     #[test]
     fn classifies_other_api_errors_as_other() {
         let error = kube::Error::Api(kube::error::ErrorResponse {
@@ -883,16 +2622,349 @@ mod tests {
         assert_eq!(key.resource, "pods");
     }
 
+    #[test]
+    fn discovery_cache_key_uses_requested_namespace_over_config_default() {
+        let mut config = kube::Config::new("https://cluster-a".parse().expect("uri must parse"));
+        config.default_namespace = "team-a".to_string();
+
+        let key = DiscoveryCacheKey::from_config(&config, None, None, "pods");
+        assert_eq!(key.namespace, "team-a");
+        let key = DiscoveryCacheKey::from_config(&config, None, Some("demo-a"), "pods");
+        assert_eq!(key.namespace, "demo-a");
+    }
+
+    #[test]
+    fn discovery_cache_key_separates_contexts_on_same_server() {
+        let config = kube::Config::new("https://cluster-a".parse().expect("uri must parse"));
+
+        let staging = DiscoveryCacheKey::from_config(&config, Some("staging"), None, "pods");
+        let prod = DiscoveryCacheKey::from_config(&config, Some("prod"), None, "pods");
+        assert_ne!(staging, prod);
+        assert!(
+            staging
+                .cluster_identity
+                .starts_with("staging@https://cluster-a")
+        );
+    }
+
+    #[test]
+    fn loads_config_from_explicit_kubeconfig_path() {
+        let path =
+            std::env::temp_dir().join(format!("kubiq-kubeconfig-{}.yaml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+apiVersion: v1
+kind: Config
+current-context: a
+clusters:
+  - name: cluster-a
+    cluster:
+      server: https://cluster-a
+  - name: cluster-b
+    cluster:
+      server: https://cluster-b
+contexts:
+  - name: a
+    context:
+      cluster: cluster-a
+      user: user-a
+  - name: b
+    context:
+      cluster: cluster-b
+      user: user-a
+users:
+  - name: user-a
+    user:
+      token: secret
+"#,
+        )
+        .expect("kubeconfig must be written");
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let load = |context: Option<&str>, path: &std::path::Path| {
+            runtime.block_on(load_config(&ConnectionOptions {
+                context: context.map(str::to_string),
+                kubeconfig: Some(path.to_path_buf()),
+                ..ConnectionOptions::default()
+            }))
+        };
+
+        let config = load(None, &path).expect("current context must load");
+        assert_eq!(config.cluster_url.to_string(), "https://cluster-a/");
+        let config = load(Some("b"), &path).expect("named context must load");
+        let key = DiscoveryCacheKey::from_config(&config, None, None, "pods");
+        assert!(key.cluster_identity.starts_with("https://cluster-b"));
+        std::fs::remove_file(&path).expect("kubeconfig must be removed");
+
+        assert!(matches!(
+            load(None, &path),
+            Err(K8sError::KubeconfigRead { path: reported, .. })
+                if reported == path.display().to_string()
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_context_with_available_names() {
+        let kubeconfig = kube::config::Kubeconfig::from_yaml(
+            r#"
+apiVersion: v1
+kind: Config
+contexts:
+  - name: dev
+    context:
+      cluster: cluster-a
+  - name: staging
+    context:
+      cluster: cluster-a
+"#,
+        )
+        .expect("kubeconfig must parse");
+
+        assert!(ensure_context_exists(&kubeconfig, "staging").is_ok());
+        let error = ensure_context_exists(&kubeconfig, "prod").expect_err("prod is unknown");
+        assert!(matches!(
+            &error,
+            K8sError::ContextNotFound { context, available }
+                if context == "prod" && available == &["dev".to_string(), "staging".to_string()]
+        ));
+        assert!(error.to_string().contains("available: dev, staging"));
+    }
+
+    #[test]
+    fn lists_in_kubeconfig_namespace_unless_all_namespaces_requested() {
+        let kubeconfig = kube::config::Kubeconfig::from_yaml(
+            r#"
+apiVersion: v1
+kind: Config
+current-context: team
+clusters:
+  - name: cluster-a
+    cluster:
+      server: https://cluster-a
+contexts:
+  - name: team
+    context:
+      cluster: cluster-a
+      user: user-a
+      namespace: team-a
+users:
+  - name: user-a
+    user:
+      token: secret
+"#,
+        )
+        .expect("kubeconfig must parse");
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let config = runtime
+            .block_on(kube::Config::from_custom_kubeconfig(
+                kubeconfig,
+                &kube::config::KubeConfigOptions::default(),
+            ))
+            .expect("config must load");
+
+        let namespace = |connection: ConnectionOptions| list_namespace(&config, &connection, None);
+        assert_eq!(
+            namespace(ConnectionOptions::default()),
+            Some("team-a".to_string())
+        );
+        assert_eq!(
+            namespace(ConnectionOptions {
+                namespace: Some("demo-a".to_string()),
+                ..ConnectionOptions::default()
+            }),
+            Some("demo-a".to_string())
+        );
+        assert_eq!(
+            namespace(ConnectionOptions {
+                all_namespaces: true,
+                ..ConnectionOptions::default()
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn env_namespace_applies_without_flags_and_is_ignored_with_all_namespaces() {
+        let mut config = kube::Config::new("https://cluster-a".parse().expect("uri must parse"));
+        config.default_namespace = "team-a".to_string();
+        let env = || Some("shell-ns".to_string());
+
+        assert_eq!(
+            list_namespace(&config, &ConnectionOptions::default(), env()),
+            Some("shell-ns".to_string())
+        );
+        let explicit = ConnectionOptions {
+            namespace: Some("demo-a".to_string()),
+            ..ConnectionOptions::default()
+        };
+        assert_eq!(
+            list_namespace(&config, &explicit, env()),
+            Some("demo-a".to_string())
+        );
+        let all = ConnectionOptions {
+            all_namespaces: true,
+            ..ConnectionOptions::default()
+        };
+        assert_eq!(list_namespace(&config, &all, env()), None);
+    }
+
+    #[test]
+    fn mixed_case_qualified_resource_uses_narrowed_group_discovery() {
+        use tower::service_fn;
+
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let _entered = runtime.enter();
+        let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = service_fn({
+            let paths = Arc::clone(&paths);
+            move |request: http::Request<kube::client::Body>| {
+                let path = request.uri().path().to_string();
+                paths.lock().expect("paths lock").push(path.clone());
+                async move {
+                    let (status, payload) = match path.as_str() {
+                        "/apis" => (
+                            http::StatusCode::OK,
+                            json!({
+                                "kind": "APIGroupList",
+                                "apiVersion": "v1",
+                                "groups": [{
+                                    "name": "apps",
+                                    "versions": [{"groupVersion": "apps/v1", "version": "v1"}],
+                                    "preferredVersion": {"groupVersion": "apps/v1", "version": "v1"}
+                                }]
+                            }),
+                        ),
+                        "/apis/apps/v1" => (
+                            http::StatusCode::OK,
+                            json!({
+                                "kind": "APIResourceList",
+                                "groupVersion": "apps/v1",
+                                "resources": [{
+                                    "name": "deployments",
+                                    "singularName": "deployment",
+                                    "namespaced": true,
+                                    "kind": "Deployment",
+                                    "verbs": ["get", "list"]
+                                }]
+                            }),
+                        ),
+                        _ => (
+                            http::StatusCode::NOT_FOUND,
+                            json!({
+                                "kind": "Status",
+                                "apiVersion": "v1",
+                                "status": "Failure",
+                                "message": "not found",
+                                "reason": "NotFound",
+                                "code": 404
+                            }),
+                        ),
+                    };
+                    let body = serde_json::to_vec(&payload).expect("payload must serialize");
+                    let mut response = http::Response::new(kube::client::Body::from(body));
+                    *response.status_mut() = status;
+                    Ok::<_, std::io::Error>(response)
+                }
+            }
+        });
+        let client = kube::Client::new(service, "default");
+
+        let resources = runtime
+            .block_on(discover_resources(
+                &client,
+                "Deployments.Apps",
+                RetryOn::All,
+                &DEFAULT_RETRY_POLICY,
+            ))
+            .expect("narrowed discovery must succeed");
+
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].0.plural, "deployments");
+        let paths = paths.lock().expect("paths lock");
+        assert_eq!(
+            *paths,
+            vec!["/apis".to_string(), "/apis/apps/v1".to_string()]
+        );
+    }
+
+    #[test]
+    fn namespace_scope_diagnostic_follows_the_given_resolution() {
+        let namespaced = dummy_resolved_resource();
+        let cluster_scoped = ResolvedResource {
+            namespaced: false,
+            ..dummy_resolved_resource()
+        };
+        let explicit = ConnectionOptions {
+            namespace: Some("demo-a".to_string()),
+            ..ConnectionOptions::default()
+        };
+        let all = ConnectionOptions {
+            all_namespaces: true,
+            ..ConnectionOptions::default()
+        };
+
+        assert_eq!(
+            namespace_scope_diagnostic("widgets", &namespaced, &explicit),
+            None
+        );
+        assert_eq!(
+            namespace_scope_diagnostic("widgets", &cluster_scoped, &explicit),
+            Some(K8sDiagnostic::NamespaceIgnored {
+                resource: "widgets".to_string(),
+                namespace: "demo-a".to_string(),
+            })
+        );
+        assert_eq!(
+            namespace_scope_diagnostic("widgets", &cluster_scoped, &all),
+            Some(K8sDiagnostic::AllNamespacesIgnored {
+                resource: "widgets".to_string(),
+            })
+        );
+        assert_eq!(
+            namespace_scope_diagnostic("widgets", &cluster_scoped, &ConnectionOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn scoped_api_uses_namespace_only_for_namespaced_resources() {
+        use tower::service_fn;
+
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let _entered = runtime.enter();
+        let client = kube::Client::new(
+            service_fn(|_: http::Request<kube::client::Body>| async {
+                Err::<http::Response<kube::client::Body>, _>(std::io::Error::other("offline"))
+            }),
+            "default",
+        );
+        let namespaced = dummy_resolved_resource();
+        let cluster_scoped = ResolvedResource {
+            namespaced: false,
+            ..dummy_resolved_resource()
+        };
+
+        let api = scoped_api(client.clone(), &namespaced, Some("demo-a"));
+        assert_eq!(
+            api.resource_url(),
+            "/apis/apps/v1/namespaces/demo-a/deployments"
+        );
+        let api = scoped_api(client.clone(), &namespaced, None);
+        assert_eq!(api.resource_url(), "/apis/apps/v1/deployments");
+        let api = scoped_api(client, &cluster_scoped, Some("demo-a"));
+        assert_eq!(api.resource_url(), "/apis/apps/v1/deployments");
+    }
+
     #[test]
     fn cache_lookup_returns_inserted_entry_before_expiry() {
         let _guard = cache_test_guard();
         clear_discovery_cache();
         let key = DiscoveryCacheKey::new("cluster-a".to_string(), "default".to_string(), "pods");
-        let api_resource = dummy_api_resource();
-        cache_insert(key.clone(), api_resource.clone(), Duration::from_secs(30));
+        let resolved = dummy_resolved_resource();
+        cache_insert(key.clone(), resolved.clone(), Duration::from_secs(30));
 
         let cached = cache_lookup(&key).expect("cache hit expected");
-        assert_eq!(cached.plural, api_resource.plural);
+        assert_eq!(cached, resolved);
     }
 
     #[test]
@@ -900,14 +2972,14 @@ mod tests {
         let _guard = cache_test_guard();
         clear_discovery_cache();
         let key = DiscoveryCacheKey::new("cluster-a".to_string(), "default".to_string(), "pods");
-        let api_resource = dummy_api_resource();
+        let resource = dummy_resolved_resource();
         discovery_cache()
             .write()
             .expect("discovery cache write lock must not be poisoned")
             .insert(
                 key.clone(),
                 DiscoveryCacheEntry {
-                    api_resource,
+                    resource,
                     expires_at: Instant::now() - Duration::from_secs(1),
                 },
             );
@@ -924,7 +2996,11 @@ mod tests {
         let _guard = cache_test_guard();
         clear_discovery_cache();
         let key = DiscoveryCacheKey::new("cluster-a".to_string(), "default".to_string(), "pods");
-        cache_insert(key.clone(), dummy_api_resource(), Duration::from_secs(30));
+        cache_insert(
+            key.clone(),
+            dummy_resolved_resource(),
+            Duration::from_secs(30),
+        );
         invalidate_discovery_cache(&key);
 
         assert!(cache_lookup(&key).is_none());
@@ -972,7 +3048,7 @@ mod tests {
             kube::Error::Api(inner) => inner,
             _ => unreachable!("must be api error"),
         }));
-        assert!(is_retryable_kube_error(&error));
+        assert!(is_retryable_kube_error(&error, RetryOn::All));
     }
 
     #[test]
@@ -983,7 +3059,35 @@ mod tests {
             reason: "InternalError".to_string(),
             code: 500,
         });
-        assert!(is_retryable_kube_error(&error));
+        assert!(is_retryable_kube_error(&error, RetryOn::All));
+    }
+
+    #[test]
+    fn retry_on_mode_controls_api_500_classification() {
+        let server_error = kube::Error::Api(kube::error::ErrorResponse {
+            status: "Failure".to_string(),
+            message: "internal".to_string(),
+            reason: "InternalError".to_string(),
+            code: 500,
+        });
+        let throttled = kube::Error::Api(kube::error::ErrorResponse {
+            status: "Failure".to_string(),
+            message: "too many requests".to_string(),
+            reason: "TooManyRequests".to_string(),
+            code: 429,
+        });
+
+        assert!(!is_retryable_kube_error(&server_error, RetryOn::Transport));
+        assert!(!is_retryable_kube_error(
+            &server_error,
+            RetryOn::TransportAnd429
+        ));
+        assert!(is_retryable_kube_error(&server_error, RetryOn::All));
+        assert!(!is_retryable_kube_error(&throttled, RetryOn::Transport));
+        assert!(is_retryable_kube_error(
+            &throttled,
+            RetryOn::TransportAnd429
+        ));
     }
 
     #[test]
@@ -994,7 +3098,7 @@ mod tests {
             reason: "RequestTimeout".to_string(),
             code: 408,
         });
-        assert!(is_retryable_kube_error(&error));
+        assert!(is_retryable_kube_error(&error, RetryOn::All));
         assert_eq!(
             classify_list_error(&error, false),
             ListErrorClass::ApiTransient
@@ -1009,29 +3113,42 @@ mod tests {
             reason: "BadRequest".to_string(),
             code: 400,
         });
-        assert!(!is_retryable_kube_error(&error));
+        assert!(!is_retryable_kube_error(&error, RetryOn::All));
     }
 
     #[test]
     fn computes_exponential_backoff_with_cap() {
         assert_eq!(
-            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, 1),
+            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, "list", 1),
             Duration::from_millis(100)
         );
         assert_eq!(
-            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, 2),
+            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, "list", 2),
             Duration::from_millis(200)
         );
         assert_eq!(
-            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, 3),
+            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, "list", 3),
             Duration::from_millis(400)
         );
         assert_eq!(
-            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, 4),
+            retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, "list", 4),
             Duration::from_millis(400)
         );
     }
 
+    #[test]
+    fn discovery_and_list_use_separate_backoff_ceilings() {
+        let list = |attempt| retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, "list", attempt);
+        let discovery =
+            |attempt| retry_backoff_for_attempt(&DEFAULT_RETRY_POLICY, DISCOVERY_STAGE, attempt);
+
+        assert_eq!(list(2), discovery(2));
+        assert_eq!(list(4), Duration::from_millis(400));
+        assert_eq!(discovery(4), Duration::from_millis(800));
+        assert_eq!(discovery(6), Duration::from_millis(1_600));
+        assert_eq!(discovery(7), Duration::from_millis(1_600));
+    }
+
     #[test]
     fn run_with_retry_succeeds_after_transient_error() {
         let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
@@ -1040,7 +3157,9 @@ mod tests {
             max_attempts: 3,
             initial_backoff: Duration::from_millis(1),
             max_backoff: Duration::from_millis(2),
+            discovery_max_backoff: Duration::from_millis(2),
             request_timeout: Duration::from_millis(20),
+            discovery_timeout: Duration::from_millis(20),
         };
 
         let result = runtime.block_on(run_with_retry(
@@ -1060,8 +3179,8 @@ mod tests {
                     }
                 }
             },
-            |source| super::map_list_error("pods", false, source),
-            super::is_retryable_kube_error,
+            |source| super::map_list_error("pods", false, false, source),
+            |source| super::is_retryable_kube_error(source, RetryOn::All),
         ));
 
         assert_eq!(result.expect("must succeed after retry"), 7_u8);
@@ -1075,7 +3194,9 @@ mod tests {
             max_attempts: 3,
             initial_backoff: Duration::from_millis(1),
             max_backoff: Duration::from_millis(2),
+            discovery_max_backoff: Duration::from_millis(2),
             request_timeout: Duration::from_millis(20),
+            discovery_timeout: Duration::from_millis(20),
         };
 
         let result: Result<u8, K8sError> = runtime.block_on(run_with_retry(
@@ -1089,8 +3210,8 @@ mod tests {
                     code: 403,
                 }))
             },
-            |source| super::map_list_error("pods", false, source),
-            super::is_retryable_kube_error,
+            |source| super::map_list_error("pods", false, false, source),
+            |source| super::is_retryable_kube_error(source, RetryOn::All),
         ));
 
         assert!(matches!(result, Err(K8sError::ListFailed { .. })));
@@ -1103,7 +3224,9 @@ mod tests {
             max_attempts: 3,
             initial_backoff: Duration::from_millis(1),
             max_backoff: Duration::from_millis(2),
+            discovery_max_backoff: Duration::from_millis(2),
             request_timeout: Duration::from_millis(20),
+            discovery_timeout: Duration::from_millis(20),
         };
 
         let result: Result<u8, K8sError> = runtime.block_on(run_with_retry(
@@ -1114,8 +3237,8 @@ mod tests {
                     std::io::Error::other("dial tcp timeout").into(),
                 ))
             },
-            |source| super::map_list_error("pods", false, source),
-            super::is_retryable_kube_error,
+            |source| super::map_list_error("pods", false, false, source),
+            |source| super::is_retryable_kube_error(source, RetryOn::All),
         ));
 
         assert!(matches!(
@@ -1135,6 +3258,7 @@ mod tests {
         let mapped = map_list_error(
             "pods",
             false,
+            false,
             kube::Error::Api(kube::error::ErrorResponse {
                 status: "Failure".to_string(),
                 message: "too many requests".to_string(),
@@ -1169,7 +3293,9 @@ mod tests {
             max_attempts: 2,
             initial_backoff: Duration::from_millis(1),
             max_backoff: Duration::from_millis(2),
+            discovery_max_backoff: Duration::from_millis(2),
             request_timeout: Duration::from_millis(5),
+            discovery_timeout: Duration::from_millis(5),
         };
 
         let result = runtime.block_on(run_with_retry(
@@ -1179,8 +3305,8 @@ mod tests {
                 tokio::time::sleep(Duration::from_millis(25)).await;
                 Ok(1_u8)
             },
-            |source| super::map_list_error("pods", false, source),
-            super::is_retryable_kube_error,
+            |source| super::map_list_error("pods", false, false, source),
+            |source| super::is_retryable_kube_error(source, RetryOn::All),
         ));
 
         assert!(matches!(
@@ -1195,6 +3321,43 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn discovery_stage_uses_discovery_timeout() {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        let policy = ConnectionOptions {
+            discovery_timeout: Some(Duration::from_millis(5)),
+            ..ConnectionOptions::default()
+        }
+        .retry_policy();
+        assert_eq!(policy.timeout_for("list"), REQUEST_TIMEOUT);
+
+        let result = runtime.block_on(run_with_retry(
+            DISCOVERY_STAGE,
+            &RetryPolicy {
+                max_attempts: 1,
+                ..policy
+            },
+            || async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(1_u8)
+            },
+            super::map_discovery_error,
+            |source| super::is_retryable_kube_error(source, RetryOn::All),
+        ));
+
+        let Err(K8sError::RetryExhausted { source, .. }) = result else {
+            panic!("discovery must time out");
+        };
+        assert!(matches!(
+            source.downcast_ref::<K8sError>(),
+            Some(K8sError::RequestTimeout {
+                stage: "discovery",
+                timeout_ms: 5,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn builds_retry_summary_diagnostic_from_retry_exhausted_error() {
         let error = K8sError::RetryExhausted {
@@ -1224,6 +3387,9 @@ mod tests {
             attempted: ListQueryOptions {
                 field_selector: Some("metadata.namespace=demo-a".to_string()),
                 label_selector: None,
+                limit: None,
+                shard_by: None,
+                name_fan_out: Vec::new(),
             },
         };
 
@@ -1234,8 +3400,51 @@ mod tests {
                 attempted: ListQueryOptions {
                     field_selector: Some(_),
                     label_selector: None,
+                    limit: None,
+                    shard_by: None,
+                    name_fan_out: _,
                 }
             }
         ));
     }
+
+    #[test]
+    fn server_warning_headers_become_diagnostics() {
+        use tower::{Layer, ServiceExt, service_fn};
+
+        let warnings = WarningRecorder::default();
+        let service = warning_layer(warnings.clone()).layer(service_fn(|_| async {
+            let response = http::Response::builder()
+                .header(
+                    http::header::WARNING,
+                    "299 - \"policy/v1beta1 PodSecurityPolicy is deprecated\"",
+                )
+                .header(http::header::WARNING, "299 - \"quoted \\\"name\\\"\"")
+                .body(kube::client::Body::empty())
+                .expect("response must build");
+            Ok::<_, std::convert::Infallible>(response)
+        }));
+        let runtime = tokio::runtime::Runtime::new().expect("runtime init must succeed");
+        for _ in 0..2 {
+            runtime
+                .block_on(
+                    service
+                        .clone()
+                        .oneshot(http::Request::new(kube::client::Body::empty())),
+                )
+                .expect("service must respond");
+        }
+
+        assert_eq!(
+            warnings.diagnostics(),
+            vec![
+                K8sDiagnostic::ServerWarning {
+                    message: "policy/v1beta1 PodSecurityPolicy is deprecated".to_string(),
+                },
+                K8sDiagnostic::ServerWarning {
+                    message: "quoted \"name\"".to_string(),
+                },
+            ]
+        );
+    }
 }
@@ -1,6 +1,10 @@
 use crate::{k8s::ListQueryOptions, parser};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Сколько веток `metadata.name == ... or ...` раскладывается в отдельные
+/// list-запросы; больше — обычный list с client-side фильтром.
+pub const MAX_NAME_FAN_OUT: usize = 8;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct PushdownPlan {
     pub options: ListQueryOptions,
     pub diagnostics: Vec<PlannerDiagnostic>,
@@ -17,37 +21,163 @@ pub struct PlannerDiagnostic {
 pub enum NotPushableReason {
     UnsupportedPath,
     UnsupportedOperator,
+    /// `>`/`>=`/`<`/`<=`: selectors API не умеют диапазоны, в том числе
+    /// по числовым значениям label.
+    RangeOperator,
     NonStringValue,
     UnsafeSelectorValue,
     UnsafeLabelKey,
+    ComputedField,
+    FieldReference,
+    ComputedValue,
+    WildcardPath,
+    DuplicateFieldSelector,
+    SetOnFieldSelector,
+    InsideOr,
+    InsideNot,
+    NameFanOutLimit,
 }
 
-pub fn plan_pushdown(predicates: &[parser::Predicate]) -> PushdownPlan {
-    let mut field_selectors = Vec::new();
+/// В selectors уходят только predicates верхнего уровня `and`: selectors
+/// API соединяются через AND, условия под `or`/`not` остаются client-side.
+/// Исключение — `or` только из равенств `metadata.name`: он раскладывается
+/// в `name_fan_out`, по list-запросу на ветку (не больше `MAX_NAME_FAN_OUT`).
+/// Повтор того же field selector отбрасывается; второй selector по уже
+/// занятому ключу (`metadata.name=a` и `metadata.name!=b`) API может
+/// отклонить, поэтому такой predicate остается client-side.
+pub fn plan_pushdown(filter: Option<&parser::Expr>) -> PushdownPlan {
+    let mut field_selectors: Vec<(String, String)> = Vec::new();
     let mut label_selectors = Vec::new();
     let mut diagnostics = Vec::new();
+    let mut predicates = Vec::new();
+    let mut nested = Vec::new();
+    let mut disjunctions = Vec::new();
+    if let Some(filter) = filter {
+        collect_conjuncts(filter, &mut predicates, &mut nested, &mut disjunctions);
+    }
 
     for predicate in predicates {
         match predicate_to_selector(predicate) {
-            Ok(SelectorTarget::Field(selector)) => field_selectors.push(selector),
+            Ok(SelectorTarget::Field(selector)) => {
+                let key = predicate.path.to_ascii_lowercase();
+                if field_selectors
+                    .iter()
+                    .any(|(_, pushed)| *pushed == selector)
+                {
+                    continue;
+                }
+                if field_selectors
+                    .iter()
+                    .any(|(pushed_key, _)| *pushed_key == key)
+                {
+                    diagnostics.push(not_pushable(
+                        predicate,
+                        NotPushableReason::DuplicateFieldSelector,
+                    ));
+                    continue;
+                }
+                field_selectors.push((key, selector));
+            }
             Ok(SelectorTarget::Label(selector)) => label_selectors.push(selector),
-            Err(reason) => diagnostics.push(PlannerDiagnostic {
-                path: predicate.path.clone(),
-                op: predicate.op.clone(),
-                reason,
-            }),
+            Err(reason) => diagnostics.push(not_pushable(predicate, reason)),
         }
     }
+    let mut name_fan_out = Vec::new();
+    let name_pushed = field_selectors
+        .iter()
+        .any(|(key, _)| key == "metadata.name");
+    for disjunction in disjunctions {
+        let branches = name_fan_out_branches(disjunction).filter(|_| name_fan_out.is_empty());
+        let reason = match branches {
+            Some(branches) if !name_pushed && branches.len() <= MAX_NAME_FAN_OUT => {
+                name_fan_out = branches;
+                continue;
+            }
+            Some(_) if !name_pushed => NotPushableReason::NameFanOutLimit,
+            _ => NotPushableReason::InsideOr,
+        };
+        diagnostics.extend(
+            disjunction
+                .predicates()
+                .into_iter()
+                .map(|predicate| not_pushable(predicate, reason.clone())),
+        );
+    }
+    diagnostics.extend(nested);
 
     PushdownPlan {
         options: ListQueryOptions {
-            field_selector: join_selector_parts(field_selectors),
+            field_selector: join_selector_parts(
+                field_selectors
+                    .into_iter()
+                    .map(|(_, selector)| selector)
+                    .collect(),
+            ),
             label_selector: join_selector_parts(label_selectors),
+            limit: None,
+            shard_by: None,
+            name_fan_out,
         },
         diagnostics,
     }
 }
 
+fn collect_conjuncts<'a>(
+    expr: &'a parser::Expr,
+    predicates: &mut Vec<&'a parser::Predicate>,
+    nested: &mut Vec<PlannerDiagnostic>,
+    disjunctions: &mut Vec<&'a parser::Expr>,
+) {
+    match expr {
+        parser::Expr::And(terms) => {
+            for term in terms {
+                collect_conjuncts(term, predicates, nested, disjunctions);
+            }
+        }
+        parser::Expr::Leaf(predicate) => predicates.push(predicate),
+        parser::Expr::Or(_) => disjunctions.push(expr),
+        parser::Expr::Not(_) => nested.extend(
+            expr.predicates()
+                .into_iter()
+                .map(|predicate| not_pushable(predicate, NotPushableReason::InsideNot)),
+        ),
+    }
+}
+
+/// Field selectors веток `or`, если каждая ветка — pushdown-имое
+/// `metadata.name == <value>`; повторы имен схлопываются.
+fn name_fan_out_branches(disjunction: &parser::Expr) -> Option<Vec<String>> {
+    let parser::Expr::Or(terms) = disjunction else {
+        return None;
+    };
+    let mut branches = Vec::new();
+    for term in terms {
+        let parser::Expr::Leaf(predicate) = term else {
+            return None;
+        };
+        if predicate.op != parser::Operator::Eq
+            || !predicate.path.eq_ignore_ascii_case("metadata.name")
+        {
+            return None;
+        }
+        let Ok(SelectorTarget::Field(selector)) = predicate_to_selector(predicate) else {
+            return None;
+        };
+        if !branches.contains(&selector) {
+            branches.push(selector);
+        }
+    }
+    Some(branches)
+}
+
+fn not_pushable(predicate: &parser::Predicate, reason: NotPushableReason) -> PlannerDiagnostic {
+    PlannerDiagnostic {
+        path: predicate.path.clone(),
+        op: predicate.op.clone(),
+        reason,
+    }
+}
+
 enum SelectorTarget {
     Field(String),
     Label(String),
@@ -56,15 +186,47 @@ enum SelectorTarget {
 fn predicate_to_selector(
     predicate: &parser::Predicate
 ) -> Result<SelectorTarget, NotPushableReason> {
+    if predicate.function.is_some() {
+        return Err(NotPushableReason::ComputedField);
+    }
+    if predicate.value_path.is_some() {
+        return Err(NotPushableReason::FieldReference);
+    }
+    if predicate.value_function.is_some() {
+        return Err(NotPushableReason::ComputedValue);
+    }
+    if predicate.path.ends_with('*') {
+        return Err(NotPushableReason::WildcardPath);
+    }
+    if matches!(
+        predicate.op,
+        parser::Operator::Exists | parser::Operator::IsNotNull
+    ) {
+        return existence_selector(&predicate.path);
+    }
+    if predicate.op == parser::Operator::IsNull {
+        return existence_selector(&predicate.path).map(|target| match target {
+            SelectorTarget::Label(key) => SelectorTarget::Label(format!("!{key}")),
+            field => field,
+        });
+    }
+    match &predicate.op {
+        parser::Operator::In { values } => return set_selector(&predicate.path, "in", values),
+        parser::Operator::NotIn { values } => {
+            return set_selector(&predicate.path, "notin", values);
+        }
+        _ => {}
+    }
+
     let operator = selector_operator(&predicate.op)?;
     let value = selector_value(&predicate.value).ok_or(NotPushableReason::NonStringValue)?;
-    if !is_selector_value_safe(&value) {
-        return Err(NotPushableReason::UnsafeSelectorValue);
-    }
 
     if predicate.path.eq_ignore_ascii_case("metadata.name")
         || predicate.path.eq_ignore_ascii_case("metadata.namespace")
     {
+        if !is_selector_value_safe(&value) {
+            return Err(NotPushableReason::UnsafeSelectorValue);
+        }
         return Ok(SelectorTarget::Field(format!(
             "{}{operator}{value}",
             predicate.path
@@ -75,6 +237,9 @@ fn predicate_to_selector(
         if !is_label_key_safe(label_key) {
             return Err(NotPushableReason::UnsafeLabelKey);
         }
+        if !is_label_value_safe(&value) {
+            return Err(NotPushableReason::UnsafeSelectorValue);
+        }
         return Ok(SelectorTarget::Label(format!(
             "{label_key}{operator}{value}"
         )));
@@ -83,10 +248,64 @@ fn predicate_to_selector(
     Err(NotPushableReason::UnsupportedPath)
 }
 
+fn existence_selector(path: &str) -> Result<SelectorTarget, NotPushableReason> {
+    let Some(label_key) = path.strip_prefix("metadata.labels.") else {
+        return Err(NotPushableReason::UnsupportedOperator);
+    };
+    if !is_label_key_safe(label_key) {
+        return Err(NotPushableReason::UnsafeLabelKey);
+    }
+    Ok(SelectorTarget::Label(label_key.to_string()))
+}
+
+/// Set-based форма есть только у label selector: `key in (a,b)`.
+fn set_selector(
+    path: &str,
+    operator: &str,
+    values: &[serde_json::Value],
+) -> Result<SelectorTarget, NotPushableReason> {
+    let Some(label_key) = path.strip_prefix("metadata.labels.") else {
+        if path.eq_ignore_ascii_case("metadata.name")
+            || path.eq_ignore_ascii_case("metadata.namespace")
+        {
+            return Err(NotPushableReason::SetOnFieldSelector);
+        }
+        return Err(NotPushableReason::UnsupportedPath);
+    };
+    if !is_label_key_safe(label_key) {
+        return Err(NotPushableReason::UnsafeLabelKey);
+    }
+    let mut members = Vec::with_capacity(values.len());
+    for value in values {
+        let value = selector_value(value).ok_or(NotPushableReason::NonStringValue)?;
+        if !is_label_value_safe(&value) {
+            return Err(NotPushableReason::UnsafeSelectorValue);
+        }
+        members.push(value);
+    }
+    Ok(SelectorTarget::Label(format!(
+        "{label_key} {operator} ({})",
+        members.join(",")
+    )))
+}
+
 fn selector_operator(op: &parser::Operator) -> Result<&'static str, NotPushableReason> {
     match op {
         parser::Operator::Eq => Ok("="),
         parser::Operator::Ne => Ok("!="),
+        parser::Operator::Gt
+        | parser::Operator::Ge
+        | parser::Operator::Lt
+        | parser::Operator::Le => Err(NotPushableReason::RangeOperator),
+        parser::Operator::In { .. }
+        | parser::Operator::NotIn { .. }
+        | parser::Operator::Like(_)
+        | parser::Operator::Regex
+        | parser::Operator::NotRegex
+        | parser::Operator::IsNull
+        | parser::Operator::IsNotNull
+        | parser::Operator::Exists
+        | parser::Operator::HasKey => Err(NotPushableReason::UnsupportedOperator),
     }
 }
 
@@ -105,7 +324,20 @@ fn is_selector_value_safe(value: &str) -> bool {
         && !value.chars().any(char::is_whitespace)
 }
 
-fn is_label_key_safe(key: &str) -> bool {
+/// Значение label по правилам Kubernetes: до 63 символов `[A-Za-z0-9._-]`,
+/// начинается и заканчивается буквой или цифрой. Пустое значение не
+/// pushdown-ится, хотя API его допускает.
+fn is_label_value_safe(value: &str) -> bool {
+    let edge_is_alphanumeric = |ch: Option<char>| ch.is_some_and(|ch| ch.is_ascii_alphanumeric());
+    value.len() <= 63
+        && edge_is_alphanumeric(value.chars().next())
+        && edge_is_alphanumeric(value.chars().last())
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'))
+}
+
+pub fn is_label_key_safe(key: &str) -> bool {
     !key.is_empty() && !key.contains(',') && !key.chars().any(char::is_whitespace)
 }
 
@@ -121,26 +353,32 @@ fn join_selector_parts(parts: Vec<String>) -> Option<String> {
 mod tests {
     use serde_json::Value;
 
-    use crate::parser::{Operator, Predicate};
+    use crate::parser::{Expr, FieldFunction, Operator, Predicate};
 
-    use super::{NotPushableReason, plan_pushdown};
+    use super::{MAX_NAME_FAN_OUT, NotPushableReason, plan_pushdown};
 
     #[test]
     fn pushes_field_selectors_for_eq_and_ne() {
         let predicates = vec![
             Predicate {
                 path: "metadata.name".to_string(),
+                function: None,
                 op: Operator::Eq,
                 value: Value::String("pod-a".to_string()),
+                value_path: None,
+                value_function: None,
             },
             Predicate {
                 path: "metadata.namespace".to_string(),
+                function: None,
                 op: Operator::Ne,
                 value: Value::String("kube-system".to_string()),
+                value_path: None,
+                value_function: None,
             },
         ];
 
-        let plan = plan_pushdown(&predicates);
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
         assert_eq!(
             plan.options.field_selector.as_deref(),
             Some("metadata.name=pod-a,metadata.namespace!=kube-system")
@@ -149,22 +387,335 @@ mod tests {
         assert!(plan.diagnostics.is_empty());
     }
 
+    #[test]
+    fn pushes_set_membership_on_labels_only() {
+        let set = |path: &str, op: Operator| Predicate {
+            path: path.to_string(),
+            function: None,
+            op,
+            value: Value::Null,
+            value_path: None,
+            value_function: None,
+        };
+        let members = || {
+            vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+            ]
+        };
+        let predicates = vec![
+            set("metadata.labels.tier", Operator::In { values: members() }),
+            set("metadata.labels.env", Operator::NotIn { values: members() }),
+            set("metadata.namespace", Operator::In { values: members() }),
+            set(
+                "metadata.labels.team",
+                Operator::In {
+                    values: vec![Value::String("has space".to_string())],
+                },
+            ),
+        ];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(
+            plan.options.label_selector.as_deref(),
+            Some("tier in (a,b),env notin (a,b)")
+        );
+        assert_eq!(plan.options.field_selector, None);
+        let reasons: Vec<NotPushableReason> = plan
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                NotPushableReason::SetOnFieldSelector,
+                NotPushableReason::UnsafeSelectorValue,
+            ]
+        );
+    }
+
+    #[test]
+    fn pushes_null_checks_on_labels_as_existence_selectors() {
+        let check = |path: &str, op: Operator| Predicate {
+            path: path.to_string(),
+            function: None,
+            op,
+            value: Value::Null,
+            value_path: None,
+            value_function: None,
+        };
+        let predicates = vec![
+            check("metadata.labels.app", Operator::IsNotNull),
+            check("metadata.labels.legacy", Operator::IsNull),
+            check("spec.nodeName", Operator::IsNull),
+        ];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(plan.options.label_selector.as_deref(), Some("app,!legacy"));
+        assert_eq!(plan.diagnostics.len(), 1);
+        assert_eq!(
+            plan.diagnostics[0].reason,
+            NotPushableReason::UnsupportedOperator
+        );
+    }
+
+    #[test]
+    fn pushes_only_top_level_conjuncts_of_or_queries() {
+        let eq = |path: &str, value: &str| {
+            Expr::Leaf(Predicate {
+                path: path.to_string(),
+                function: None,
+                op: Operator::Eq,
+                value: Value::String(value.to_string()),
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let filter = Expr::And(vec![
+            Expr::Or(vec![
+                eq("metadata.name", "api"),
+                eq("metadata.labels.tier", "web"),
+            ]),
+            eq("metadata.namespace", "demo-a"),
+            Expr::Not(Box::new(eq("metadata.labels.app", "db"))),
+        ]);
+
+        let plan = plan_pushdown(Some(&filter));
+        assert_eq!(
+            plan.options.field_selector.as_deref(),
+            Some("metadata.namespace=demo-a")
+        );
+        assert_eq!(plan.options.label_selector, None);
+        let reasons: Vec<(&str, &NotPushableReason)> = plan
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.path.as_str(), &diagnostic.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("metadata.name", &NotPushableReason::InsideOr),
+                ("metadata.labels.tier", &NotPushableReason::InsideOr),
+                ("metadata.labels.app", &NotPushableReason::InsideNot),
+            ]
+        );
+    }
+
+    #[test]
+    fn fans_out_or_of_name_equalities_into_one_selector_per_branch() {
+        let eq = |path: &str, value: &str| {
+            Expr::Leaf(Predicate {
+                path: path.to_string(),
+                function: None,
+                op: Operator::Eq,
+                value: Value::String(value.to_string()),
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let filter = Expr::And(vec![
+            Expr::Or(vec![
+                eq("metadata.name", "api"),
+                eq("metadata.name", "db"),
+                eq("metadata.name", "api"),
+            ]),
+            eq("metadata.namespace", "demo-a"),
+        ]);
+
+        let plan = plan_pushdown(Some(&filter));
+        assert_eq!(
+            plan.options.name_fan_out,
+            vec!["metadata.name=api", "metadata.name=db"]
+        );
+        assert_eq!(
+            plan.options.field_selector.as_deref(),
+            Some("metadata.namespace=demo-a")
+        );
+        assert!(plan.diagnostics.is_empty());
+
+        let pinned = Expr::And(vec![
+            eq("metadata.name", "api"),
+            Expr::Or(vec![eq("metadata.name", "api"), eq("metadata.name", "db")]),
+        ]);
+        let plan = plan_pushdown(Some(&pinned));
+        assert!(plan.options.name_fan_out.is_empty());
+        assert_eq!(plan.diagnostics.len(), 2);
+        assert!(
+            plan.diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.reason == NotPushableReason::InsideOr)
+        );
+    }
+
+    #[test]
+    fn caps_name_fan_out_branches() {
+        let names: Vec<String> = (0..=MAX_NAME_FAN_OUT)
+            .map(|index| format!("pod-{index}"))
+            .collect();
+        let branches = |names: &[String]| {
+            Expr::Or(
+                names
+                    .iter()
+                    .map(|name| {
+                        Expr::Leaf(Predicate {
+                            path: "metadata.name".to_string(),
+                            function: None,
+                            op: Operator::Eq,
+                            value: Value::String(name.clone()),
+                            value_path: None,
+                            value_function: None,
+                        })
+                    })
+                    .collect(),
+            )
+        };
+
+        let at_cap = plan_pushdown(Some(&branches(&names[..MAX_NAME_FAN_OUT])));
+        assert_eq!(at_cap.options.name_fan_out.len(), MAX_NAME_FAN_OUT);
+        assert!(at_cap.diagnostics.is_empty());
+
+        let over_cap = plan_pushdown(Some(&branches(&names)));
+        assert!(over_cap.options.name_fan_out.is_empty());
+        assert_eq!(over_cap.diagnostics.len(), MAX_NAME_FAN_OUT + 1);
+        assert!(
+            over_cap
+                .diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.reason == NotPushableReason::NameFanOutLimit)
+        );
+    }
+
+    #[test]
+    fn does_not_push_negated_groups() {
+        let label = |key: &str| {
+            Expr::Leaf(Predicate {
+                path: format!("metadata.labels.{key}"),
+                function: None,
+                op: Operator::Eq,
+                value: Value::String("x".to_string()),
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let filter = Expr::Not(Box::new(Expr::And(vec![label("app"), label("tier")])));
+
+        let plan = plan_pushdown(Some(&filter));
+        assert_eq!(plan.options, Default::default());
+        assert_eq!(plan.diagnostics.len(), 2);
+        assert!(
+            plan.diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.reason == NotPushableReason::InsideNot)
+        );
+    }
+
+    #[test]
+    fn keeps_like_predicates_client_side() {
+        let predicates = vec![Predicate {
+            path: "metadata.name".to_string(),
+            function: None,
+            op: Operator::Like("api-*".to_string()),
+            value: Value::Null,
+            value_path: None,
+            value_function: None,
+        }];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(plan.options, Default::default());
+        assert_eq!(plan.diagnostics.len(), 1);
+        assert_eq!(
+            plan.diagnostics[0].reason,
+            NotPushableReason::UnsupportedOperator
+        );
+    }
+
+    #[test]
+    fn does_not_push_computed_field_predicates() {
+        let predicates = vec![Predicate {
+            path: "metadata.name".to_string(),
+            function: Some(FieldFunction::ImageTag),
+            op: Operator::Eq,
+            value: Value::String("latest".to_string()),
+            value_path: None,
+            value_function: None,
+        }];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(plan.options, Default::default());
+        assert_eq!(plan.diagnostics.len(), 1);
+        assert_eq!(plan.diagnostics[0].reason, NotPushableReason::ComputedField);
+    }
+
+    #[test]
+    fn handles_duplicate_field_selector_keys_before_request() {
+        let predicate = |path: &str, op: Operator, value: &str| Predicate {
+            path: path.to_string(),
+            function: None,
+            op,
+            value: Value::String(value.to_string()),
+            value_path: None,
+            value_function: None,
+        };
+        let predicates = vec![
+            predicate("metadata.name", Operator::Eq, "a"),
+            predicate("metadata.name", Operator::Eq, "a"),
+            predicate("metadata.name", Operator::Ne, "b"),
+            predicate("metadata.namespace", Operator::Eq, "demo-a"),
+        ];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(
+            plan.options.field_selector.as_deref(),
+            Some("metadata.name=a,metadata.namespace=demo-a")
+        );
+        assert_eq!(plan.diagnostics.len(), 1);
+        assert_eq!(plan.diagnostics[0].op, Operator::Ne);
+        assert_eq!(
+            plan.diagnostics[0].reason,
+            NotPushableReason::DuplicateFieldSelector
+        );
+    }
+
+    #[test]
+    fn does_not_push_wildcard_predicates() {
+        let predicates = vec![Predicate {
+            path: "metadata.labels.app.kubernetes.io/*".to_string(),
+            function: None,
+            op: Operator::Eq,
+            value: Value::String("api".to_string()),
+            value_path: None,
+            value_function: None,
+        }];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(plan.options, Default::default());
+        assert_eq!(plan.diagnostics.len(), 1);
+        assert_eq!(plan.diagnostics[0].reason, NotPushableReason::WildcardPath);
+    }
+
     #[test]
     fn pushes_label_selectors_for_eq_and_ne() {
         let predicates = vec![
             Predicate {
                 path: "metadata.labels.app".to_string(),
+                function: None,
                 op: Operator::Eq,
                 value: Value::String("api".to_string()),
+                value_path: None,
+                value_function: None,
             },
             Predicate {
                 path: "metadata.labels.tier".to_string(),
+                function: None,
                 op: Operator::Ne,
                 value: Value::String("batch".to_string()),
+                value_path: None,
+                value_function: None,
             },
         ];
 
-        let plan = plan_pushdown(&predicates);
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
         assert_eq!(plan.options.field_selector, None);
         assert_eq!(
             plan.options.label_selector.as_deref(),
@@ -173,22 +724,76 @@ mod tests {
         assert!(plan.diagnostics.is_empty());
     }
 
+    #[test]
+    fn pushes_label_exists_as_existence_selector() {
+        let predicates = vec![
+            Predicate {
+                path: "metadata.labels.app".to_string(),
+                function: None,
+                op: Operator::Exists,
+                value: Value::Null,
+                value_path: None,
+                value_function: None,
+            },
+            Predicate {
+                path: "status.conditions".to_string(),
+                function: None,
+                op: Operator::Exists,
+                value: Value::Null,
+                value_path: None,
+                value_function: None,
+            },
+        ];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(plan.options.field_selector, None);
+        assert_eq!(plan.options.label_selector.as_deref(), Some("app"));
+        assert_eq!(plan.diagnostics.len(), 1);
+        assert_eq!(
+            plan.diagnostics[0].reason,
+            NotPushableReason::UnsupportedOperator
+        );
+    }
+
+    #[test]
+    fn reports_range_operator_on_label_as_not_pushable() {
+        let predicates = vec![Predicate {
+            path: "metadata.labels.replicas".to_string(),
+            function: None,
+            op: Operator::Gt,
+            value: Value::from(2),
+            value_path: None,
+            value_function: None,
+        }];
+
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(plan.options, Default::default());
+        assert_eq!(plan.diagnostics.len(), 1);
+        assert_eq!(plan.diagnostics[0].reason, NotPushableReason::RangeOperator);
+    }
+
     #[test]
     fn reports_non_string_and_unsupported_path_as_not_pushable() {
         let predicates = vec![
             Predicate {
                 path: "spec.replicas".to_string(),
+                function: None,
                 op: Operator::Eq,
                 value: Value::from(3),
+                value_path: None,
+                value_function: None,
             },
             Predicate {
                 path: "spec.nodeName".to_string(),
+                function: None,
                 op: Operator::Eq,
                 value: Value::String("worker-a".to_string()),
+                value_path: None,
+                value_function: None,
             },
         ];
 
-        let plan = plan_pushdown(&predicates);
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
         assert_eq!(plan.options.field_selector, None);
         assert_eq!(plan.options.label_selector, None);
         assert_eq!(plan.diagnostics.len(), 2);
@@ -202,29 +807,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pushes_label_values_matching_kubernetes_syntax() {
+        let label = |value: &str| Predicate {
+            path: "metadata.labels.version".to_string(),
+            function: None,
+            op: Operator::Eq,
+            value: Value::String(value.to_string()),
+            value_path: None,
+            value_function: None,
+        };
+
+        for value in ["1.2.3", "v1_2-rc.1", "a", &"x".repeat(63)] {
+            let plan = plan_pushdown(Expr::all(vec![label(value)]).as_ref());
+            assert_eq!(
+                plan.options.label_selector,
+                Some(format!("version={value}"))
+            );
+            assert!(plan.diagnostics.is_empty());
+        }
+        for value in ["-1.2", "1.2.", "a/b", "a:b", "(x)", &"x".repeat(64)] {
+            let plan = plan_pushdown(Expr::all(vec![label(value)]).as_ref());
+            assert_eq!(plan.options.label_selector, None);
+            assert_eq!(
+                plan.diagnostics[0].reason,
+                NotPushableReason::UnsafeSelectorValue
+            );
+        }
+    }
+
     #[test]
     fn reports_unsafe_selector_inputs() {
         let predicates = vec![
             Predicate {
                 path: "metadata.name".to_string(),
+                function: None,
                 op: Operator::Eq,
                 value: Value::String("pod,a".to_string()),
+                value_path: None,
+                value_function: None,
+            },
+            Predicate {
+                path: "metadata.labels.team".to_string(),
+                function: None,
+                op: Operator::Eq,
+                value: Value::String(String::new()),
+                value_path: None,
+                value_function: None,
             },
             Predicate {
                 path: "metadata.labels.bad,key".to_string(),
+                function: None,
                 op: Operator::Eq,
                 value: Value::String("ok".to_string()),
+                value_path: None,
+                value_function: None,
             },
         ];
 
-        let plan = plan_pushdown(&predicates);
-        assert_eq!(plan.diagnostics.len(), 2);
+        let plan = plan_pushdown(Expr::all(predicates).as_ref());
+        assert_eq!(plan.diagnostics.len(), 3);
         assert_eq!(
             plan.diagnostics[0].reason,
             NotPushableReason::UnsafeSelectorValue
         );
         assert_eq!(
             plan.diagnostics[1].reason,
+            NotPushableReason::UnsafeSelectorValue
+        );
+        assert_eq!(
+            plan.diagnostics[2].reason,
             NotPushableReason::UnsafeLabelKey
         );
     }
@@ -0,0 +1,216 @@
+use kube::core::{ApiResource, GroupVersionKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolutionMatch {
+    Plural,
+    Qualified,
+    Kind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolutionCandidate {
+    pub group: String,
+    pub version: String,
+    pub plural: String,
+    pub kind: String,
+    pub matched_by: Option<ResolutionMatch>,
+}
+
+impl ResolutionCandidate {
+    pub fn from_api_resource(api_resource: &ApiResource) -> Self {
+        Self {
+            group: api_resource.group.clone(),
+            version: api_resource.version.clone(),
+            plural: api_resource.plural.clone(),
+            kind: api_resource.kind.clone(),
+            matched_by: None,
+        }
+    }
+
+    pub fn group_version(&self) -> String {
+        if self.group.is_empty() {
+            self.version.clone()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        }
+    }
+
+    pub fn to_api_resource(&self) -> ApiResource {
+        let gvk = GroupVersionKind::gvk(&self.group, &self.version, &self.kind);
+        ApiResource::from_gvk_with_plural(&gvk, &self.plural)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResolutionTrace {
+    pub resource: String,
+    pub considered: Vec<ResolutionCandidate>,
+    pub chosen: Option<usize>,
+}
+
+impl ResolutionTrace {
+    pub fn chosen(&self) -> Option<&ResolutionCandidate> {
+        self.chosen.map(|index| &self.considered[index])
+    }
+
+    pub fn alternatives(&self) -> impl Iterator<Item = &ResolutionCandidate> {
+        self.considered
+            .iter()
+            .enumerate()
+            .filter(move |(index, candidate)| {
+                Some(*index) != self.chosen && candidate.matched_by.is_some()
+            })
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+pub fn trace_resolution(
+    resource: &str,
+    candidates: impl IntoIterator<Item = ResolutionCandidate>,
+) -> ResolutionTrace {
+    let considered: Vec<ResolutionCandidate> = candidates
+        .into_iter()
+        .map(|mut candidate| {
+            candidate.matched_by = match_candidate(&candidate, resource);
+            candidate
+        })
+        .collect();
+    let chosen = first_match(&considered, ResolutionMatch::Plural)
+        .or_else(|| first_match(&considered, ResolutionMatch::Qualified))
+        .or_else(|| first_match(&considered, ResolutionMatch::Kind));
+
+    ResolutionTrace {
+        resource: resource.to_string(),
+        considered,
+        chosen,
+    }
+}
+
+/// Группа из `plural.group` в нижнем регистре: для такого имени
+/// достаточно discovery одной группы вместо полного обхода всех API.
+pub fn qualified_group(resource: &str) -> Option<String> {
+    resource
+        .split_once('.')
+        .map(|(_, group)| group.to_ascii_lowercase())
+        .filter(|group| !group.is_empty())
+}
+
+fn match_candidate(candidate: &ResolutionCandidate, resource: &str) -> Option<ResolutionMatch> {
+    if candidate.plural.eq_ignore_ascii_case(resource) {
+        Some(ResolutionMatch::Plural)
+    } else if is_qualified_match(candidate, resource) {
+        Some(ResolutionMatch::Qualified)
+    } else if candidate.kind.eq_ignore_ascii_case(resource) {
+        Some(ResolutionMatch::Kind)
+    } else {
+        None
+    }
+}
+
+fn is_qualified_match(candidate: &ResolutionCandidate, resource: &str) -> bool {
+    !candidate.group.is_empty()
+        && resource.split_once('.').is_some_and(|(plural, group)| {
+            candidate.plural.eq_ignore_ascii_case(plural)
+                && candidate.group.eq_ignore_ascii_case(group)
+        })
+}
+
+fn first_match(considered: &[ResolutionCandidate], matched_by: ResolutionMatch) -> Option<usize> {
+    considered
+        .iter()
+        .position(|candidate| candidate.matched_by == Some(matched_by))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResolutionCandidate, ResolutionMatch, qualified_group, trace_resolution};
+
+    fn candidate(group: &str, version: &str, plural: &str, kind: &str) -> ResolutionCandidate {
+        ResolutionCandidate {
+            group: group.to_string(),
+            version: version.to_string(),
+            plural: plural.to_string(),
+            kind: kind.to_string(),
+            matched_by: None,
+        }
+    }
+
+    #[test]
+    fn prefers_first_plural_match_and_keeps_alternatives() {
+        let trace = trace_resolution(
+            "events",
+            [
+                candidate("", "v1", "pods", "Pod"),
+                candidate("", "v1", "events", "Event"),
+                candidate("events.k8s.io", "v1", "events", "Event"),
+            ],
+        );
+
+        let chosen = trace.chosen().expect("plural match must be chosen");
+        assert_eq!(chosen.group_version(), "v1");
+        assert_eq!(chosen.matched_by, Some(ResolutionMatch::Plural));
+        let alternatives: Vec<String> = trace
+            .alternatives()
+            .map(ResolutionCandidate::group_version)
+            .collect();
+        assert_eq!(alternatives, vec!["events.k8s.io/v1".to_string()]);
+        assert_eq!(trace.considered[0].matched_by, None);
+    }
+
+    #[test]
+    fn falls_back_to_kind_match() {
+        let trace = trace_resolution(
+            "Deployment",
+            [
+                candidate("apps", "v1", "deployments", "Deployment"),
+                candidate("", "v1", "pods", "Pod"),
+            ],
+        );
+
+        let chosen = trace.chosen().expect("kind match must be chosen");
+        assert_eq!(chosen.plural, "deployments");
+        assert_eq!(chosen.matched_by, Some(ResolutionMatch::Kind));
+        assert_eq!(trace.alternatives().count(), 0);
+    }
+
+    #[test]
+    fn matches_qualified_group_case_insensitively() {
+        let candidates = [
+            candidate("other.io", "v1", "widgets", "Widget"),
+            candidate("Example.com", "v1", "widgets", "Widget"),
+        ];
+
+        for resource in [
+            "Widgets.Example.Com",
+            "widgets.example.com",
+            "WIDGETS.EXAMPLE.COM",
+        ] {
+            let trace = trace_resolution(resource, candidates.clone());
+            let chosen = trace.chosen().expect("qualified match must be chosen");
+            assert_eq!(chosen.group, "Example.com");
+            assert_eq!(chosen.matched_by, Some(ResolutionMatch::Qualified));
+        }
+    }
+
+    #[test]
+    fn reports_no_choice_without_matches() {
+        let trace = trace_resolution("widgets", [candidate("", "v1", "pods", "Pod")]);
+        assert_eq!(trace.chosen(), None);
+    }
+
+    #[test]
+    fn only_group_qualified_resources_narrow_discovery() {
+        assert_eq!(
+            qualified_group("widgets.example.com").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(qualified_group("deployments.apps").as_deref(), Some("apps"));
+        assert_eq!(qualified_group("Deployments.Apps").as_deref(), Some("apps"));
+        assert_eq!(
+            qualified_group("Widgets.Example.COM").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(qualified_group("pods"), None);
+        assert_eq!(qualified_group("pods."), None);
+    }
+}
@@ -6,6 +6,7 @@ pub mod k8s;
 pub mod output;
 pub mod path;
 pub mod parser;
+pub mod snapshot;
 
 pub fn run() -> Result<(), error::CliError> {
     cli::run()
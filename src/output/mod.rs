@@ -1,6 +1,8 @@
-use std::collections::BTreeSet;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
 
-use crate::dynamic_object::DynamicObject;
+use crate::dynamic_object::{CHANGED_FIELD, CONTEXT_FIELD, DynamicObject};
 use crate::error::OutputError;
 use crate::path;
 
@@ -9,6 +11,15 @@ pub enum OutputFormat {
     Table,
     Json,
     Yaml,
+    Value,
+    Histogram,
+    Prometheus,
+    Env,
+    Tsv,
+    Ndjson,
+    Kv,
+    CustomColumns,
+    Name,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -17,86 +28,725 @@ pub enum DetailLevel {
     Describe,
 }
 
+/// Рамка таблицы: ASCII `|`/`-` или box-drawing символы.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TableStyle {
+    #[default]
+    Ascii,
+    Unicode,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub max_value_bytes: Option<usize>,
+    pub duration_columns: Vec<String>,
+    pub max_columns: Option<usize>,
+    pub metric_columns: Vec<String>,
+    pub only_varying: bool,
+    pub bucket: Option<String>,
+    pub single: bool,
+    pub column_names: BTreeMap<String, String>,
+    pub table_style: TableStyle,
+    /// Колонки `(заголовок, путь)` для `OutputFormat::CustomColumns`.
+    pub custom_columns: Vec<(String, String)>,
+}
+
+pub fn load_column_names(path: &str) -> Result<BTreeMap<String, String>, OutputError> {
+    let content = std::fs::read_to_string(path).map_err(|error| OutputError::ColumnNames {
+        path: path.to_string(),
+        message: error.to_string(),
+    })?;
+    parse_column_names(&content).map_err(|message| OutputError::ColumnNames {
+        path: path.to_string(),
+        message,
+    })
+}
+
+/// Разбирает подмножество TOML: строки `"metadata.name" = "Name"`,
+/// пустые строки и комментарии `#`.
+pub fn parse_column_names(content: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut names = BTreeMap::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_number = index + 1;
+        let Some((column, name)) = line.split_once('=') else {
+            return Err(format!(
+                "line {line_number}: expected `\"path\" = \"Name\"`"
+            ));
+        };
+
+        let column = unquote(column.trim());
+        let name = unquote(name.trim());
+        if column.is_empty() || name.is_empty() {
+            return Err(format!(
+                "line {line_number}: path and name must not be empty"
+            ));
+        }
+        if names.insert(column.to_string(), name.to_string()).is_some() {
+            return Err(format!("line {line_number}: duplicate path `{column}`"));
+        }
+    }
+    Ok(names)
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Заголовок колонки в табличном выводе: имя из `--rename-cols` или путь.
+fn column_header<'a>(column: &'a str, options: &'a RenderOptions) -> &'a str {
+    options
+        .column_names
+        .get(column)
+        .map_or(column, String::as_str)
+}
+
 pub fn print(
     objects: &[DynamicObject],
     format: OutputFormat,
     detail: DetailLevel,
     select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> Result<(), OutputError> {
+    print_to(
+        &mut std::io::stdout().lock(),
+        objects,
+        format,
+        detail,
+        select_paths,
+        options,
+    )
+}
+
+/// Пишет результат в `out` и сразу делает `flush`, чтобы потребитель
+/// в pipe (`jq`, `tee`) получил данные без ожидания буфера.
+pub fn print_to(
+    out: &mut impl Write,
+    objects: &[DynamicObject],
+    format: OutputFormat,
+    detail: DetailLevel,
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
 ) -> Result<(), OutputError> {
     let content = match format {
-        OutputFormat::Table => render_table(objects, detail, select_paths),
-        OutputFormat::Json => render_json(objects, detail, select_paths)?,
-        OutputFormat::Yaml => render_yaml(objects, detail, select_paths)?,
+        OutputFormat::Ndjson => return write_ndjson(out, objects, detail, select_paths, options),
+        OutputFormat::Name => return write_names(out, objects),
+        OutputFormat::Table => render_table(objects, detail, select_paths, options),
+        OutputFormat::Json => render_json(objects, detail, select_paths, options)?,
+        OutputFormat::Yaml => render_yaml(objects, detail, select_paths, options)?,
+        OutputFormat::Value => render_value(objects, detail, select_paths, options)?,
+        OutputFormat::Histogram => render_histogram(objects, select_paths, options)?,
+        OutputFormat::Prometheus => render_prometheus(objects, select_paths, options)?,
+        OutputFormat::Env => render_env(objects, select_paths, options)?,
+        OutputFormat::Tsv => render_tsv(objects, detail, select_paths, options),
+        OutputFormat::Kv => render_kv(objects, detail, select_paths, options),
+        OutputFormat::CustomColumns => render_custom_columns(objects, options),
     };
-    println!("{content}");
-    Ok(())
+    write_line(out, &content)
+}
+
+fn write_line(out: &mut impl Write, line: &str) -> Result<(), OutputError> {
+    writeln!(out, "{line}")
+        .and_then(|()| out.flush())
+        .map_err(|source| OutputError::Write { source })
 }
 
 pub fn render_json(
     objects: &[DynamicObject],
     detail: DetailLevel,
     select_paths: Option<&[String]>,
+    options: &RenderOptions,
 ) -> Result<String, OutputError> {
-    let rows: Vec<_> = objects
+    let projected: Vec<_> = objects
         .iter()
-        .map(|object| project_fields(object, detail, select_paths))
+        .map(|object| project_fields(object, detail, select_paths, options))
         .collect();
+    let columns = collect_columns(&projected, select_paths);
+    let rows = ordered_rows(&projected, &columns);
     serde_json::to_string_pretty(&rows).map_err(|source| OutputError::JsonSerialize { source })
 }
 
+/// Один компактный JSON-объект на строку без обрамляющего массива; каждая
+/// строка пишется и flush-ится сразу, пустой результат не печатает ничего.
+pub fn write_ndjson(
+    out: &mut impl Write,
+    objects: &[DynamicObject],
+    detail: DetailLevel,
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> Result<(), OutputError> {
+    for object in objects {
+        let fields = project_fields(object, detail, select_paths, options);
+        let columns = collect_columns(std::slice::from_ref(&fields), select_paths);
+        let row = OrderedRow {
+            fields: &fields,
+            columns: &columns,
+        };
+        let line =
+            serde_json::to_string(&row).map_err(|source| OutputError::JsonSerialize { source })?;
+        write_line(out, &line)?;
+    }
+    Ok(())
+}
+
 pub fn render_yaml(
     objects: &[DynamicObject],
     detail: DetailLevel,
     select_paths: Option<&[String]>,
+    options: &RenderOptions,
 ) -> Result<String, OutputError> {
-    let rows: Vec<_> = objects
+    let projected: Vec<_> = objects
         .iter()
-        .map(|object| project_fields(object, detail, select_paths))
+        .map(|object| project_fields(object, detail, select_paths, options))
         .collect();
-    serde_yaml::to_string(&rows).map_err(|source| OutputError::YamlSerialize { source })
+    let columns = collect_columns(&projected, select_paths);
+    let rows = ordered_rows(&projected, &columns);
+    let serialized = match rows.as_slice() {
+        [row] if options.single => serde_yaml::to_string(row),
+        _ => serde_yaml::to_string(&rows),
+    };
+    serialized.map_err(|source| OutputError::YamlSerialize { source })
 }
 
-pub fn render_table(
+pub fn render_value(
+    objects: &[DynamicObject],
+    detail: DetailLevel,
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> Result<String, OutputError> {
+    let projected: Vec<_> = objects
+        .iter()
+        .map(|object| project_fields(object, detail, select_paths, options))
+        .collect();
+    let columns = collect_columns(&projected, select_paths);
+    let [row] = projected.as_slice() else {
+        return Err(OutputError::NotSingleValue {
+            rows: projected.len(),
+            columns: columns.len(),
+        });
+    };
+    let [column] = columns.as_slice() else {
+        return Err(OutputError::NotSingleValue {
+            rows: 1,
+            columns: columns.len(),
+        });
+    };
+
+    match row.get(column) {
+        Some(serde_json::Value::Array(_) | serde_json::Value::Object(_)) => {
+            Err(OutputError::NonScalarValue {
+                column: column.clone(),
+            })
+        }
+        Some(value) => Ok(value_to_cell(value)),
+        None => Ok(serde_json::Value::Null.to_string()),
+    }
+}
+
+pub fn render_histogram(
+    objects: &[DynamicObject],
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> Result<String, OutputError> {
+    let Some([label_path, count_path]) = select_paths else {
+        return Err(OutputError::HistogramShape {
+            columns: select_paths.map_or(0, <[String]>::len),
+        });
+    };
+
+    let mut bars = Vec::with_capacity(objects.len());
+    for object in objects {
+        let object = elide_large_values(object, options.max_value_bytes);
+        let label = select_value(&object, label_path)
+            .map(|value| value_to_cell(&value))
+            .unwrap_or_else(|| "-".to_string());
+        let value = select_value(&object, count_path);
+        let count = value
+            .as_ref()
+            .and_then(serde_json::Value::as_f64)
+            .filter(|count| *count >= 0.0)
+            .ok_or_else(|| OutputError::HistogramCount {
+                label: label.clone(),
+                column: count_path.clone(),
+            })?;
+        bars.push((label, count, value.map(|value| value_to_cell(&value))));
+    }
+    if bars.is_empty() {
+        return Ok("items: 0".to_string());
+    }
+
+    let max_count = bars.iter().map(|(_, count, _)| *count).fold(0.0, f64::max);
+    let label_width = bars
+        .iter()
+        .map(|(label, _, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<String> = bars
+        .iter()
+        .map(|(label, count, cell)| {
+            let bar = HISTOGRAM_BAR.repeat(histogram_bar_len(*count, max_count));
+            let cell = cell.as_deref().unwrap_or_default();
+            format!("{label:<label_width$} {bar} {cell}")
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// Prometheus exposition: метрика на каждую aggregation-колонку из
+/// `options.metric_columns`, остальные колонки становятся labels.
+pub fn render_prometheus(
+    objects: &[DynamicObject],
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> Result<String, OutputError> {
+    let columns = select_paths.unwrap_or_default();
+    let (metrics, labels): (Vec<&String>, Vec<&String>) = columns
+        .iter()
+        .partition(|column| options.metric_columns.contains(column));
+    if metrics.is_empty() {
+        return Err(OutputError::PrometheusShape);
+    }
+
+    let mut lines = Vec::new();
+    for metric in metrics {
+        let (name, argument) = prometheus_metric_name(metric);
+        lines.push(format!("# TYPE {name} gauge"));
+        for object in objects {
+            let sample = match select_value(object, metric) {
+                None | Some(serde_json::Value::Null) => continue,
+                Some(serde_json::Value::Number(number)) => number.to_string(),
+                Some(_) => {
+                    return Err(OutputError::PrometheusValue {
+                        column: metric.clone(),
+                    });
+                }
+            };
+            let mut pairs: Vec<String> = labels
+                .iter()
+                .filter_map(|label| {
+                    let value = select_value(object, label)?;
+                    Some(format!(
+                        "{}=\"{}\"",
+                        prometheus_label_name(label),
+                        escape_prometheus_label_value(&value_to_cell(&value))
+                    ))
+                })
+                .collect();
+            if let Some(argument) = argument {
+                pairs.push(format!(
+                    "field=\"{}\"",
+                    escape_prometheus_label_value(argument)
+                ));
+            }
+            if pairs.is_empty() {
+                lines.push(format!("{name} {sample}"));
+            } else {
+                lines.push(format!("{name}{{{}}} {sample}", pairs.join(",")));
+            }
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+fn prometheus_metric_name(column: &str) -> (String, Option<&str>) {
+    let (function, argument) = column
+        .split_once('(')
+        .map_or((column, None), |(function, rest)| {
+            (function, rest.strip_suffix(')'))
+        });
+    let argument = argument.filter(|argument| *argument != "*");
+    (
+        format!("kubiq_{}", sanitize_prometheus_name(function)),
+        argument,
+    )
+}
+
+/// `KEY=value` на каждую агрегацию единственной строки результата,
+/// чтобы вывод можно было подключить через `source`/`eval`.
+pub fn render_env(
+    objects: &[DynamicObject],
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> Result<String, OutputError> {
+    let columns = select_paths.unwrap_or_default();
+    let [object] = objects else {
+        return Err(OutputError::EnvShape);
+    };
+    if columns.is_empty()
+        || !columns
+            .iter()
+            .all(|column| options.metric_columns.contains(column))
+    {
+        return Err(OutputError::EnvShape);
+    }
+
+    let lines: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            let value = match select_value(object, column) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(value) => shell_quote(&value_to_cell(&value)),
+            };
+            format!("{}={value}", env_key(column))
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// Строка заголовка и по строке на объект, колонки разделены табуляцией.
+/// Отсутствующее значение и `null` дают пустую ячейку; табуляция, перевод
+/// строки и `\` внутри значения экранируются, чтобы строка не распалась.
+pub fn render_tsv(
     objects: &[DynamicObject],
     detail: DetailLevel,
     select_paths: Option<&[String]>,
+    options: &RenderOptions,
 ) -> String {
     let projected: Vec<_> = objects
         .iter()
-        .map(|object| project_fields(object, detail, select_paths))
+        .map(|object| project_fields(object, detail, select_paths, options))
+        .collect();
+    let columns = collect_columns(&projected, select_paths);
+    if columns.is_empty() {
+        return String::new();
+    }
+
+    let mut lines = Vec::with_capacity(projected.len() + 1);
+    let header: Vec<String> = columns
+        .iter()
+        .map(|column| escape_tsv_cell(column_header(column, options)))
+        .collect();
+    lines.push(header.join("\t"));
+    for fields in &projected {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| match fields.get(column) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(value) => escape_tsv_cell(&value_to_cell(value)),
+            })
+            .collect();
+        lines.push(cells.join("\t"));
+    }
+    lines.join("\n")
+}
+
+/// Блок строк `path=value` на объект (блоки разделены пустой строкой):
+/// спроецированные поля снова раскладываются в flattened пути и
+/// сортируются; `null` -> пустое значение, переводы строк экранируются.
+pub fn render_kv(
+    objects: &[DynamicObject],
+    detail: DetailLevel,
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> String {
+    let blocks: Vec<String> = objects
+        .iter()
+        .map(|object| {
+            let mut lines = BTreeMap::new();
+            for (key, value) in project_fields(object, detail, select_paths, options) {
+                match value {
+                    serde_json::Value::Object(_) | serde_json::Value::Array(_) => {
+                        let nested =
+                            path::flatten_json_with_array_mode(&value, path::ArrayMode::Indexed);
+                        for (suffix, leaf) in nested {
+                            let path = format!("{key}.{}", path::decode_path(&suffix));
+                            lines.insert(path, value_to_cell(&leaf));
+                        }
+                    }
+                    serde_json::Value::Null => {
+                        lines.insert(key, String::new());
+                    }
+                    value => {
+                        lines.insert(key, value_to_cell(&value));
+                    }
+                }
+            }
+            lines
+                .into_iter()
+                .map(|(path, value)| format!("{path}={}", escape_tsv_cell(&value)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect();
+    blocks.join("\n\n")
+}
+
+fn escape_tsv_cell(cell: &str) -> String {
+    let mut escaped = String::with_capacity(cell.len());
+    for c in cell.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `sum(spec.replicas)` -> `SUM_SPEC_REPLICAS`, `count(*)` -> `COUNT`.
+fn env_key(column: &str) -> String {
+    let mut key = String::with_capacity(column.len());
+    for c in column.chars() {
+        if c.is_ascii_alphanumeric() {
+            key.push(c.to_ascii_uppercase());
+        } else if !key.is_empty() && !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    let key = key.trim_end_matches('_');
+    if key.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{key}")
+    } else {
+        key.to_string()
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '/' | ':' | '+'));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn prometheus_label_name(column: &str) -> String {
+    let segment = column.rsplit('.').next().unwrap_or(column);
+    let name = sanitize_prometheus_name(segment);
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+fn sanitize_prometheus_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn escape_prometheus_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+const HISTOGRAM_BAR: &str = "\u{2588}";
+const HISTOGRAM_WIDTH: usize = 40;
+
+fn histogram_bar_len(count: f64, max_count: f64) -> usize {
+    if max_count <= 0.0 {
+        return 0;
+    }
+    (count / max_count * HISTOGRAM_WIDTH as f64).round() as usize
+}
+
+pub fn render_table(
+    objects: &[DynamicObject],
+    detail: DetailLevel,
+    select_paths: Option<&[String]>,
+    options: &RenderOptions,
+) -> String {
+    let mut buckets: Vec<Option<String>> = vec![None; objects.len()];
+    let mut projected: Vec<_> = objects
+        .iter()
+        .map(|object| project_fields(object, detail, select_paths, options))
         .collect();
-    let columns = collect_columns(&projected);
+    if let Some(bucket_path) = options.bucket.as_deref() {
+        let mut keyed: Vec<_> = objects
+            .iter()
+            .map(|object| {
+                select_value(object, bucket_path)
+                    .filter(|value| !value.is_null())
+                    .map_or_else(|| "-".to_string(), |value| value_to_cell(&value))
+            })
+            .zip(projected)
+            .collect();
+        keyed.sort_by(|(left, _), (right, _)| left.cmp(right));
+        let mut previous: Option<String> = None;
+        projected = Vec::with_capacity(keyed.len());
+        for (index, (key, fields)) in keyed.into_iter().enumerate() {
+            if previous.as_ref() != Some(&key) {
+                buckets[index] = Some(format!("[{bucket_path} = {key}]"));
+                previous = Some(key);
+            }
+            projected.push(fields);
+        }
+    }
+    let mut columns = collect_columns(&projected, select_paths);
     if columns.is_empty() {
         return "items: 0".to_string();
     }
+    if options.only_varying {
+        retain_varying_columns(&mut columns, &projected);
+    }
+    let hidden_columns = cap_columns(&mut columns, options.max_columns);
 
-    let widths = compute_widths(&projected, &columns);
+    let mut widths = compute_widths(&projected, &columns, options);
+    let mut header: Vec<String> = columns
+        .iter()
+        .map(|column| column_header(column, options).to_string())
+        .collect();
+    if hidden_columns > 0 {
+        let indicator = format!("{HIDDEN_COLUMNS_CELL}(+{hidden_columns} more)");
+        widths.push(display_width(&indicator));
+        header.push(indicator);
+    }
+    let style = options.table_style;
     let mut lines = Vec::new();
-    lines.push(format_row(&columns, &widths));
-    lines.push(format_separator(&widths));
+    if style == TableStyle::Unicode {
+        lines.push(format_rule(&widths, ['\u{250c}', '\u{252c}', '\u{2510}']));
+    }
+    lines.push(format_row(&header, &widths, style));
+    lines.push(format_separator(&widths, style));
 
-    for fields in projected {
-        let row: Vec<String> = columns
+    for (fields, bucket) in projected.into_iter().zip(buckets) {
+        if let Some(bucket) = bucket {
+            lines.push(bucket);
+        }
+        let mut row: Vec<String> = columns
             .iter()
             .map(|column| {
                 fields
                     .get(column)
-                    .map(value_to_cell)
+                    .map(|value| table_cell(column, value, options))
                     .unwrap_or_else(|| "-".to_string())
             })
             .collect();
-        lines.push(format_row(&row, &widths));
+        if hidden_columns > 0 {
+            row.push(HIDDEN_COLUMNS_CELL.to_string());
+        }
+        lines.push(format_row(&row, &widths, style));
+    }
+
+    if style == TableStyle::Unicode {
+        lines.push(format_rule(&widths, ['\u{2514}', '\u{2534}', '\u{2518}']));
+    }
+    lines.push(format!("items: {}", objects.len()));
+    lines.join("\n")
+}
+
+/// Только `metadata.name` каждого объекта, по одному на строку, без
+/// заголовка и `items:`; `select` и `--describe` не учитываются, пустой
+/// результат не печатает ничего.
+pub fn write_names(out: &mut impl Write, objects: &[DynamicObject]) -> Result<(), OutputError> {
+    for object in objects {
+        let name = object
+            .get("metadata.name")
+            .filter(|value| !value.is_null())
+            .map_or_else(|| "-".to_string(), value_to_cell);
+        write_line(out, &name)?;
     }
+    Ok(())
+}
+
+/// Таблица по `options.custom_columns` в порядке спецификации: заголовок
+/// задан пользователем, ячейка — значение `DynamicObject::get(path)`.
+pub fn render_custom_columns(objects: &[DynamicObject], options: &RenderOptions) -> String {
+    let header: Vec<String> = options
+        .custom_columns
+        .iter()
+        .map(|(header, _)| header.clone())
+        .collect();
+    let rows: Vec<Vec<String>> = objects
+        .iter()
+        .map(|object| {
+            options
+                .custom_columns
+                .iter()
+                .map(|(_, path)| {
+                    object
+                        .get(path)
+                        .filter(|value| !value.is_null())
+                        .map_or_else(|| "-".to_string(), value_to_cell)
+                })
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            rows.iter()
+                .map(|row| display_width(&row[index]))
+                .fold(display_width(header), usize::max)
+        })
+        .collect();
 
+    let style = options.table_style;
+    let mut lines = Vec::new();
+    if style == TableStyle::Unicode {
+        lines.push(format_rule(&widths, ['\u{250c}', '\u{252c}', '\u{2510}']));
+    }
+    lines.push(format_row(&header, &widths, style));
+    lines.push(format_separator(&widths, style));
+    lines.extend(rows.iter().map(|row| format_row(row, &widths, style)));
+    if style == TableStyle::Unicode {
+        lines.push(format_rule(&widths, ['\u{2514}', '\u{2534}', '\u{2518}']));
+    }
     lines.push(format!("items: {}", objects.len()));
     lines.join("\n")
 }
 
+const HIDDEN_COLUMNS_CELL: &str = "\u{2026}";
+const ALWAYS_KEPT_COLUMNS: &[&str] = &["name", "metadata.name"];
+
+/// Убирает колонки с одинаковым значением во всех строках (отсутствие
+/// значения тоже считается значением); имя объекта остается всегда.
+fn retain_varying_columns(
+    columns: &mut Vec<String>,
+    projected: &[std::collections::BTreeMap<String, serde_json::Value>],
+) {
+    if projected.len() < 2 {
+        return;
+    }
+    columns.retain(|column| {
+        ALWAYS_KEPT_COLUMNS.contains(&column.as_str()) || {
+            let first = projected[0].get(column);
+            projected[1..]
+                .iter()
+                .any(|fields| fields.get(column) != first)
+        }
+    });
+}
+
+fn cap_columns(columns: &mut Vec<String>, max_columns: Option<usize>) -> usize {
+    let Some(max_columns) = max_columns else {
+        return 0;
+    };
+    let hidden = columns.len().saturating_sub(max_columns);
+    columns.truncate(max_columns);
+    hidden
+}
+
 fn project_fields(
     object: &DynamicObject,
     detail: DetailLevel,
     select_paths: Option<&[String]>,
+    options: &RenderOptions,
 ) -> std::collections::BTreeMap<String, serde_json::Value> {
+    let object = elide_large_values(object, options.max_value_bytes);
+    let object = object.as_ref();
+
     if let Some(select_paths) = select_paths {
         let mut projected = std::collections::BTreeMap::new();
         for path in select_paths {
@@ -116,11 +766,54 @@ fn project_fields(
                 .cloned()
                 .unwrap_or_else(|| serde_json::Value::String("-".to_string()));
             projected.insert("name".to_string(), name);
+            for extra in [CONTEXT_FIELD, CHANGED_FIELD] {
+                if let Some(value) = object.fields.get(extra) {
+                    projected.insert(extra.to_string(), value.clone());
+                }
+            }
             projected
         }
     }
 }
 
+fn elide_large_values(
+    object: &DynamicObject,
+    max_value_bytes: Option<usize>,
+) -> Cow<'_, DynamicObject> {
+    let Some(max_value_bytes) = max_value_bytes else {
+        return Cow::Borrowed(object);
+    };
+    if object
+        .fields
+        .values()
+        .all(|value| value_size_bytes(value) <= max_value_bytes)
+    {
+        return Cow::Borrowed(object);
+    }
+
+    let fields = object
+        .fields
+        .iter()
+        .map(|(path, value)| {
+            let size = value_size_bytes(value);
+            let value = if size > max_value_bytes {
+                serde_json::Value::String(format!("<{size} bytes omitted>"))
+            } else {
+                value.clone()
+            };
+            (path.clone(), value)
+        })
+        .collect();
+    Cow::Owned(DynamicObject { fields })
+}
+
+fn value_size_bytes(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::String(s) => s.len(),
+        _ => value.to_string().len(),
+    }
+}
+
 fn nested_fields_map(
     object: &DynamicObject
 ) -> std::collections::BTreeMap<String, serde_json::Value> {
@@ -139,9 +832,23 @@ fn select_value(
     path::select_path_value(&object.fields, path)
 }
 
+/// Колонки в порядке `select`, если пути заданы; иначе отсортированное
+/// объединение ключей всех строк. Без строк колонок нет.
 fn collect_columns(
-    objects: &[std::collections::BTreeMap<String, serde_json::Value>]
+    objects: &[std::collections::BTreeMap<String, serde_json::Value>],
+    select_paths: Option<&[String]>,
 ) -> Vec<String> {
+    if objects.is_empty() {
+        return Vec::new();
+    }
+    if let Some(select_paths) = select_paths {
+        let mut seen = BTreeSet::new();
+        return select_paths
+            .iter()
+            .filter(|path| seen.insert(path.as_str()))
+            .cloned()
+            .collect();
+    }
     let mut set = BTreeSet::new();
     for fields in objects {
         for key in fields.keys() {
@@ -151,26 +858,89 @@ fn collect_columns(
     set.into_iter().collect()
 }
 
+/// Строка для сериализации: ключи идут в порядке `columns`, а не
+/// в алфавитном порядке `BTreeMap`.
+struct OrderedRow<'a> {
+    fields: &'a BTreeMap<String, serde_json::Value>,
+    columns: &'a [String],
+}
+
+impl serde::Serialize for OrderedRow<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for column in self.columns {
+            if let Some(value) = self.fields.get(column) {
+                map.serialize_entry(column, value)?;
+            }
+        }
+        map.end()
+    }
+}
+
+fn ordered_rows<'a>(
+    projected: &'a [BTreeMap<String, serde_json::Value>],
+    columns: &'a [String],
+) -> Vec<OrderedRow<'a>> {
+    projected
+        .iter()
+        .map(|fields| OrderedRow { fields, columns })
+        .collect()
+}
+
 fn compute_widths(
     objects: &[std::collections::BTreeMap<String, serde_json::Value>],
     columns: &[String],
+    options: &RenderOptions,
 ) -> Vec<usize> {
     columns
         .iter()
         .map(|column| {
-            let mut width = column.len();
+            let mut width = display_width(column_header(column, options));
             for fields in objects {
                 let cell = fields
                     .get(column)
-                    .map(value_to_cell)
+                    .map(|value| table_cell(column, value, options))
                     .unwrap_or_else(|| "-".to_string());
-                width = width.max(cell.len());
+                width = width.max(display_width(&cell));
             }
             width
         })
         .collect()
 }
 
+fn table_cell(column: &str, value: &serde_json::Value, options: &RenderOptions) -> String {
+    if options
+        .duration_columns
+        .iter()
+        .any(|duration| duration == column)
+        && let Some(seconds) = value.as_f64()
+    {
+        return format_duration(seconds.round() as i64);
+    }
+    value_to_cell(value)
+}
+
+fn format_duration(seconds: i64) -> String {
+    const UNITS: [(i64, &str); 4] = [(86_400, "d"), (3_600, "h"), (60, "m"), (1, "s")];
+
+    let sign = if seconds < 0 { "-" } else { "" };
+    let mut remaining = seconds.unsigned_abs() as i64;
+    let mut parts = Vec::new();
+    for (unit_seconds, suffix) in UNITS {
+        let amount = remaining / unit_seconds;
+        if amount > 0 || (parts.is_empty() && unit_seconds == 1) {
+            parts.push(format!("{amount}{suffix}"));
+            remaining -= amount * unit_seconds;
+        }
+        if parts.len() == 2 || (!parts.is_empty() && remaining == 0) {
+            break;
+        }
+    }
+    format!("{sign}{}", parts.concat())
+}
+
 fn value_to_cell(value: &serde_json::Value) -> String {
     match value {
         serde_json::Value::String(s) => s.clone(),
@@ -178,26 +948,36 @@ fn value_to_cell(value: &serde_json::Value) -> String {
     }
 }
 
-fn format_row(
-    cells: &[String],
-    widths: &[usize],
-) -> String {
+/// Ширина ячейки в символах, а не в байтах, чтобы `…` и не-ASCII
+/// значения не сдвигали рамку.
+fn display_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn format_row(cells: &[String], widths: &[usize], style: TableStyle) -> String {
+    let border = match style {
+        TableStyle::Ascii => '|',
+        TableStyle::Unicode => '\u{2502}',
+    };
     let mut out = String::new();
-    out.push('|');
+    out.push(border);
     for (index, cell) in cells.iter().enumerate() {
         out.push(' ');
         out.push_str(cell);
-        let padding = widths[index].saturating_sub(cell.len());
+        let padding = widths[index].saturating_sub(display_width(cell));
         for _ in 0..padding {
             out.push(' ');
         }
         out.push(' ');
-        out.push('|');
+        out.push(border);
     }
     out
 }
 
-fn format_separator(widths: &[usize]) -> String {
+fn format_separator(widths: &[usize], style: TableStyle) -> String {
+    if style == TableStyle::Unicode {
+        return format_rule(widths, ['\u{251c}', '\u{253c}', '\u{2524}']);
+    }
     let mut out = String::new();
     out.push('|');
     for width in widths {
@@ -211,6 +991,21 @@ fn format_separator(widths: &[usize]) -> String {
     out
 }
 
+/// Горизонтальная линия из `─` для unicode-рамки: `[левый, стык, правый]`
+/// угол или перекресток на местах вертикальных границ.
+fn format_rule(widths: &[usize], [left, joint, right]: [char; 3]) -> String {
+    let mut out = String::new();
+    out.push(left);
+    for (index, width) in widths.iter().enumerate() {
+        if index > 0 {
+            out.push(joint);
+        }
+        out.extend(std::iter::repeat_n('\u{2500}', width + 2));
+    }
+    out.push(right);
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -219,7 +1014,13 @@ mod tests {
 
     use crate::dynamic_object::DynamicObject;
 
-    use super::{DetailLevel, render_json, render_table, render_yaml};
+    use super::{
+        DetailLevel, HISTOGRAM_WIDTH, OutputFormat, RenderOptions, TableStyle, format_duration,
+        parse_column_names, print_to, render_custom_columns, render_env, render_histogram,
+        render_json, render_kv, render_prometheus, render_table, render_tsv, render_value,
+        render_yaml,
+    };
+    use crate::error::OutputError;
 
     #[test]
     fn renders_table_with_columns_and_count() {
@@ -232,7 +1033,12 @@ mod tests {
             "metadata.namespace".to_string(),
             Value::String("demo-a".to_string()),
         );
-        let out = render_table(&[DynamicObject { fields }], DetailLevel::Describe, None);
+        let out = render_table(
+            &[DynamicObject { fields }],
+            DetailLevel::Describe,
+            None,
+            &RenderOptions::default(),
+        );
 
         assert!(out.contains("metadata"));
         assert!(out.contains("pod-a"));
@@ -240,14 +1046,193 @@ mod tests {
     }
 
     #[test]
-    fn renders_json_array() {
-        let mut fields = BTreeMap::new();
+    fn renders_unicode_table_style_with_box_drawing_borders() {
+        let pod = |name: &str, phase: &str| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::from(name));
+            fields.insert("status.phase".to_string(), Value::from(phase));
+            DynamicObject { fields }
+        };
+        let select = ["metadata.name".to_string(), "status.phase".to_string()];
+        let options = RenderOptions {
+            table_style: TableStyle::Unicode,
+            ..RenderOptions::default()
+        };
+
+        let out = render_table(
+            &[pod("api", "Running"), pod("café", "Pending")],
+            DetailLevel::Describe,
+            Some(&select),
+            &options,
+        );
+
+        assert_eq!(
+            out,
+            [
+                "┌───────────────┬──────────────┐",
+                "│ metadata.name │ status.phase │",
+                "├───────────────┼──────────────┤",
+                "│ api           │ Running      │",
+                "│ café          │ Pending      │",
+                "└───────────────┴──────────────┘",
+                "items: 2",
+            ]
+            .join("\n")
+        );
+        let ascii = render_table(
+            &[pod("api", "Running")],
+            DetailLevel::Describe,
+            Some(&select),
+            &RenderOptions::default(),
+        );
+        assert!(ascii.starts_with("| metadata.name | status.phase |"));
+    }
+
+    #[test]
+    fn renders_kv_blocks_of_sorted_flattened_paths() {
+        let pod = |name: &str, phase: Value| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::from(name));
+            fields.insert(
+                "metadata.labels.app.kubernetes.io/name".to_string(),
+                Value::from("api"),
+            );
+            fields.insert("spec.containers.0.image".to_string(), Value::from("nginx"));
+            fields.insert("spec.replicas".to_string(), Value::from(2));
+            fields.insert("status.phase".to_string(), phase);
+            DynamicObject { fields }
+        };
+        let objects = [
+            pod("pod-a", Value::from("Running")),
+            pod("pod-b", Value::from("a\nb")),
+        ];
+
+        let out = render_kv(
+            &objects,
+            DetailLevel::Describe,
+            None,
+            &RenderOptions::default(),
+        );
+        let block = |name: &str, phase: &str| {
+            [
+                "metadata.labels.app.kubernetes.io/name=api".to_string(),
+                format!("metadata.name={name}"),
+                "spec.containers.0.image=nginx".to_string(),
+                "spec.replicas=2".to_string(),
+                format!("status.phase={phase}"),
+            ]
+            .join("\n")
+        };
+        assert_eq!(
+            out,
+            format!(
+                "{}\n\n{}",
+                block("pod-a", "Running"),
+                block("pod-b", "a\\nb")
+            )
+        );
+
+        let select = ["status.phase".to_string(), "metadata.uid".to_string()];
+        let options = RenderOptions::default();
+        let out = render_kv(
+            &objects[..1],
+            DetailLevel::Describe,
+            Some(&select),
+            &options,
+        );
+        assert_eq!(out, "metadata.uid=\nstatus.phase=Running");
+        assert_eq!(render_kv(&[], DetailLevel::Describe, None, &options), "");
+    }
+
+    #[test]
+    fn name_output_prints_one_name_per_line_ignoring_select() {
+        let mut named = BTreeMap::new();
+        named.insert("metadata.name".to_string(), Value::from("pod-a"));
+        named.insert("status.phase".to_string(), Value::from("Running"));
+        let mut unnamed = BTreeMap::new();
+        unnamed.insert("status.phase".to_string(), Value::from("Pending"));
+        let objects = [
+            DynamicObject { fields: named },
+            DynamicObject { fields: unnamed },
+        ];
+
+        let select = ["status.phase".to_string()];
+        let mut out = Vec::new();
+        print_to(
+            &mut out,
+            &objects,
+            OutputFormat::Name,
+            DetailLevel::Describe,
+            Some(&select),
+            &RenderOptions::default(),
+        )
+        .expect("name output must render");
+        assert_eq!(
+            String::from_utf8(out).expect("output must be utf-8"),
+            "pod-a\n-\n"
+        );
+
+        let mut empty = Vec::new();
+        print_to(
+            &mut empty,
+            &[],
+            OutputFormat::Name,
+            DetailLevel::Summary,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("name output must render");
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn renders_custom_columns_in_spec_order_with_user_headers() {
+        let pod = |name: &str, phase: Option<&str>| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::from(name));
+            if let Some(phase) = phase {
+                fields.insert("status.phase".to_string(), Value::from(phase));
+            }
+            DynamicObject { fields }
+        };
+        let options = RenderOptions {
+            custom_columns: vec![
+                ("PHASE".to_string(), "status.phase".to_string()),
+                ("NAME".to_string(), "metadata.name".to_string()),
+            ],
+            ..RenderOptions::default()
+        };
+
+        let objects = [pod("pod-a", Some("Running")), pod("pod-b", None)];
+        let out = render_custom_columns(&objects, &options);
+
+        assert_eq!(
+            out,
+            [
+                "| PHASE   | NAME  |",
+                "| ------- | ----- |",
+                "| Running | pod-a |",
+                "| -       | pod-b |",
+                "items: 2",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn renders_json_array() {
+        let mut fields = BTreeMap::new();
         fields.insert(
             "metadata.name".to_string(),
             Value::String("pod-a".to_string()),
         );
-        let out = render_json(&[DynamicObject { fields }], DetailLevel::Describe, None)
-            .expect("json output must serialize");
+        let out = render_json(
+            &[DynamicObject { fields }],
+            DetailLevel::Describe,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("json output must serialize");
 
         assert!(out.starts_with("["));
         assert!(out.contains("\"metadata\": {"));
@@ -261,14 +1246,103 @@ mod tests {
             "metadata.name".to_string(),
             Value::String("pod-a".to_string()),
         );
-        let out = render_yaml(&[DynamicObject { fields }], DetailLevel::Describe, None)
-            .expect("yaml output must serialize");
+        let out = render_yaml(
+            &[DynamicObject { fields }],
+            DetailLevel::Describe,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("yaml output must serialize");
 
         assert!(out.starts_with("-"));
         assert!(out.contains("metadata:"));
         assert!(out.contains("name: pod-a"));
     }
 
+    #[test]
+    fn renders_yaml_describe_with_same_nested_shape_as_json() {
+        let mut fields = BTreeMap::new();
+        fields.insert("metadata.name".to_string(), Value::from("pod-a"));
+        fields.insert("metadata.labels.app".to_string(), Value::from("api"));
+        fields.insert("spec.containers.0.image".to_string(), Value::from("nginx"));
+        fields.insert("spec.replicas".to_string(), Value::from(2));
+        let objects = [DynamicObject { fields }];
+        let options = RenderOptions::default();
+
+        let yaml = render_yaml(&objects, DetailLevel::Describe, None, &options)
+            .expect("yaml output must serialize");
+        let json = render_json(&objects, DetailLevel::Describe, None, &options)
+            .expect("json output must serialize");
+        let from_yaml: Value = serde_yaml::from_str(&yaml).expect("yaml must parse");
+        let from_json: Value = serde_json::from_str(&json).expect("json must parse");
+        assert_eq!(from_yaml, from_json);
+        assert_eq!(
+            from_yaml[0]["metadata"]["labels"]["app"],
+            Value::from("api")
+        );
+    }
+
+    #[test]
+    fn table_and_json_keep_select_column_order() {
+        let mut fields = BTreeMap::new();
+        fields.insert("metadata.name".to_string(), Value::from("pod-a"));
+        fields.insert("metadata.namespace".to_string(), Value::from("demo-a"));
+        let objects = [DynamicObject { fields }];
+        let select = [
+            "metadata.namespace".to_string(),
+            "metadata.name".to_string(),
+        ];
+        let options = RenderOptions::default();
+
+        let table = render_table(&objects, DetailLevel::Summary, Some(&select), &options);
+        let header = table.lines().next().expect("table must have a header");
+        assert_eq!(header, "| metadata.namespace | metadata.name |");
+
+        let json = render_json(&objects, DetailLevel::Summary, Some(&select), &options)
+            .expect("json output must serialize");
+        let compact: String = json.split_whitespace().collect();
+        assert_eq!(
+            compact,
+            r#"[{"metadata.namespace":"demo-a","metadata.name":"pod-a"}]"#
+        );
+
+        let json = render_json(&objects, DetailLevel::Describe, None, &options)
+            .expect("json output must serialize");
+        let compact: String = json.split_whitespace().collect();
+        assert_eq!(
+            compact,
+            r#"[{"metadata":{"name":"pod-a","namespace":"demo-a"}}]"#
+        );
+    }
+
+    #[test]
+    fn renders_single_yaml_result_as_bare_mapping() {
+        let pod = |name: &str| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::String(name.to_string()));
+            DynamicObject { fields }
+        };
+        let options = RenderOptions {
+            single: true,
+            ..RenderOptions::default()
+        };
+
+        let out = render_yaml(&[pod("pod-a")], DetailLevel::Describe, None, &options)
+            .expect("yaml output must serialize");
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&out).expect("yaml must parse");
+        assert!(parsed.is_mapping());
+        assert_eq!(out, "metadata:\n  name: pod-a\n");
+
+        let out = render_yaml(
+            &[pod("pod-a"), pod("pod-b")],
+            DetailLevel::Describe,
+            None,
+            &options,
+        )
+        .expect("yaml output must serialize");
+        assert!(out.starts_with("-"));
+    }
+
     #[test]
     fn renders_summary_with_name_only() {
         let mut fields = BTreeMap::new();
@@ -287,17 +1361,133 @@ mod tests {
             }],
             DetailLevel::Summary,
             None,
+            &RenderOptions::default(),
         );
         assert!(table.contains("| name"));
         assert!(table.contains("pod-a"));
         assert!(!table.contains("metadata.namespace"));
 
-        let json = render_json(&[DynamicObject { fields }], DetailLevel::Summary, None)
-            .expect("json output must serialize");
+        let json = render_json(
+            &[DynamicObject { fields }],
+            DetailLevel::Summary,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("json output must serialize");
         assert!(json.contains("\"name\": \"pod-a\""));
         assert!(!json.contains("metadata.namespace"));
     }
 
+    #[derive(Default)]
+    struct RecordingWriter {
+        written: Vec<u8>,
+        flushed_at: Vec<usize>,
+    }
+
+    impl std::io::Write for RecordingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushed_at.push(self.written.len());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn print_to_flushes_after_emitting_output() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("pod-a".to_string()),
+        );
+        let mut out = RecordingWriter::default();
+
+        print_to(
+            &mut out,
+            &[DynamicObject { fields }],
+            OutputFormat::Json,
+            DetailLevel::Summary,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("output must be written");
+
+        let written = String::from_utf8(out.written).expect("utf-8 output");
+        assert!(written.ends_with("]\n"));
+        assert_eq!(out.flushed_at, vec![written.len()]);
+    }
+
+    #[test]
+    fn ndjson_writes_one_compact_object_per_line_and_flushes_each() {
+        let pod = |name: &str| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::from(name));
+            fields.insert("metadata.namespace".to_string(), Value::from("demo-a"));
+            DynamicObject { fields }
+        };
+        let mut out = RecordingWriter::default();
+
+        print_to(
+            &mut out,
+            &[pod("pod-a"), pod("pod-b")],
+            OutputFormat::Ndjson,
+            DetailLevel::Summary,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("output must be written");
+
+        let written = String::from_utf8(out.written).expect("utf-8 output");
+        assert_eq!(written, "{\"name\":\"pod-a\"}\n{\"name\":\"pod-b\"}\n");
+        assert_eq!(out.flushed_at, vec![17, written.len()]);
+
+        let mut empty = RecordingWriter::default();
+        print_to(
+            &mut empty,
+            &[],
+            OutputFormat::Ndjson,
+            DetailLevel::Summary,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("output must be written");
+        assert!(empty.written.is_empty());
+    }
+
+    #[test]
+    fn streaming_output_flushes_once_per_emitted_row() {
+        let objects: Vec<_> = ["pod-a", "pod-bb", "pod-ccc", "pod-dddd"]
+            .into_iter()
+            .map(|name| {
+                let mut fields = BTreeMap::new();
+                fields.insert("metadata.name".to_string(), Value::from(name));
+                DynamicObject { fields }
+            })
+            .collect();
+        let mut out = RecordingWriter::default();
+
+        print_to(
+            &mut out,
+            &objects,
+            OutputFormat::Ndjson,
+            DetailLevel::Summary,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("output must be written");
+
+        let written = String::from_utf8(out.written).expect("utf-8 output");
+        let line_ends: Vec<usize> = written
+            .match_indices('\n')
+            .map(|(index, _)| index + 1)
+            .collect();
+        assert_eq!(line_ends.len(), objects.len());
+        assert_eq!(out.flushed_at, line_ends);
+    }
+
     #[test]
     fn select_projection_overrides_summary() {
         let mut fields = BTreeMap::new();
@@ -315,6 +1505,7 @@ mod tests {
             &[DynamicObject { fields }],
             DetailLevel::Summary,
             Some(&select),
+            &RenderOptions::default(),
         );
         assert!(table.contains("metadata.namespace"));
         assert!(table.contains("demo-a"));
@@ -338,6 +1529,7 @@ mod tests {
             &[DynamicObject { fields }],
             DetailLevel::Summary,
             Some(&select),
+            &RenderOptions::default(),
         )
         .expect("json output must serialize");
 
@@ -358,6 +1550,7 @@ mod tests {
             &[DynamicObject { fields }],
             DetailLevel::Summary,
             Some(&select),
+            &RenderOptions::default(),
         )
         .expect("json output must serialize");
 
@@ -376,6 +1569,7 @@ mod tests {
             &[DynamicObject { fields }],
             DetailLevel::Summary,
             Some(&select),
+            &RenderOptions::default(),
         );
 
         assert!(table.contains("| spec.nodeName |"));
@@ -390,8 +1584,13 @@ mod tests {
             Value::String("2026-02-22T10:00:00Z".to_string()),
         );
 
-        let json = render_json(&[DynamicObject { fields }], DetailLevel::Describe, None)
-            .expect("json output must serialize");
+        let json = render_json(
+            &[DynamicObject { fields }],
+            DetailLevel::Describe,
+            None,
+            &RenderOptions::default(),
+        )
+        .expect("json output must serialize");
         assert!(json.contains("\"metadata\": {"));
         assert!(json.contains("\"annotations\": {"));
         assert!(json.contains("\"kubectl.kubernetes.io/restartedAt\": \"2026-02-22T10:00:00Z\""));
@@ -414,6 +1613,7 @@ mod tests {
             &[DynamicObject { fields }],
             DetailLevel::Summary,
             Some(&select),
+            &RenderOptions::default(),
         )
         .expect("json output must serialize");
 
@@ -421,4 +1621,501 @@ mod tests {
         assert!(json.contains("\"kubectl.kubernetes.io/restartedAt\": \"2026-02-22T10:00:00Z\""));
         assert!(json.contains("\"app.kubernetes.io/name\": \"api\""));
     }
+
+    #[test]
+    fn max_value_bytes_elides_large_values_and_keeps_small_ones() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("pod-a".to_string()),
+        );
+        fields.insert("data.blob".to_string(), Value::String("x".repeat(64)));
+
+        let select = vec!["metadata.name".to_string(), "data.blob".to_string()];
+        let options = RenderOptions {
+            max_value_bytes: Some(8),
+            ..RenderOptions::default()
+        };
+        let json = render_json(
+            &[DynamicObject { fields }],
+            DetailLevel::Summary,
+            Some(&select),
+            &options,
+        )
+        .expect("json output must serialize");
+
+        assert!(json.contains("\"metadata.name\": \"pod-a\""));
+        assert!(json.contains("\"data.blob\": \"<64 bytes omitted>\""));
+    }
+
+    #[test]
+    fn max_value_bytes_applies_to_table_cells() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("a-very-long-pod-name".to_string()),
+        );
+
+        let table = render_table(
+            &[DynamicObject { fields }],
+            DetailLevel::Summary,
+            None,
+            &RenderOptions {
+                max_value_bytes: Some(8),
+                ..RenderOptions::default()
+            },
+        );
+
+        assert!(table.contains("<20 bytes omitted>"));
+        assert!(!table.contains("a-very-long-pod-name"));
+    }
+
+    #[test]
+    fn renders_summary_with_context_column_when_tagged() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("pod-a".to_string()),
+        );
+        fields.insert("context".to_string(), Value::String("ctx-a".to_string()));
+
+        let table = render_table(
+            &[DynamicObject { fields }],
+            DetailLevel::Summary,
+            None,
+            &RenderOptions::default(),
+        );
+
+        assert!(table.contains("| context | name  |"));
+        assert!(table.contains("| ctx-a   | pod-a |"));
+    }
+
+    #[test]
+    fn renders_duration_aggregation_human_readable_in_table_and_numeric_in_json() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "max(age(metadata.creationTimestamp))".to_string(),
+            Value::from(615_600),
+        );
+        let rows = [DynamicObject { fields }];
+        let columns = vec!["max(age(metadata.creationTimestamp))".to_string()];
+        let options = RenderOptions {
+            duration_columns: columns.clone(),
+            ..RenderOptions::default()
+        };
+
+        let table = render_table(&rows, DetailLevel::Summary, Some(&columns), &options);
+        assert!(table.contains("| 7d3h "));
+
+        let json = render_json(&rows, DetailLevel::Summary, Some(&columns), &options)
+            .expect("json output must serialize");
+        assert!(json.contains("\"max(age(metadata.creationTimestamp))\": 615600"));
+    }
+
+    #[test]
+    fn formats_durations_with_two_largest_units() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(3_900), "1h5m");
+        assert_eq!(format_duration(86_400), "1d");
+        assert_eq!(format_duration(90_061), "1d1h");
+        assert_eq!(format_duration(-120), "-2m");
+    }
+
+    #[test]
+    fn renders_single_aggregation_value_as_bare_scalar() {
+        let mut fields = BTreeMap::new();
+        fields.insert("count(*)".to_string(), Value::from(42));
+        let columns = vec!["count(*)".to_string()];
+
+        let out = render_value(
+            &[DynamicObject { fields }],
+            DetailLevel::Summary,
+            Some(&columns),
+            &RenderOptions::default(),
+        )
+        .expect("single value must render");
+        assert_eq!(out, "42");
+    }
+
+    #[test]
+    fn rejects_value_output_for_multiple_values() {
+        let mut fields = BTreeMap::new();
+        fields.insert("count(*)".to_string(), Value::from(3));
+        fields.insert("sum(spec.replicas)".to_string(), Value::from(7));
+        let columns = vec!["count(*)".to_string(), "sum(spec.replicas)".to_string()];
+        let rows = [DynamicObject { fields }];
+
+        let err = render_value(
+            &rows,
+            DetailLevel::Summary,
+            Some(&columns),
+            &RenderOptions::default(),
+        )
+        .expect_err("two columns must be rejected");
+        assert!(matches!(
+            err,
+            OutputError::NotSingleValue {
+                rows: 1,
+                columns: 2
+            }
+        ));
+
+        let two_rows = [rows[0].clone(), rows[0].clone()];
+        let err = render_value(
+            &two_rows,
+            DetailLevel::Summary,
+            Some(&columns[..1]),
+            &RenderOptions::default(),
+        )
+        .expect_err("two rows must be rejected");
+        assert!(matches!(
+            err,
+            OutputError::NotSingleValue {
+                rows: 2,
+                columns: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_value_output_for_nested_value() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.labels.app".to_string(),
+            Value::String("api".to_string()),
+        );
+        let columns = vec!["metadata.labels".to_string()];
+
+        let err = render_value(
+            &[DynamicObject { fields }],
+            DetailLevel::Summary,
+            Some(&columns),
+            &RenderOptions::default(),
+        )
+        .expect_err("object value must be rejected");
+        assert!(matches!(
+            err,
+            OutputError::NonScalarValue { column } if column == "metadata.labels"
+        ));
+    }
+
+    #[test]
+    fn scales_histogram_bars_to_max_count() {
+        let row = |phase: &str, count: u64| {
+            let mut fields = BTreeMap::new();
+            fields.insert("status.phase".to_string(), Value::String(phase.to_string()));
+            fields.insert("count".to_string(), Value::from(count));
+            DynamicObject { fields }
+        };
+        let rows = [row("Running", 40), row("Pending", 10), row("Failed", 0)];
+        let columns = vec!["status.phase".to_string(), "count".to_string()];
+
+        let out = render_histogram(&rows, Some(&columns), &RenderOptions::default())
+            .expect("two-column rows must render");
+        let bar_lens: Vec<usize> = out
+            .lines()
+            .map(|line| line.chars().filter(|c| *c == '\u{2588}').count())
+            .collect();
+        assert_eq!(bar_lens, vec![HISTOGRAM_WIDTH, HISTOGRAM_WIDTH / 4, 0]);
+        assert!(
+            out.lines()
+                .next()
+                .is_some_and(|line| line.starts_with("Running "))
+        );
+        assert!(out.lines().next().is_some_and(|line| line.ends_with(" 40")));
+
+        let empty = render_histogram(&[], Some(&columns), &RenderOptions::default())
+            .expect("empty result must render");
+        assert_eq!(empty, "items: 0");
+    }
+
+    #[test]
+    fn rejects_histogram_for_non_two_column_or_non_numeric_rows() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "status.phase".to_string(),
+            Value::String("Running".to_string()),
+        );
+        let rows = [DynamicObject { fields }];
+
+        let single = vec!["status.phase".to_string()];
+        let err = render_histogram(&rows, Some(&single), &RenderOptions::default())
+            .expect_err("one column must be rejected");
+        assert!(matches!(err, OutputError::HistogramShape { columns: 1 }));
+
+        let pair = vec!["status.phase".to_string(), "status.phase".to_string()];
+        let err = render_histogram(&rows, Some(&pair), &RenderOptions::default())
+            .expect_err("string counts must be rejected");
+        assert!(matches!(err, OutputError::HistogramCount { label, .. } if label == "Running"));
+    }
+
+    #[test]
+    fn renders_group_rows_as_prometheus_lines_with_escaped_labels() {
+        let row = |namespace: &str, count: u64| {
+            let mut fields = BTreeMap::new();
+            fields.insert(
+                "metadata.namespace".to_string(),
+                Value::String(namespace.to_string()),
+            );
+            fields.insert("count(*)".to_string(), Value::from(count));
+            DynamicObject { fields }
+        };
+        let rows = [row("demo-a", 5), row("we\"ird\\ns\n", 1)];
+        let columns = vec!["metadata.namespace".to_string(), "count(*)".to_string()];
+        let options = RenderOptions {
+            metric_columns: vec!["count(*)".to_string()],
+            ..RenderOptions::default()
+        };
+
+        let out = render_prometheus(&rows, Some(&columns), &options)
+            .expect("numeric aggregation rows must render");
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "# TYPE kubiq_count gauge",
+                "kubiq_count{namespace=\"demo-a\"} 5",
+                "kubiq_count{namespace=\"we\\\"ird\\\\ns\\n\"} 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_prometheus_without_numeric_aggregations() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "min(metadata.name)".to_string(),
+            Value::String("a".to_string()),
+        );
+        let rows = [DynamicObject { fields }];
+        let columns = vec!["min(metadata.name)".to_string()];
+
+        let err = render_prometheus(&rows, Some(&columns), &RenderOptions::default())
+            .expect_err("plain columns must be rejected");
+        assert!(matches!(err, OutputError::PrometheusShape));
+
+        let options = RenderOptions {
+            metric_columns: columns.clone(),
+            ..RenderOptions::default()
+        };
+        let err = render_prometheus(&rows, Some(&columns), &options)
+            .expect_err("string aggregation must be rejected");
+        assert!(matches!(err, OutputError::PrometheusValue { .. }));
+    }
+
+    #[test]
+    fn renders_single_aggregation_row_as_env_assignments() {
+        let mut fields = BTreeMap::new();
+        fields.insert("count(*)".to_string(), Value::from(42));
+        fields.insert("sum(spec.replicas)".to_string(), Value::from(6));
+        fields.insert(
+            "min(metadata.name)".to_string(),
+            Value::String("it's a".to_string()),
+        );
+        fields.insert("avg(spec.replicas)".to_string(), Value::Null);
+        let rows = [DynamicObject { fields }];
+        let columns = vec![
+            "count(*)".to_string(),
+            "sum(spec.replicas)".to_string(),
+            "min(metadata.name)".to_string(),
+            "avg(spec.replicas)".to_string(),
+        ];
+        let options = RenderOptions {
+            metric_columns: columns.clone(),
+            ..RenderOptions::default()
+        };
+
+        let out = render_env(&rows, Some(&columns), &options).expect("aggregation row must render");
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            vec![
+                "COUNT=42",
+                "SUM_SPEC_REPLICAS=6",
+                "MIN_METADATA_NAME='it'\\''s a'",
+                "AVG_SPEC_REPLICAS=",
+            ]
+        );
+
+        let two_rows = [rows[0].clone(), rows[0].clone()];
+        let err = render_env(&two_rows, Some(&columns), &options)
+            .expect_err("several rows must be rejected");
+        assert!(matches!(err, OutputError::EnvShape));
+        let err = render_env(&rows, Some(&columns), &RenderOptions::default())
+            .expect_err("plain columns must be rejected");
+        assert!(matches!(err, OutputError::EnvShape));
+    }
+
+    #[test]
+    fn renders_tab_separated_rows_with_escaped_cells() {
+        let row = |name: &str, note: Value| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::String(name.to_string()));
+            fields.insert("spec.note".to_string(), note);
+            fields.insert("spec.replicas".to_string(), Value::from(3));
+            DynamicObject { fields }
+        };
+        let rows = [
+            row("pod-a", Value::String("a\tb\nc\\d".to_string())),
+            row("pod-b", Value::Null),
+        ];
+        let columns = vec![
+            "metadata.name".to_string(),
+            "spec.note".to_string(),
+            "spec.replicas".to_string(),
+        ];
+        let options = RenderOptions {
+            column_names: [("metadata.name".to_string(), "Name".to_string())].into(),
+            ..RenderOptions::default()
+        };
+
+        let out = render_tsv(&rows, DetailLevel::Summary, Some(&columns), &options);
+        assert_eq!(
+            out.split('\n').collect::<Vec<_>>(),
+            vec![
+                "Name\tspec.note\tspec.replicas",
+                "pod-a\ta\\tb\\nc\\\\d\t3",
+                "pod-b\t\t3",
+            ]
+        );
+        assert_eq!(
+            render_tsv(&[], DetailLevel::Summary, Some(&columns), &options),
+            ""
+        );
+    }
+
+    #[test]
+    fn only_varying_drops_constant_columns_and_keeps_name() {
+        let row = |name: &str, phase: &str| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::String(name.to_string()));
+            fields.insert(
+                "metadata.namespace".to_string(),
+                Value::String("demo-a".to_string()),
+            );
+            fields.insert("status.phase".to_string(), Value::String(phase.to_string()));
+            DynamicObject { fields }
+        };
+        let rows = [row("pod-a", "Running"), row("pod-b", "Pending")];
+        let columns = vec![
+            "metadata.name".to_string(),
+            "metadata.namespace".to_string(),
+            "status.phase".to_string(),
+        ];
+        let options = RenderOptions {
+            only_varying: true,
+            ..RenderOptions::default()
+        };
+
+        let table = render_table(&rows, DetailLevel::Summary, Some(&columns), &options);
+        let header = table.lines().next().expect("header line");
+
+        assert!(header.contains("metadata.name"));
+        assert!(header.contains("status.phase"));
+        assert!(!header.contains("metadata.namespace"));
+        assert!(!table.contains("demo-a"));
+    }
+
+    #[test]
+    fn rename_cols_replaces_table_header_only() {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "metadata.name".to_string(),
+            Value::String("pod-a".to_string()),
+        );
+        fields.insert(
+            "status.phase".to_string(),
+            Value::String("Running".to_string()),
+        );
+        let rows = [DynamicObject { fields }];
+        let columns = vec!["metadata.name".to_string(), "status.phase".to_string()];
+        let options = RenderOptions {
+            column_names: parse_column_names(
+                "# friendly headers\n\"metadata.name\" = \"Name\"\nstatus.phase = \"Phase\"\n",
+            )
+            .expect("column names must parse"),
+            ..RenderOptions::default()
+        };
+
+        let table = render_table(&rows, DetailLevel::Summary, Some(&columns), &options);
+        let header = table.lines().next().expect("header line");
+        assert!(header.contains("Name"));
+        assert!(header.contains("Phase"));
+        assert!(!header.contains("metadata.name"));
+        assert!(table.contains("pod-a"));
+
+        let json = render_json(&rows, DetailLevel::Summary, Some(&columns), &options)
+            .expect("json must render");
+        assert!(json.contains("pod-a"));
+        assert!(!json.contains("Name"));
+        assert!(!json.contains("Phase"));
+    }
+
+    #[test]
+    fn rename_cols_rejects_duplicate_paths() {
+        let err = parse_column_names("metadata.name = \"Name\"\nmetadata.name = \"Pod\"\n")
+            .expect_err("duplicate path must be rejected");
+        assert!(err.contains("line 2"));
+    }
+
+    #[test]
+    fn bucket_inserts_section_headers_between_groups() {
+        let row = |name: &str, phase: &str| {
+            let mut fields = BTreeMap::new();
+            fields.insert("metadata.name".to_string(), Value::String(name.to_string()));
+            fields.insert("status.phase".to_string(), Value::String(phase.to_string()));
+            DynamicObject { fields }
+        };
+        let rows = [
+            row("pod-a", "Running"),
+            row("pod-b", "Pending"),
+            row("pod-c", "Running"),
+        ];
+        let options = RenderOptions {
+            bucket: Some("status.phase".to_string()),
+            ..RenderOptions::default()
+        };
+
+        let table = render_table(&rows, DetailLevel::Summary, None, &options);
+        let lines: Vec<&str> = table.lines().map(str::trim_end).collect();
+
+        assert_eq!(
+            lines[2..],
+            [
+                "[status.phase = Pending]",
+                "| pod-b |",
+                "[status.phase = Running]",
+                "| pod-a |",
+                "| pod-c |",
+                "items: 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn caps_table_columns_and_appends_hidden_indicator() {
+        let mut fields = BTreeMap::new();
+        for key in ["a", "b", "c", "d", "e"] {
+            fields.insert(format!("spec.{key}"), Value::String(key.to_string()));
+        }
+        let rows = [DynamicObject { fields }];
+        let columns: Vec<String> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|key| format!("spec.{key}"))
+            .collect();
+        let options = RenderOptions {
+            max_columns: Some(2),
+            ..RenderOptions::default()
+        };
+
+        let table = render_table(&rows, DetailLevel::Summary, Some(&columns), &options);
+        let header = table.lines().next().expect("table must have a header");
+        let cells: Vec<&str> = header.trim_matches('|').split('|').map(str::trim).collect();
+        assert_eq!(cells, vec!["spec.a", "spec.b", "\u{2026}(+3 more)"]);
+        assert!(!table.contains("spec.c"));
+
+        let json = render_json(&rows, DetailLevel::Summary, Some(&columns), &options)
+            .expect("json output must serialize");
+        assert!(json.contains("spec.e"));
+    }
 }
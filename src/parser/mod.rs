@@ -2,40 +2,182 @@ use nom::{
     IResult, Parser,
     branch::alt,
     bytes::complete::{tag, tag_no_case, take_while, take_while1},
-    character::complete::{char, multispace0, multispace1},
-    combinator::{all_consuming, map, not, opt, peek, recognize, value, verify},
+    character::complete::{char, digit1, multispace0, multispace1},
+    combinator::{all_consuming, map, map_opt, not, opt, peek, recognize, value, verify},
     error::{Error, ErrorKind},
-    multi::{many0, separated_list1},
+    multi::{many0, many1, separated_list1},
     sequence::{delimited, preceded, terminated, tuple},
 };
 use serde_json::Value;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct QueryAst {
-    pub predicates: Vec<Predicate>,
+    pub filter: Option<Expr>,
     pub select: Option<SelectClause>,
     pub order_by: Option<Vec<SortKey>>,
+    pub limit: Option<LimitClause>,
+    pub computed: Vec<ComputedColumn>,
+    pub group_by: Option<Vec<String>>,
+    pub having: Option<Expr<HavingPredicate>>,
+    pub distinct: bool,
+}
+
+impl QueryAst {
+    /// Все predicates фильтра в порядке записи.
+    pub fn predicates(&self) -> Vec<&Predicate> {
+        self.filter.as_ref().map_or_else(Vec::new, Expr::predicates)
+    }
+}
+
+/// Условие `where`: `and` связывает сильнее `or`, скобки группируют.
+/// Вложенные `and`/`or` одного вида сворачиваются в один узел.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr<L = Predicate> {
+    And(Vec<Expr<L>>),
+    Or(Vec<Expr<L>>),
+    Not(Box<Expr<L>>),
+    Leaf(L),
+}
+
+impl<L> Expr<L> {
+    /// Конъюнкция predicates; `None` для пустого списка.
+    pub fn all(predicates: Vec<L>) -> Option<Self> {
+        predicates.into_iter().map(Self::Leaf).reduce(Self::and)
+    }
+
+    pub fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::And(mut left), Self::And(right)) => {
+                left.extend(right);
+                Self::And(left)
+            }
+            (Self::And(mut left), right) => {
+                left.push(right);
+                Self::And(left)
+            }
+            (left, Self::And(mut right)) => {
+                right.insert(0, left);
+                Self::And(right)
+            }
+            (left, right) => Self::And(vec![left, right]),
+        }
+    }
+
+    pub fn or(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Or(mut left), Self::Or(right)) => {
+                left.extend(right);
+                Self::Or(left)
+            }
+            (Self::Or(mut left), right) => {
+                left.push(right);
+                Self::Or(left)
+            }
+            (left, Self::Or(mut right)) => {
+                right.insert(0, left);
+                Self::Or(right)
+            }
+            (left, right) => Self::Or(vec![left, right]),
+        }
+    }
+
+    pub fn predicates(&self) -> Vec<&L> {
+        match self {
+            Self::And(terms) | Self::Or(terms) => terms.iter().flat_map(Self::predicates).collect(),
+            Self::Not(inner) => inner.predicates(),
+            Self::Leaf(predicate) => vec![predicate],
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Predicate {
     pub path: String,
+    pub function: Option<FieldFunction>,
+    pub op: Operator,
+    pub value: Value,
+    pub value_path: Option<String>,
+    pub value_function: Option<ValueFunction>,
+}
+
+/// Условие `having` над строкой результата aggregation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HavingPredicate {
+    pub column: HavingColumn,
     pub op: Operator,
     pub value: Value,
 }
 
+/// Левая часть `having`: колонка aggregation (`count(*)`) или ключ
+/// `group by`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HavingColumn {
+    Aggregation(AggregationExpr),
+    Path(String),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Operator {
     Eq,
     Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    In { values: Vec<Value> },
+    NotIn { values: Vec<Value> },
+    Like(String),
+    Regex,
+    NotRegex,
+    IsNull,
+    IsNotNull,
+    Exists,
+    HasKey,
+}
+
+/// `select (<условие>) as <alias>`: булева колонка, условие разбирается
+/// так же, как `where`; `alias` попадает в `SelectClause::Paths` на свое место.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComputedColumn {
+    pub alias: String,
+    pub expr: Expr,
+}
+
+/// `offset` без `limit` не записывается: он всегда продолжает `limit N`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitClause {
+    pub count: usize,
+    pub offset: Option<usize>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SortKey {
     pub path: String,
+    pub function: Option<FieldFunction>,
     pub direction: SortDirection,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldFunction {
+    Len,
+    StrLen,
+    Age,
+    ImageRepo,
+    ImageTag,
+    /// `sum(path[*].field)` и аналоги в левой части предиката: агрегат
+    /// чисел по всем индексам массивов внутри одного объекта.
+    ArraySum,
+    ArrayMin,
+    ArrayMax,
+    ArrayAvg,
+}
+
+/// Правая часть, вычисляемая при evaluation: `now()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueFunction {
+    Now,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SortDirection {
     Asc,
@@ -56,6 +198,7 @@ pub enum SelectClause {
 pub struct AggregationExpr {
     pub function: AggregationFunction,
     pub path: Option<String>,
+    pub field_function: Option<FieldFunction>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -65,15 +208,24 @@ pub enum AggregationFunction {
     Min,
     Max,
     Avg,
+    Range,
+    Median,
+    Stddev,
+    /// `percentile(path, p)`: `p` из `[0, 100]`, проверяется при валидации.
+    Percentile(serde_json::Number),
+    Mode,
+    /// `group_concat(path[, 'sep'])`; разделитель по умолчанию `,`.
+    GroupConcat(String),
 }
 
 pub fn parse_query(input: &str) -> Result<QueryAst, String> {
-    let trimmed = input.trim();
+    let uncommented = strip_comments(input);
+    let trimmed = uncommented.trim();
     if trimmed.is_empty() {
         return Err("WHERE clause is empty".to_string());
     }
     if !starts_with_where_keyword(trimmed) {
-        return Err("query must start with WHERE".to_string());
+        return Err(missing_where_message(trimmed.split_whitespace().next()));
     }
 
     match all_consuming(delimited(multispace0, query_ast, multispace0)).parse(trimmed) {
@@ -81,11 +233,167 @@ pub fn parse_query(input: &str) -> Result<QueryAst, String> {
         Err(err) if is_invalid_escape_error(&err) => {
             Err("invalid escape sequence in quoted string".to_string())
         }
-        Err(_) => Err("invalid query syntax".to_string()),
+        Err(_) if has_empty_where_clause(trimmed) => {
+            Err("WHERE clause requires at least one predicate".to_string())
+        }
+        Err(_) => {
+            Err(unexpected_token_message(trimmed)
+                .unwrap_or_else(|| "invalid query syntax".to_string()))
+        }
     }
 }
 
-fn validate_query_ast(ast: QueryAst) -> Result<QueryAst, String> {
+const CLAUSE_KEYWORDS: &[&str] = &["select", "order", "group", "having", "limit"];
+/// Слово, с которым сравнивается токен, и подсказка: для `order`/`group`
+/// подсказывается clause целиком.
+const SUGGESTED_KEYWORDS: &[(&str, &str)] = &[
+    ("select", "select"),
+    ("order", "order by"),
+    ("group", "group by"),
+    ("having", "having"),
+    ("limit", "limit"),
+    ("offset", "offset"),
+    ("distinct", "distinct"),
+    ("and", "and"),
+    ("or", "or"),
+    ("not", "not"),
+    ("asc", "asc"),
+    ("desc", "desc"),
+];
+
+fn missing_where_message(first_token: Option<&str>) -> String {
+    match first_token.and_then(|token| suggest_keyword(token, &[("where", "where")])) {
+        Some(keyword) => format!("query must start with WHERE; did you mean `{keyword}`?"),
+        None => "query must start with WHERE".to_string(),
+    }
+}
+
+/// Ближайшее по расстоянию редактирования ключевое слово: не дальше двух
+/// правок и меньше половины длины слова, чтобы короткие токены не
+/// превращались в `or`/`and`.
+fn suggest_keyword(token: &str, keywords: &[(&str, &'static str)]) -> Option<&'static str> {
+    let lowered = token.to_ascii_lowercase();
+    keywords
+        .iter()
+        .map(|(keyword, suggestion)| (edit_distance(&lowered, keyword), keyword.len(), *suggestion))
+        .filter(|(distance, length, _)| (1..=2).contains(distance) && distance * 2 < *length)
+        .min_by_key(|(distance, _, _)| *distance)
+        .map(|(_, _, suggestion)| suggestion)
+}
+
+/// Если запрос разобран не до конца, называет первый лишний токен, clause,
+/// после которого разбор остановился, и похожее ключевое слово. Ключевое
+/// слово clause на месте лишнего токена значит, что сломан сам clause, и
+/// сообщение остается общим.
+fn unexpected_token_message(input: &str) -> Option<String> {
+    let (rest, (_, clauses)) = query_clauses(input).ok()?;
+    let token = rest.split_whitespace().next()?;
+    if CLAUSE_KEYWORDS
+        .iter()
+        .any(|keyword| token.eq_ignore_ascii_case(keyword))
+    {
+        return None;
+    }
+    let after = clauses.last().map_or("WHERE", QuerySuffixClause::keyword);
+    let message = format!("unexpected token `{token}` after {after}");
+    Some(match suggest_keyword(token, SUGGESTED_KEYWORDS) {
+        Some(keyword) => format!("{message}; did you mean `{keyword}`?"),
+        None => message,
+    })
+}
+
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_ch) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, right_ch) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_ch != *right_ch);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+/// Удаляет комментарии `# ...` и `-- ...` до конца строки. Комментарий
+/// начинается только в начале токена и вне строкового литерала в `'...'`.
+fn strip_comments(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_quotes = false;
+    let mut token_start = true;
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            out.push(ch);
+            match ch {
+                '\\' => out.extend(chars.next()),
+                '\'' => in_quotes = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        let starts_comment = ch == '#' || (ch == '-' && chars.peek() == Some(&'-'));
+        if token_start && starts_comment {
+            while chars.next_if(|next| *next != '\n').is_some() {}
+            continue;
+        }
+
+        if ch == '\'' {
+            in_quotes = true;
+        }
+        token_start = ch.is_whitespace();
+        out.push(ch);
+    }
+
+    out
+}
+
+fn has_empty_where_clause(input: &str) -> bool {
+    let rest = input.get("where".len()..).unwrap_or_default().trim_start();
+    rest.is_empty()
+        || order_by_clause_start(rest).is_ok()
+        || select_clause_start(rest).is_ok()
+        || limit_clause_start(rest).is_ok()
+        || group_by_clause_start(rest).is_ok()
+        || having_clause_start(rest).is_ok()
+}
+
+fn validate_query_ast(mut ast: QueryAst) -> Result<QueryAst, String> {
+    let aggregations = match ast.select.as_ref() {
+        Some(
+            SelectClause::Aggregations(aggregations) | SelectClause::Mixed { aggregations, .. },
+        ) => aggregations.as_slice(),
+        _ => &[],
+    };
+    for aggregation in aggregations {
+        if let AggregationFunction::Percentile(rank) = &aggregation.function
+            && !rank
+                .as_f64()
+                .is_some_and(|rank| (0.0..=100.0).contains(&rank))
+        {
+            return Err(format!("percentile must be between 0 and 100, got {rank}"));
+        }
+    }
+    if let Some(group_by) = ast.group_by.as_ref() {
+        ast.select = match ast.select.take() {
+            Some(SelectClause::Mixed {
+                paths,
+                aggregations,
+            }) => {
+                if let Some(path) = paths.iter().find(|path| !group_by.contains(path)) {
+                    return Err(format!("SELECT path `{path}` must appear in GROUP BY"));
+                }
+                Some(SelectClause::Aggregations(aggregations))
+            }
+            Some(SelectClause::Aggregations(aggregations)) => {
+                Some(SelectClause::Aggregations(aggregations))
+            }
+            _ => return Err("GROUP BY requires aggregations in SELECT".to_string()),
+        };
+    }
     if matches!(ast.select.as_ref(), Some(SelectClause::Mixed { .. })) {
         return Err("cannot mix projection paths and aggregations in SELECT".to_string());
     }
@@ -93,15 +401,30 @@ fn validate_query_ast(ast: QueryAst) -> Result<QueryAst, String> {
     {
         return Err("aggregation queries do not support ORDER BY".to_string());
     }
+    if ast.distinct && matches!(ast.select.as_ref(), Some(SelectClause::Aggregations(_))) {
+        return Err("aggregation queries do not support DISTINCT".to_string());
+    }
+    if ast.limit.is_some_and(|limit| limit.count == 0) {
+        return Err("LIMIT must be greater than zero".to_string());
+    }
     Ok(ast)
 }
 
+/// Делит путь `select` на сам путь и тип приведения: `spec.replicas::int`
+/// -> (`spec.replicas`, `Some("int")`).
+pub fn split_type_annotation(path: &str) -> (&str, Option<&str>) {
+    match path.split_once("::") {
+        Some((path, target)) => (path, Some(target)),
+        None => (path, None),
+    }
+}
+
 pub fn parse_query_args(args: &[String]) -> Result<QueryAst, String> {
     if args.is_empty() {
         return Err("WHERE clause is empty".to_string());
     }
     if !args[0].eq_ignore_ascii_case("where") {
-        return Err("query must start with WHERE".to_string());
+        return Err(missing_where_message(args[0].split_whitespace().next()));
     }
 
     let normalized_args: Vec<String> = args.iter().map(|arg| normalize_arg(arg)).collect();
@@ -116,7 +439,11 @@ fn starts_with_where_keyword(input: &str) -> bool {
 }
 
 fn normalize_arg(arg: &str) -> String {
-    if arg.chars().any(char::is_whitespace) {
+    if arg.is_empty()
+        || arg.chars().any(char::is_whitespace)
+        || arg.starts_with('#')
+        || arg.starts_with("--")
+    {
         format!("'{}'", escape_for_single_quoted(arg))
     } else {
         arg.to_string()
@@ -138,20 +465,34 @@ fn escape_for_single_quoted(value: &str) -> String {
     escaped
 }
 
+fn query_clauses(input: &str) -> IResult<&str, (Expr, Vec<QuerySuffixClause>)> {
+    tuple((
+        where_clause,
+        many0(preceded(multispace1, query_suffix_clause)),
+    ))
+    .parse(input)
+}
+
 fn query_ast(input: &str) -> IResult<&str, QueryAst> {
-    let (input, predicates) = where_clause(input)?;
-    let (input, clauses) = many0(preceded(multispace1, query_suffix_clause)).parse(input)?;
+    let (input, (filter, clauses)) = query_clauses(input)?;
 
     let mut select = None;
     let mut order_by = None;
+    let mut limit = None;
+    let mut computed = Vec::new();
+    let mut group_by = None;
+    let mut having = None;
+    let mut distinct = false;
 
     for clause in clauses {
         match clause {
-            QuerySuffixClause::Select(clause) => {
+            QuerySuffixClause::Select((is_distinct, clause, columns)) => {
                 if select.is_some() {
                     return Err(nom::Err::Error(Error::new(input, ErrorKind::Tag)));
                 }
                 select = Some(clause);
+                computed = columns;
+                distinct = is_distinct;
             }
             QuerySuffixClause::OrderBy(keys) => {
                 if order_by.is_some() {
@@ -159,110 +500,537 @@ fn query_ast(input: &str) -> IResult<&str, QueryAst> {
                 }
                 order_by = Some(keys);
             }
+            QuerySuffixClause::GroupBy(paths) => {
+                if group_by.is_some() {
+                    return Err(nom::Err::Error(Error::new(input, ErrorKind::Tag)));
+                }
+                group_by = Some(paths);
+            }
+            QuerySuffixClause::Having(expr) => {
+                if having.is_some() {
+                    return Err(nom::Err::Error(Error::new(input, ErrorKind::Tag)));
+                }
+                having = Some(expr);
+            }
+            QuerySuffixClause::Limit(clause) => {
+                if limit.is_some() {
+                    return Err(nom::Err::Error(Error::new(input, ErrorKind::Tag)));
+                }
+                limit = Some(clause);
+            }
         }
     }
 
     Ok((
         input,
         QueryAst {
-            predicates,
+            filter: Some(filter),
             select,
             order_by,
+            limit,
+            computed,
+            group_by,
+            having,
+            distinct,
         },
     ))
 }
 
 #[derive(Clone, Debug, PartialEq)]
 enum QuerySuffixClause {
-    Select(SelectClause),
+    Select((bool, SelectClause, Vec<ComputedColumn>)),
     OrderBy(Vec<SortKey>),
+    Limit(LimitClause),
+    GroupBy(Vec<String>),
+    Having(Expr<HavingPredicate>),
+}
+
+impl QuerySuffixClause {
+    fn keyword(&self) -> &'static str {
+        match self {
+            Self::Select(_) => "SELECT",
+            Self::OrderBy(_) => "ORDER BY",
+            Self::Limit(_) => "LIMIT",
+            Self::GroupBy(_) => "GROUP BY",
+            Self::Having(_) => "HAVING",
+        }
+    }
 }
 
 fn query_suffix_clause(input: &str) -> IResult<&str, QuerySuffixClause> {
     alt((
         map(order_by_clause, QuerySuffixClause::OrderBy),
         map(select_clause, QuerySuffixClause::Select),
+        map(limit_clause, QuerySuffixClause::Limit),
+        map(group_by_clause, QuerySuffixClause::GroupBy),
+        map(having_clause, QuerySuffixClause::Having),
     ))
     .parse(input)
 }
 
-fn where_clause(input: &str) -> IResult<&str, Vec<Predicate>> {
-    preceded(
-        terminated(tag_no_case("where"), multispace1),
-        separated_list1(and_separator, predicate),
+fn where_clause(input: &str) -> IResult<&str, Expr> {
+    preceded(terminated(tag_no_case("where"), multispace1), or_expr).parse(input)
+}
+
+fn or_expr(input: &str) -> IResult<&str, Expr> {
+    expr_tree(predicate, input)
+}
+
+type LeafParser<L> = fn(&str) -> IResult<&str, L>;
+
+/// Дерево `or`/`and`/`not`/скобок над условиями `leaf`: `where` и `having`
+/// отличаются только тем, что стоит в левой части условия.
+fn expr_tree<L>(leaf: LeafParser<L>, input: &str) -> IResult<&str, Expr<L>> {
+    map_opt(
+        separated_list1(or_separator, |input| and_expr(leaf, input)),
+        |terms| terms.into_iter().reduce(Expr::or),
+    )
+    .parse(input)
+}
+
+fn and_expr<L>(leaf: LeafParser<L>, input: &str) -> IResult<&str, Expr<L>> {
+    map_opt(
+        separated_list1(and_separator, |input| unary_expr(leaf, input)),
+        |terms| terms.into_iter().reduce(Expr::and),
     )
     .parse(input)
 }
 
+/// `not` связывает сильнее `and` и отрицает следующее условие или группу
+/// целиком; поле с именем `not` (`where not == x`) остается predicate.
+fn unary_expr<L>(leaf: LeafParser<L>, input: &str) -> IResult<&str, Expr<L>> {
+    alt((
+        map(
+            preceded(
+                tuple((tag_no_case("not"), alt((multispace1, peek(tag("(")))))),
+                |input| unary_expr(leaf, input),
+            ),
+            |inner| Expr::Not(Box::new(inner)),
+        ),
+        |input| primary_expr(leaf, input),
+    ))
+    .parse(input)
+}
+
+fn primary_expr<L>(leaf: LeafParser<L>, input: &str) -> IResult<&str, Expr<L>> {
+    alt((
+        delimited(
+            terminated(char('('), multispace0),
+            |input| expr_tree(leaf, input),
+            preceded(multispace0, char(')')),
+        ),
+        map(leaf, Expr::Leaf),
+    ))
+    .parse(input)
+}
+
+fn having_clause_start(input: &str) -> IResult<&str, ()> {
+    value((), tuple((tag_no_case("having"), multispace1))).parse(input)
+}
+
+fn having_clause(input: &str) -> IResult<&str, Expr<HavingPredicate>> {
+    preceded(having_clause_start, |input| {
+        expr_tree(having_predicate, input)
+    })
+    .parse(input)
+}
+
+/// Слева колонка aggregation или ключ `group by`, справа литерал.
+fn having_predicate(input: &str) -> IResult<&str, HavingPredicate> {
+    let (input, column) = alt((
+        map(aggregation_expr, HavingColumn::Aggregation),
+        map(path, HavingColumn::Path),
+    ))
+    .parse(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, op) = operator(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, value) = predicate_value(input)?;
+
+    Ok((input, HavingPredicate { column, op, value }))
+}
+
 fn and_separator(input: &str) -> IResult<&str, ()> {
     value((), tuple((multispace1, tag_no_case("and"), multispace1))).parse(input)
 }
 
+fn or_separator(input: &str) -> IResult<&str, ()> {
+    value((), tuple((multispace1, tag_no_case("or"), multispace1))).parse(input)
+}
+
 fn predicate(input: &str) -> IResult<&str, Predicate> {
-    let (input, path) = path(input)?;
+    alt((
+        exists_predicate,
+        haskey_predicate,
+        set_predicate,
+        like_predicate,
+        null_predicate,
+        comparison_predicate,
+    ))
+    .parse(input)
+}
+
+fn exists_predicate(input: &str) -> IResult<&str, Predicate> {
+    let (input, path) = preceded(
+        terminated(tag_no_case("exists"), multispace1),
+        predicate_path,
+    )
+    .parse(input)?;
+
+    Ok((
+        input,
+        Predicate {
+            path,
+            function: None,
+            op: Operator::Exists,
+            value: Value::Null,
+            value_path: None,
+            value_function: None,
+        },
+    ))
+}
+
+/// `haskey <path> <glob>`: под `path` есть ключ, подходящий под glob.
+fn haskey_predicate(input: &str) -> IResult<&str, Predicate> {
+    let (input, (path, pattern)) = preceded(
+        terminated(tag_no_case("haskey"), multispace1),
+        tuple((terminated(path, multispace1), predicate_value)),
+    )
+    .parse(input)?;
+
+    Ok((
+        input,
+        Predicate {
+            path,
+            function: None,
+            op: Operator::HasKey,
+            value: Value::String(value_to_pattern(pattern)),
+            value_path: None,
+            value_function: None,
+        },
+    ))
+}
+
+fn value_to_pattern(value: Value) -> String {
+    match value {
+        Value::String(pattern) => pattern,
+        other => other.to_string(),
+    }
+}
+
+/// `<path> in (a, b)` и `<path> not in (a, b)`.
+fn set_predicate(input: &str) -> IResult<&str, Predicate> {
+    let (input, (function, path)) = predicate_lhs(input)?;
+    let (input, negated) = preceded(
+        multispace1,
+        alt((
+            value(
+                true,
+                tuple((tag_no_case("not"), multispace1, tag_no_case("in"))),
+            ),
+            value(false, tag_no_case("in")),
+        )),
+    )
+    .parse(input)?;
+    let (input, values) = preceded(
+        multispace0,
+        delimited(
+            terminated(char('('), multispace0),
+            separated_list1(delimited(multispace0, char(','), multispace0), set_value),
+            preceded(multispace0, char(')')),
+        ),
+    )
+    .parse(input)?;
+
+    Ok((
+        input,
+        Predicate {
+            path,
+            function,
+            op: if negated {
+                Operator::NotIn { values }
+            } else {
+                Operator::In { values }
+            },
+            value: Value::Null,
+            value_path: None,
+            value_function: None,
+        },
+    ))
+}
+
+/// `<path> like 'api-*'`: glob с `*` и `?`, `\` экранирует символ.
+fn like_predicate(input: &str) -> IResult<&str, Predicate> {
+    let (input, (function, path)) = predicate_lhs(input)?;
+    let (input, pattern) = preceded(
+        tuple((multispace1, tag_no_case("like"), multispace1)),
+        predicate_value,
+    )
+    .parse(input)?;
+
+    Ok((
+        input,
+        Predicate {
+            path,
+            function,
+            op: Operator::Like(value_to_pattern(pattern)),
+            value: Value::Null,
+            value_path: None,
+            value_function: None,
+        },
+    ))
+}
+
+/// `<path> is null` / `<path> is not null`, без правой части.
+fn null_predicate(input: &str) -> IResult<&str, Predicate> {
+    let (input, (function, path)) = predicate_lhs(input)?;
+    let (input, negated) = delimited(
+        tuple((multispace1, tag_no_case("is"), multispace1)),
+        map(opt(terminated(tag_no_case("not"), multispace1)), |not| {
+            not.is_some()
+        }),
+        tag_no_case("null"),
+    )
+    .parse(input)?;
+
+    Ok((
+        input,
+        Predicate {
+            path,
+            function,
+            op: if negated {
+                Operator::IsNotNull
+            } else {
+                Operator::IsNull
+            },
+            value: Value::Null,
+            value_path: None,
+            value_function: None,
+        },
+    ))
+}
+
+fn set_value(input: &str) -> IResult<&str, Value> {
+    alt((
+        quoted_string_value,
+        map(
+            verify(
+                take_while1(|c: char| !c.is_ascii_whitespace() && c != ',' && c != ')'),
+                |token: &str| !token.starts_with('\''),
+            ),
+            parse_scalar_value,
+        ),
+    ))
+    .parse(input)
+}
+
+fn predicate_lhs(input: &str) -> IResult<&str, (Option<FieldFunction>, String)> {
+    alt((
+        map(array_aggregate_call, |(function, path)| {
+            (Some(function), path)
+        }),
+        map(field_function_call, |(function, path)| {
+            (Some(function), path)
+        }),
+        map(predicate_path, |path| (None, path)),
+    ))
+    .parse(input)
+}
+
+fn comparison_predicate(input: &str) -> IResult<&str, Predicate> {
+    let (input, (function, path)) = predicate_lhs(input)?;
     let (input, _) = multispace0(input)?;
     let (input, op) = operator(input)?;
     let (input, _) = multispace0(input)?;
-    let (input, value) = predicate_value(input)?;
+    let (input, (value, value_path, value_function)) = predicate_rhs(input)?;
+    if value_function.is_some() && matches!(op, Operator::Regex | Operator::NotRegex) {
+        return Err(nom::Err::Error(Error::new(input, ErrorKind::Verify)));
+    }
 
-    Ok((input, Predicate { path, op, value }))
+    Ok((
+        input,
+        Predicate {
+            path,
+            function,
+            op,
+            value,
+            value_path,
+            value_function,
+        },
+    ))
+}
+
+type PredicateRhs = (Value, Option<String>, Option<ValueFunction>);
+
+fn predicate_rhs(input: &str) -> IResult<&str, PredicateRhs> {
+    alt((
+        map(preceded(char('@'), path), |path| {
+            (Value::Null, Some(path), None)
+        }),
+        map(value_function_call, |function| {
+            (Value::Null, None, Some(function))
+        }),
+        map(predicate_value, |value| (value, None, None)),
+    ))
+    .parse(input)
+}
+
+fn value_function_call(input: &str) -> IResult<&str, ValueFunction> {
+    value(
+        ValueFunction::Now,
+        tuple((
+            tag_no_case("now"),
+            multispace0,
+            char('('),
+            multispace0,
+            char(')'),
+        )),
+    )
+    .parse(input)
 }
 
 fn operator(input: &str) -> IResult<&str, Operator> {
     alt((
         value(Operator::Eq, tag("==")),
         value(Operator::Ne, tag("!=")),
+        value(Operator::Regex, tag("=~")),
+        value(Operator::NotRegex, tag("!~")),
+        value(Operator::Ge, tag(">=")),
+        value(Operator::Le, tag("<=")),
+        value(Operator::Gt, tag(">")),
+        value(Operator::Lt, tag("<")),
     ))
     .parse(input)
 }
 
-fn select_clause(input: &str) -> IResult<&str, SelectClause> {
-    let (input, items) = preceded(
+/// `select distinct ...` оставляет только первую из строк с одинаковыми
+/// значениями выбранных колонок.
+fn select_clause(input: &str) -> IResult<&str, (bool, SelectClause, Vec<ComputedColumn>)> {
+    let (input, distinct) = preceded(
         terminated(tag_no_case("select"), multispace1),
-        separated_list1(select_separator, select_item),
+        map(
+            opt(terminated(tag_no_case("distinct"), multispace1)),
+            |keyword| keyword.is_some(),
+        ),
     )
     .parse(input)?;
+    let (input, items) = separated_list1(select_separator, select_items).parse(input)?;
 
-    classify_select_items(input, items)
+    let (input, (clause, computed)) =
+        classify_select_items(input, items.into_iter().flatten().collect())?;
+    Ok((input, (distinct, clause, computed)))
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 enum SelectItem {
     Path(String),
     Aggregation(AggregationExpr),
+    Computed(ComputedColumn),
+}
+
+fn select_items(input: &str) -> IResult<&str, Vec<SelectItem>> {
+    alt((
+        map(jsonpath_group, |paths| {
+            paths.into_iter().map(SelectItem::Path).collect()
+        }),
+        map(select_item, |item| vec![item]),
+    ))
+    .parse(input)
+}
+
+/// kubectl-совместимые `{.a.b}{.c[0].d}` — только извлечение пути,
+/// без фильтров, wildcard и функций.
+fn jsonpath_group(input: &str) -> IResult<&str, Vec<String>> {
+    many1(delimited(char('{'), jsonpath_path, char('}'))).parse(input)
+}
+
+fn jsonpath_path(input: &str) -> IResult<&str, String> {
+    map(
+        preceded(
+            opt(char('$')),
+            many1(alt((
+                preceded(char('.'), ident),
+                delimited(char('['), digit1, char(']')),
+                delimited(tag("['"), ident, tag("']")),
+            ))),
+        ),
+        |segments| segments.join("."),
+    )
+    .parse(input)
 }
 
 fn select_item(input: &str) -> IResult<&str, SelectItem> {
     alt((
         map(aggregation_expr, SelectItem::Aggregation),
-        map(path, SelectItem::Path),
+        map(computed_column, SelectItem::Computed),
+        map(select_path, SelectItem::Path),
     ))
     .parse(input)
 }
 
+fn computed_column(input: &str) -> IResult<&str, ComputedColumn> {
+    map(
+        tuple((
+            delimited(
+                terminated(char('('), multispace0),
+                or_expr,
+                preceded(multispace0, char(')')),
+            ),
+            preceded(tuple((multispace1, tag_no_case("as"), multispace1)), ident),
+        )),
+        |(expr, alias)| ComputedColumn {
+            alias: alias.to_string(),
+            expr,
+        },
+    )
+    .parse(input)
+}
+
+fn select_path(input: &str) -> IResult<&str, String> {
+    map(
+        recognize(tuple((
+            path,
+            opt(preceded(
+                tag("::"),
+                alt((tag("int"), tag("float"), tag("string"), tag("bool"))),
+            )),
+        ))),
+        str::to_string,
+    )
+    .parse(input)
+}
+
 fn classify_select_items(
     input: &str,
     items: Vec<SelectItem>,
-) -> IResult<&str, SelectClause> {
+) -> IResult<&str, (SelectClause, Vec<ComputedColumn>)> {
     let mut paths = Vec::new();
     let mut aggregations = Vec::new();
+    let mut computed = Vec::new();
 
     for item in items {
         match item {
             SelectItem::Path(path) => paths.push(path),
             SelectItem::Aggregation(aggregation) => aggregations.push(aggregation),
+            SelectItem::Computed(column) => {
+                paths.push(column.alias.clone());
+                computed.push(column);
+            }
         }
     }
 
     match (paths.is_empty(), aggregations.is_empty()) {
-        (false, true) => Ok((input, SelectClause::Paths(paths))),
-        (true, false) => Ok((input, SelectClause::Aggregations(aggregations))),
+        (false, true) => Ok((input, (SelectClause::Paths(paths), computed))),
+        (true, false) => Ok((input, (SelectClause::Aggregations(aggregations), computed))),
         _ => Ok((
             input,
-            SelectClause::Mixed {
-                paths,
-                aggregations,
-            },
+            (
+                SelectClause::Mixed {
+                    paths,
+                    aggregations,
+                },
+                computed,
+            ),
         )),
     }
 }
@@ -274,7 +1042,13 @@ fn select_separator(input: &str) -> IResult<&str, ()> {
             (),
             terminated(
                 multispace1,
-                not(peek(alt((order_by_clause_start, select_clause_start)))),
+                not(peek(alt((
+                    order_by_clause_start,
+                    select_clause_start,
+                    limit_clause_start,
+                    group_by_clause_start,
+                    having_clause_start,
+                )))),
             ),
         ),
     ))
@@ -298,6 +1072,47 @@ fn select_clause_start(input: &str) -> IResult<&str, ()> {
     value((), tuple((tag_no_case("select"), multispace1))).parse(input)
 }
 
+fn group_by_clause_start(input: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        tuple((
+            tag_no_case("group"),
+            multispace1,
+            tag_no_case("by"),
+            multispace1,
+        )),
+    )
+    .parse(input)
+}
+
+/// Ключи группировки — обычные пути; вычисляемые поля не поддерживаются.
+fn group_by_clause(input: &str) -> IResult<&str, Vec<String>> {
+    preceded(
+        group_by_clause_start,
+        separated_list1(order_key_separator, path),
+    )
+    .parse(input)
+}
+
+fn limit_clause_start(input: &str) -> IResult<&str, ()> {
+    value((), tuple((tag_no_case("limit"), multispace1, digit1))).parse(input)
+}
+
+fn limit_clause(input: &str) -> IResult<&str, LimitClause> {
+    let (input, count) =
+        preceded(tuple((tag_no_case("limit"), multispace1)), count_literal).parse(input)?;
+    let (input, offset) = opt(preceded(
+        tuple((multispace1, tag_no_case("offset"), multispace1)),
+        count_literal,
+    ))
+    .parse(input)?;
+    Ok((input, LimitClause { count, offset }))
+}
+
+fn count_literal(input: &str) -> IResult<&str, usize> {
+    map_opt(digit1, |digits: &str| digits.parse::<usize>().ok()).parse(input)
+}
+
 fn order_by_clause(input: &str) -> IResult<&str, Vec<SortKey>> {
     preceded(
         tuple((
@@ -316,18 +1131,72 @@ fn order_key_separator(input: &str) -> IResult<&str, ()> {
 }
 
 fn sort_key(input: &str) -> IResult<&str, SortKey> {
-    let (input, path) = path(input)?;
+    let (input, (function, path)) = alt((
+        map(field_function_call, |(function, path)| {
+            (Some(function), path)
+        }),
+        map(path, |path| (None, path)),
+    ))
+    .parse(input)?;
     let (input, direction) = opt(preceded(multispace1, sort_direction)).parse(input)?;
 
     Ok((
         input,
         SortKey {
             path,
+            function,
             direction: direction.unwrap_or(SortDirection::Asc),
         },
     ))
 }
 
+fn field_function_call(input: &str) -> IResult<&str, (FieldFunction, String)> {
+    let (input, function) = field_function(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = char('(')(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, path) = path(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = char(')')(input)?;
+
+    Ok((input, (function, path)))
+}
+
+/// `sum(spec.containers[*].ports[*].containerPort)`: `[*]` хранится в пути
+/// как сегмент `*` (`spec.containers.*.ports.*.containerPort`).
+fn array_aggregate_call(input: &str) -> IResult<&str, (FieldFunction, String)> {
+    let (input, function) = alt((
+        value(FieldFunction::ArraySum, tag_no_case("sum")),
+        value(FieldFunction::ArrayMin, tag_no_case("min")),
+        value(FieldFunction::ArrayMax, tag_no_case("max")),
+        value(FieldFunction::ArrayAvg, tag_no_case("avg")),
+    ))
+    .parse(input)?;
+    let (input, _) = delimited(multispace0, char('('), multispace0).parse(input)?;
+    let (input, path) = map(
+        recognize(tuple((
+            path,
+            many1(tuple((tag("[*]"), opt(preceded(char('.'), path))))),
+        ))),
+        |path: &str| path.replace("[*]", ".*"),
+    )
+    .parse(input)?;
+    let (input, _) = preceded(multispace0, char(')')).parse(input)?;
+
+    Ok((input, (function, path)))
+}
+
+fn field_function(input: &str) -> IResult<&str, FieldFunction> {
+    alt((
+        value(FieldFunction::StrLen, tag_no_case("strlen")),
+        value(FieldFunction::Len, tag_no_case("len")),
+        value(FieldFunction::Age, tag_no_case("age")),
+        value(FieldFunction::ImageRepo, tag_no_case("image_repo")),
+        value(FieldFunction::ImageTag, tag_no_case("image_tag")),
+    ))
+    .parse(input)
+}
+
 fn sort_direction(input: &str) -> IResult<&str, SortDirection> {
     alt((
         value(SortDirection::Asc, tag_no_case("asc")),
@@ -341,11 +1210,39 @@ fn aggregation_expr(input: &str) -> IResult<&str, AggregationExpr> {
     let (input, _) = multispace0(input)?;
     let (input, _) = char('(')(input)?;
     let (input, _) = multispace0(input)?;
-    let (input, path) = aggregation_arg(input, function.clone())?;
+    let (input, (field_function, path)) = aggregation_arg(input, function.clone())?;
+    let (input, function) = match function {
+        AggregationFunction::Percentile(_) => map(
+            preceded(
+                delimited(multispace0, char(','), multispace0),
+                percentile_rank,
+            ),
+            AggregationFunction::Percentile,
+        )
+        .parse(input)?,
+        AggregationFunction::GroupConcat(separator) => map(
+            opt(preceded(
+                delimited(multispace0, char(','), multispace0),
+                map_opt(quoted_string_value, |value| {
+                    value.as_str().map(str::to_string)
+                }),
+            )),
+            |custom| AggregationFunction::GroupConcat(custom.unwrap_or_else(|| separator.clone())),
+        )
+        .parse(input)?,
+        function => (input, function),
+    };
     let (input, _) = multispace0(input)?;
     let (input, _) = char(')')(input)?;
 
-    Ok((input, AggregationExpr { function, path }))
+    Ok((
+        input,
+        AggregationExpr {
+            function,
+            path,
+            field_function,
+        },
+    ))
 }
 
 fn aggregation_function(input: &str) -> IResult<&str, AggregationFunction> {
@@ -355,33 +1252,85 @@ fn aggregation_function(input: &str) -> IResult<&str, AggregationFunction> {
         value(AggregationFunction::Min, tag_no_case("min")),
         value(AggregationFunction::Max, tag_no_case("max")),
         value(AggregationFunction::Avg, tag_no_case("avg")),
+        value(AggregationFunction::Range, tag_no_case("range")),
+        value(AggregationFunction::Median, tag_no_case("median")),
+        value(AggregationFunction::Stddev, tag_no_case("stddev")),
+        value(
+            AggregationFunction::Percentile(serde_json::Number::from(0)),
+            tag_no_case("percentile"),
+        ),
+        value(AggregationFunction::Mode, tag_no_case("mode")),
+        value(
+            AggregationFunction::GroupConcat(",".to_string()),
+            tag_no_case("group_concat"),
+        ),
     ))
     .parse(input)
 }
 
+/// Второй аргумент `percentile(path, p)`; диапазон проверяет
+/// `validate_query_ast`, чтобы ошибка называла значение.
+fn percentile_rank(input: &str) -> IResult<&str, serde_json::Number> {
+    map_opt(
+        recognize(tuple((
+            opt(char('-')),
+            digit1,
+            opt(tuple((char('.'), digit1))),
+        ))),
+        |rank: &str| serde_json::from_str(rank).ok(),
+    )
+    .parse(input)
+}
+
 fn aggregation_arg(
     input: &str,
     function: AggregationFunction,
-) -> IResult<&str, Option<String>> {
-    if matches!(function, AggregationFunction::Count) {
+) -> IResult<&str, (Option<FieldFunction>, Option<String>)> {
+    let field_arg = |input| {
         alt((
-            value(None, char('*')),
-            map(path, Some),
+            map(field_function_call, |(function, path)| {
+                (Some(function), Some(path))
+            }),
+            map(path, |path| (None, Some(path))),
         ))
         .parse(input)
+    };
+
+    if matches!(function, AggregationFunction::Count) {
+        alt((value((None, None), char('*')), field_arg)).parse(input)
     } else {
-        map(path, Some).parse(input)
+        field_arg(input)
     }
 }
 
 fn path(input: &str) -> IResult<&str, String> {
     map(
-        recognize(tuple((ident, many0(preceded(char('.'), ident))))),
+        recognize(tuple((ident, many0(preceded(char('.'), path_segment))))),
+        str::to_string,
+    )
+    .parse(input)
+}
+
+/// Путь в предикате может заканчиваться `*`: условие проверяется на
+/// всех полях, чей путь начинается с префикса до `*`.
+fn predicate_path(input: &str) -> IResult<&str, String> {
+    map(
+        recognize(tuple((path, opt(alt((tag(".*"), tag("/*"), tag("*"))))))),
         str::to_string,
     )
     .parse(input)
 }
 
+/// Сегмент пути; `/` допускается внутри сегмента для ключей вида
+/// `app.kubernetes.io/name`.
+fn path_segment(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        alt((ident, digit1)),
+        many0(preceded(char('/'), alt((ident, digit1)))),
+    )))
+    .parse(input)
+}
+
 fn ident(input: &str) -> IResult<&str, &str> {
     recognize(tuple((
         take_while1(is_ident_start),
@@ -462,15 +1411,37 @@ fn is_invalid_escape_error(err: &nom::Err<Error<&str>>) -> bool {
 
 fn bare_value(input: &str) -> IResult<&str, Value> {
     map(
-        verify(
-            take_while1(|c: char| !c.is_ascii_whitespace()),
-            |token: &str| !token.starts_with('\''),
-        ),
+        verify(bare_token, |token: &str| !token.starts_with('\'')),
         parse_scalar_value,
     )
     .parse(input)
 }
 
+/// Токен до пробела; непарная `)` закрывает группу условий и в токен не
+/// входит, парные скобки (`^(api|web)-`) остаются частью значения.
+fn bare_token(input: &str) -> IResult<&str, &str> {
+    let mut depth: usize = 0;
+    let end = input
+        .char_indices()
+        .find(|&(_, c)| match c {
+            '(' => {
+                depth += 1;
+                false
+            }
+            ')' if depth == 0 => true,
+            ')' => {
+                depth -= 1;
+                false
+            }
+            _ => c.is_ascii_whitespace(),
+        })
+        .map_or(input.len(), |(index, _)| index);
+    if end == 0 {
+        return Err(nom::Err::Error(Error::new(input, ErrorKind::TakeWhile1)));
+    }
+    Ok((&input[end..], &input[..end]))
+}
+
 fn parse_scalar_value(token: &str) -> Value {
     if token.eq_ignore_ascii_case("true") {
         return Value::Bool(true);
@@ -501,26 +1472,285 @@ mod tests {
     use serde_json::Value;
 
     use super::{
-        AggregationFunction, Operator, SelectClause, SortDirection, parse_query, parse_query_args,
+        AggregationExpr, AggregationFunction, Expr, FieldFunction, HavingColumn, LimitClause,
+        Operator, Predicate, SelectClause, SortDirection, ValueFunction, parse_query,
+        parse_query_args,
     };
 
+    #[test]
+    fn rejects_where_without_predicates() {
+        let err = parse_query("where").expect_err("must fail");
+        assert_eq!(err, "WHERE clause requires at least one predicate");
+
+        let err = parse_query("where select metadata.name").expect_err("must fail");
+        assert_eq!(err, "WHERE clause requires at least one predicate");
+
+        let err = parse_query_args(&["where".to_string()]).expect_err("must fail");
+        assert_eq!(err, "WHERE clause requires at least one predicate");
+    }
+
     #[test]
     fn parses_and_chain() {
         let ast = parse_query("where metadata.namespace == default AND spec.nodeName != worker-1")
             .expect("must parse valid query");
 
-        assert_eq!(ast.predicates.len(), 2);
-        assert_eq!(ast.predicates[0].op, Operator::Eq);
-        assert_eq!(ast.predicates[1].op, Operator::Ne);
+        assert_eq!(ast.predicates().len(), 2);
+        assert_eq!(ast.predicates()[0].op, Operator::Eq);
+        assert_eq!(ast.predicates()[1].op, Operator::Ne);
         assert_eq!(ast.select, None);
         assert_eq!(ast.order_by, None);
     }
 
+    #[test]
+    fn parses_exists_predicate() {
+        let ast = parse_query("where exists status.conditions and metadata.namespace == demo-a")
+            .expect("must parse valid query");
+
+        assert_eq!(ast.predicates().len(), 2);
+        assert_eq!(ast.predicates()[0].path, "status.conditions");
+        assert_eq!(ast.predicates()[0].op, Operator::Exists);
+        assert_eq!(ast.predicates()[0].value, Value::Null);
+        assert_eq!(ast.predicates()[1].op, Operator::Eq);
+    }
+
+    #[test]
+    fn parses_range_operators() {
+        let ast = parse_query(
+            "where spec.replicas > 3 and spec.replicas<=10 and metadata.name >= b and x < '5'",
+        )
+        .expect("must parse valid query");
+
+        let ops: Vec<Operator> = ast.predicates().into_iter().map(|p| p.op.clone()).collect();
+        assert_eq!(
+            ops,
+            vec![Operator::Gt, Operator::Le, Operator::Ge, Operator::Lt]
+        );
+        assert_eq!(ast.predicates()[0].value, Value::from(3));
+        assert_eq!(ast.predicates()[1].value, Value::from(10));
+        assert_eq!(ast.predicates()[3].value, Value::String("5".to_string()));
+    }
+
+    #[test]
+    fn parses_set_membership_predicates() {
+        let ast = parse_query(
+            "where metadata.namespace in (demo-a, demo-b,'name with space') \
+             and spec.replicas not in (1,2) and in == x",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(
+            ast.predicates()[0].op,
+            Operator::In {
+                values: vec![
+                    Value::String("demo-a".to_string()),
+                    Value::String("demo-b".to_string()),
+                    Value::String("name with space".to_string()),
+                ]
+            }
+        );
+        assert_eq!(
+            ast.predicates()[1].op,
+            Operator::NotIn {
+                values: vec![Value::from(1), Value::from(2)]
+            }
+        );
+        assert_eq!(ast.predicates()[2].path, "in");
+        assert_eq!(ast.predicates()[2].op, Operator::Eq);
+        assert!(parse_query("where metadata.namespace in ()").is_err());
+    }
+
+    #[test]
+    fn parses_like_predicate() {
+        let ast = parse_query("where metadata.name like 'api-*' and spec.x like '' and like == y")
+            .expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].op, Operator::Like("api-*".to_string()));
+        assert_eq!(ast.predicates()[1].op, Operator::Like(String::new()));
+        assert_eq!(ast.predicates()[2].path, "like");
+        assert_eq!(ast.predicates()[2].op, Operator::Eq);
+    }
+
+    #[test]
+    fn parses_regex_operators() {
+        let ast = parse_query("where metadata.name =~ '^api-[0-9]+$' and spec.x !~ tmp")
+            .expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].op, Operator::Regex);
+        assert_eq!(
+            ast.predicates()[0].value,
+            Value::String("^api-[0-9]+$".to_string())
+        );
+        assert_eq!(ast.predicates()[1].op, Operator::NotRegex);
+        assert_eq!(ast.predicates()[1].value, Value::String("tmp".to_string()));
+    }
+
+    #[test]
+    fn parses_null_checks() {
+        let ast = parse_query(
+            "where spec.nodeName is null and image_tag(spec.image) IS NOT NULL and is == x",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].path, "spec.nodeName");
+        assert_eq!(ast.predicates()[0].op, Operator::IsNull);
+        assert_eq!(ast.predicates()[1].function, Some(FieldFunction::ImageTag));
+        assert_eq!(ast.predicates()[1].op, Operator::IsNotNull);
+        assert_eq!(ast.predicates()[2].path, "is");
+        assert_eq!(ast.predicates()[2].op, Operator::Eq);
+        assert!(parse_query("where spec.nodeName is nil").is_err());
+    }
+
+    #[test]
+    fn parses_haskey_predicate() {
+        let ast = parse_query("where haskey metadata.labels 'team-*' and haskey == x")
+            .expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].path, "metadata.labels");
+        assert_eq!(ast.predicates()[0].op, Operator::HasKey);
+        assert_eq!(
+            ast.predicates()[0].value,
+            Value::String("team-*".to_string())
+        );
+        assert_eq!(ast.predicates()[1].path, "haskey");
+        assert_eq!(ast.predicates()[1].op, Operator::Eq);
+        assert!(parse_query("where haskey metadata.labels").is_err());
+    }
+
+    #[test]
+    fn parses_or_with_parenthesized_groups() {
+        let leaf = |path: &str, value: Value| {
+            Expr::Leaf(Predicate {
+                path: path.to_string(),
+                function: None,
+                op: Operator::Eq,
+                value,
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let grouped = parse_query("where (a == 1 or b == 2) and c == 3").expect("must parse");
+        assert_eq!(
+            grouped.filter,
+            Some(Expr::And(vec![
+                Expr::Or(vec![leaf("a", Value::from(1)), leaf("b", Value::from(2))]),
+                leaf("c", Value::from(3)),
+            ]))
+        );
+
+        let precedence = parse_query("where a == 1 OR b == 2 and c == 3").expect("must parse");
+        assert_eq!(
+            precedence.filter,
+            Some(Expr::Or(vec![
+                leaf("a", Value::from(1)),
+                Expr::And(vec![leaf("b", Value::from(2)), leaf("c", Value::from(3))]),
+            ]))
+        );
+
+        let flat = parse_query("where a == 1 and (b == 2 and c == 3)").expect("must parse");
+        assert_eq!(
+            flat.filter,
+            Expr::all(flat.predicates().into_iter().cloned().collect())
+        );
+
+        let regex =
+            parse_query("where (metadata.name =~ ^(api|web)- or or == x)").expect("must parse");
+        assert_eq!(
+            regex.predicates()[0].value,
+            Value::String("^(api|web)-".to_string())
+        );
+        assert_eq!(regex.predicates()[1].path, "or");
+        assert!(parse_query("where (a == 1 or b == 2").is_err());
+        assert!(parse_query("where a == 1 or").is_err());
+    }
+
+    #[test]
+    fn parses_not_prefix_over_predicates_and_groups() {
+        let eq = |path: &str| {
+            Expr::Leaf(Predicate {
+                path: path.to_string(),
+                function: None,
+                op: Operator::Eq,
+                value: Value::from(1),
+                value_path: None,
+                value_function: None,
+            })
+        };
+        let not = |inner| Expr::Not(Box::new(inner));
+
+        let group = parse_query("where not (a == 1 and b == 1)").expect("must parse");
+        assert_eq!(group.filter, Some(not(Expr::And(vec![eq("a"), eq("b")]))));
+        let tight = parse_query("where NOT a == 1 and not(b == 1) or c == 1").expect("must parse");
+        assert_eq!(
+            tight.filter,
+            Some(Expr::Or(vec![
+                Expr::And(vec![not(eq("a")), not(eq("b"))]),
+                eq("c")
+            ]))
+        );
+        let double = parse_query("where not not a == 1").expect("must parse");
+        assert_eq!(double.filter, Some(not(not(eq("a")))));
+        let field = parse_query("where not == 1 and a not in (1)").expect("must parse");
+        assert_eq!(field.predicates()[0].path, "not");
+        assert!(matches!(field.predicates()[1].op, Operator::NotIn { .. }));
+        assert!(parse_query("where not").is_err());
+    }
+
+    #[test]
+    fn parses_wildcard_predicate_paths() {
+        let ast = parse_query(
+            "where metadata.annotations.app.kubernetes.io/* == api and exists metadata.labels.*",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(
+            ast.predicates()[0].path,
+            "metadata.annotations.app.kubernetes.io/*"
+        );
+        assert_eq!(ast.predicates()[0].op, Operator::Eq);
+        assert_eq!(ast.predicates()[1].path, "metadata.labels.*");
+        assert_eq!(ast.predicates()[1].op, Operator::Exists);
+        assert!(parse_query("select metadata.labels.*").is_err());
+    }
+
+    #[test]
+    fn parses_array_aggregate_predicates_over_index_wildcards() {
+        let ast = parse_query(
+            "where sum(spec.containers[*].ports[*].containerPort) > 0 \
+             and max( spec.values[*] ) <= 10",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].function, Some(FieldFunction::ArraySum));
+        assert_eq!(
+            ast.predicates()[0].path,
+            "spec.containers.*.ports.*.containerPort"
+        );
+        assert_eq!(ast.predicates()[0].op, Operator::Gt);
+        assert_eq!(ast.predicates()[1].function, Some(FieldFunction::ArrayMax));
+        assert_eq!(ast.predicates()[1].path, "spec.values.*");
+        assert!(parse_query("where sum(spec.replicas) > 0").is_err());
+    }
+
+    #[test]
+    fn parses_path_named_exists_as_comparison() {
+        let ast = parse_query("where exists == true").expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].path, "exists");
+        assert_eq!(ast.predicates()[0].op, Operator::Eq);
+        assert_eq!(ast.predicates()[0].value, Value::Bool(true));
+    }
+
+    #[test]
+    fn rejects_exists_without_path() {
+        let err = parse_query("where exists").expect_err("must reject exists without path");
+        assert_eq!(err, "invalid query syntax");
+    }
+
     #[test]
     fn parses_lowercase_and() {
         let ast = parse_query("where metadata.namespace == default and spec.nodeName != worker-1")
             .expect("must parse valid query");
-        assert_eq!(ast.predicates.len(), 2);
+        assert_eq!(ast.predicates().len(), 2);
         assert_eq!(ast.select, None);
     }
 
@@ -528,9 +1758,9 @@ mod tests {
     fn does_not_split_and_inside_quoted_value() {
         let ast = parse_query("where metadata.name == 'a AND b' and metadata.namespace == demo-a")
             .expect("must parse valid query");
-        assert_eq!(ast.predicates.len(), 2);
+        assert_eq!(ast.predicates().len(), 2);
         assert_eq!(
-            ast.predicates[0].value,
+            ast.predicates()[0].value,
             Value::String("a AND b".to_string())
         );
         assert_eq!(ast.select, None);
@@ -541,8 +1771,8 @@ mod tests {
         let ast = parse_query("where spec.replicas == 2 AND spec.enabled == true")
             .expect("must parse valid query");
 
-        assert_eq!(ast.predicates[0].value, Value::from(2));
-        assert_eq!(ast.predicates[1].value, Value::Bool(true));
+        assert_eq!(ast.predicates()[0].value, Value::from(2));
+        assert_eq!(ast.predicates()[1].value, Value::Bool(true));
         assert_eq!(ast.select, None);
     }
 
@@ -550,11 +1780,26 @@ mod tests {
     fn parses_bare_value_with_apostrophe() {
         let ast = parse_query("where metadata.name == O'Reilly").expect("must parse valid query");
         assert_eq!(
-            ast.predicates[0].value,
+            ast.predicates()[0].value,
             Value::String("O'Reilly".to_string())
         );
     }
 
+    #[test]
+    fn parses_empty_quoted_value_in_text_and_args_form() {
+        let ast = parse_query("where metadata.labels.team == ''").expect("must parse valid query");
+        assert_eq!(ast.predicates()[0].value, Value::String(String::new()));
+
+        let ast = parse_query_args(&[
+            "where".to_string(),
+            "metadata.labels.team".to_string(),
+            "==".to_string(),
+            String::new(),
+        ])
+        .expect("must parse valid args");
+        assert_eq!(ast.predicates()[0].value, Value::String(String::new()));
+    }
+
     #[test]
     fn parses_quoted_value_with_supported_escapes() {
         let ast = parse_query(
@@ -563,7 +1808,7 @@ mod tests {
         .expect("must parse valid query");
 
         assert_eq!(
-            ast.predicates[0].value,
+            ast.predicates()[0].value,
             Value::String("line1\nline2\tcol\rret \\ ' \"".to_string())
         );
     }
@@ -577,8 +1822,11 @@ mod tests {
             "demo-a".to_string(),
         ];
         let ast = parse_query_args(&args).expect("must parse valid args");
-        assert_eq!(ast.predicates.len(), 1);
-        assert_eq!(ast.predicates[0].value, Value::String("demo-a".to_string()));
+        assert_eq!(ast.predicates().len(), 1);
+        assert_eq!(
+            ast.predicates()[0].value,
+            Value::String("demo-a".to_string())
+        );
         assert_eq!(ast.select, None);
     }
 
@@ -592,7 +1840,7 @@ mod tests {
         ];
         let ast = parse_query_args(&args).expect("must parse valid args");
         assert_eq!(
-            ast.predicates[0].value,
+            ast.predicates()[0].value,
             Value::String("O'Reilly Media".to_string())
         );
     }
@@ -633,37 +1881,88 @@ mod tests {
     }
 
     #[test]
-    fn parses_select_single_path_in_args_query() {
-        let args = vec![
-            "where".to_string(),
-            "metadata.namespace".to_string(),
-            "==".to_string(),
-            "demo-a".to_string(),
-            "select".to_string(),
-            "metadata.name".to_string(),
-        ];
-        let ast = parse_query_args(&args).expect("must parse valid args");
+    fn parses_select_single_path_in_args_query() {
+        let args = vec![
+            "where".to_string(),
+            "metadata.namespace".to_string(),
+            "==".to_string(),
+            "demo-a".to_string(),
+            "select".to_string(),
+            "metadata.name".to_string(),
+        ];
+        let ast = parse_query_args(&args).expect("must parse valid args");
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec!["metadata.name".to_string()]))
+        );
+    }
+
+    #[test]
+    fn parses_select_paths_separated_by_spaces() {
+        let ast = parse_query(
+            "where metadata.namespace == demo-a select metadata.name metadata.namespace",
+        )
+        .expect("must parse valid query");
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec![
+                "metadata.name".to_string(),
+                "metadata.namespace".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn parses_select_paths_with_type_annotations() {
+        let ast = parse_query(
+            "where metadata.namespace == demo-a select spec.replicas::int, metadata.name::string",
+        )
+        .expect("must parse valid query");
         assert_eq!(
             ast.select,
-            Some(SelectClause::Paths(vec!["metadata.name".to_string()]))
+            Some(SelectClause::Paths(vec![
+                "spec.replicas::int".to_string(),
+                "metadata.name::string".to_string()
+            ]))
+        );
+        assert!(
+            parse_query("where metadata.namespace == demo-a select spec.replicas::date").is_err()
         );
     }
 
     #[test]
-    fn parses_select_paths_separated_by_spaces() {
+    fn translates_jsonpath_select_to_dotted_paths() {
         let ast = parse_query(
-            "where metadata.namespace == demo-a select metadata.name metadata.namespace",
+            "where metadata.namespace == demo-a select {.metadata.name}{.status.phase}",
         )
         .expect("must parse valid query");
         assert_eq!(
             ast.select,
             Some(SelectClause::Paths(vec![
                 "metadata.name".to_string(),
-                "metadata.namespace".to_string()
+                "status.phase".to_string()
+            ]))
+        );
+
+        let ast =
+            parse_query("where a == b select {$.spec.containers[0].image}, {.metadata['uid']}")
+                .expect("must parse valid query");
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec![
+                "spec.containers.0.image".to_string(),
+                "metadata.uid".to_string()
             ]))
         );
     }
 
+    #[test]
+    fn rejects_jsonpath_filters_and_wildcards() {
+        assert!(parse_query("where a == b select {.items[*].metadata.name}").is_err());
+        assert!(parse_query("where a == b select {.items[?(@.x==1)].y}").is_err());
+        assert!(parse_query("where a == b select {..name}").is_err());
+    }
+
     #[test]
     fn parses_order_by_single_key_with_default_direction() {
         let ast = parse_query("where metadata.namespace == demo-a order by metadata.name")
@@ -675,6 +1974,44 @@ mod tests {
         assert_eq!(keys[0].direction, SortDirection::Asc);
     }
 
+    #[test]
+    fn ignores_line_and_inline_comments() {
+        let ast = parse_query(
+            "# pods owned by the api team\nwhere metadata.labels.team == api -- label filter\n\
+             and metadata.namespace != kube-system # skip system\n\
+             -- sorting\norder by metadata.name",
+        )
+        .expect("comments must be ignored");
+
+        assert_eq!(ast.predicates().len(), 2);
+        assert_eq!(
+            ast.predicates()[1].value,
+            Value::String("kube-system".to_string())
+        );
+        assert_eq!(ast.order_by.map(|keys| keys.len()), Some(1));
+    }
+
+    #[test]
+    fn keeps_comment_markers_inside_quoted_and_bare_values() {
+        let ast = parse_query(
+            "where metadata.annotations.note == '# not -- a comment' and metadata.labels.tier == a--b",
+        )
+        .expect("quoted comment markers must survive");
+
+        assert_eq!(
+            ast.predicates()[0].value,
+            Value::String("# not -- a comment".to_string())
+        );
+        assert_eq!(ast.predicates()[1].value, Value::String("a--b".to_string()));
+
+        let args: Vec<String> = ["where", "metadata.labels.channel", "==", "#ops"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let ast = parse_query_args(&args).expect("shell args must not start comments");
+        assert_eq!(ast.predicates()[0].value, Value::String("#ops".to_string()));
+    }
+
     #[test]
     fn parses_order_by_multiple_keys() {
         let ast = parse_query(
@@ -690,6 +2027,104 @@ mod tests {
         assert_eq!(keys[1].direction, SortDirection::Asc);
     }
 
+    #[test]
+    fn parses_image_functions_on_predicate_lhs() {
+        let ast = parse_query(
+            "where image_repo(spec.containers.0.image) == nginx and image_tag( spec.containers.0.image ) != latest",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(ast.predicates().len(), 2);
+        assert_eq!(ast.predicates()[0].path, "spec.containers.0.image");
+        assert_eq!(ast.predicates()[0].function, Some(FieldFunction::ImageRepo));
+        assert_eq!(ast.predicates()[1].function, Some(FieldFunction::ImageTag));
+        assert_eq!(ast.predicates()[1].op, Operator::Ne);
+        assert_eq!(
+            ast.predicates()[1].value,
+            Value::String("latest".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_field_reference_on_predicate_rhs() {
+        let ast = parse_query(
+            "where metadata.generation != @status.observedGeneration and spec.owner == '@team'",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].value, Value::Null);
+        assert_eq!(
+            ast.predicates()[0].value_path.as_deref(),
+            Some("status.observedGeneration")
+        );
+        assert_eq!(
+            ast.predicates()[1].value,
+            Value::String("@team".to_string())
+        );
+        assert_eq!(ast.predicates()[1].value_path, None);
+    }
+
+    #[test]
+    fn parses_now_on_predicate_rhs() {
+        let ast = parse_query(
+            "where status.startTime < now() and metadata.ts >= NOW( ) and note == 'now()'",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(ast.predicates()[0].value_function, Some(ValueFunction::Now));
+        assert_eq!(ast.predicates()[0].value, Value::Null);
+        assert_eq!(ast.predicates()[1].value_function, Some(ValueFunction::Now));
+        assert_eq!(ast.predicates()[2].value_function, None);
+        assert_eq!(
+            ast.predicates()[2].value,
+            Value::String("now()".to_string())
+        );
+        assert!(parse_query("where metadata.name =~ now()").is_err());
+    }
+
+    #[test]
+    fn parses_order_by_length_functions() {
+        let ast = parse_query(
+            "where kind == Pod order by len(spec.containers) desc, strlen( metadata.name )",
+        )
+        .expect("must parse valid query");
+
+        let keys = ast.order_by.expect("order keys must be parsed");
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].path, "spec.containers");
+        assert_eq!(keys[0].function, Some(FieldFunction::Len));
+        assert_eq!(keys[0].direction, SortDirection::Desc);
+        assert_eq!(keys[1].path, "metadata.name");
+        assert_eq!(keys[1].function, Some(FieldFunction::StrLen));
+        assert_eq!(keys[1].direction, SortDirection::Asc);
+    }
+
+    #[test]
+    fn parses_order_by_path_named_like_function() {
+        let ast = parse_query("where metadata.namespace == demo-a order by len desc")
+            .expect("must parse valid query");
+
+        let keys = ast.order_by.expect("order keys must be parsed");
+        assert_eq!(keys[0].path, "len");
+        assert_eq!(keys[0].function, None);
+    }
+
+    #[test]
+    fn parses_aggregation_over_field_function() {
+        let ast = parse_query("where kind == Pod select max(age(metadata.creationTimestamp))")
+            .expect("must parse valid query");
+
+        let Some(SelectClause::Aggregations(aggregations)) = ast.select else {
+            panic!("aggregation select expected");
+        };
+        assert_eq!(aggregations[0].function, AggregationFunction::Max);
+        assert_eq!(
+            aggregations[0].path.as_deref(),
+            Some("metadata.creationTimestamp")
+        );
+        assert_eq!(aggregations[0].field_function, Some(FieldFunction::Age));
+    }
+
     #[test]
     fn parses_order_by_before_select() {
         let ast = parse_query(
@@ -795,6 +2230,54 @@ mod tests {
         assert_eq!(expressions[0].path.as_deref(), Some("spec.replicas"));
         assert_eq!(expressions[1].function, AggregationFunction::Avg);
         assert_eq!(expressions[2].function, AggregationFunction::Count);
+
+        let ast = parse_query("where kind == Pod select MEDIAN(spec.replicas) stddev(spec.ratio)")
+            .expect("must parse aggregate query");
+        let Some(SelectClause::Aggregations(expressions)) = ast.select else {
+            panic!("expected aggregation select");
+        };
+        assert_eq!(expressions[0].function, AggregationFunction::Median);
+        assert_eq!(expressions[1].function, AggregationFunction::Stddev);
+        assert_eq!(expressions[1].path.as_deref(), Some("spec.ratio"));
+
+        let ast = parse_query("where kind == Pod select percentile(spec.replicas, 99.5)")
+            .expect("must parse percentile");
+        let Some(SelectClause::Aggregations(expressions)) = ast.select else {
+            panic!("expected aggregation select");
+        };
+        assert_eq!(
+            expressions[0].function,
+            AggregationFunction::Percentile(serde_json::Number::from_f64(99.5).expect("finite"))
+        );
+
+        let ast =
+            parse_query("where kind == Pod select mode(status.phase)").expect("must parse mode");
+        let Some(SelectClause::Aggregations(expressions)) = ast.select else {
+            panic!("expected aggregation select");
+        };
+        assert_eq!(expressions[0].function, AggregationFunction::Mode);
+
+        let ast = parse_query(
+            "where kind == Pod select spec.owner, group_concat(metadata.name), \
+             group_concat(metadata.uid, '; ') group by spec.owner",
+        )
+        .expect("must parse group_concat");
+        let Some(SelectClause::Aggregations(aggregations)) = ast.select else {
+            panic!("expected aggregation select");
+        };
+        assert_eq!(
+            aggregations[0].function,
+            AggregationFunction::GroupConcat(",".to_string())
+        );
+        assert_eq!(
+            aggregations[1].function,
+            AggregationFunction::GroupConcat("; ".to_string())
+        );
+        assert_eq!(aggregations[1].path.as_deref(), Some("metadata.uid"));
+
+        let err = parse_query("where kind == Pod select percentile(spec.replicas, 150)")
+            .expect_err("must reject out-of-range percentile");
+        assert_eq!(err, "percentile must be between 0 and 100, got 150");
     }
 
     #[test]
@@ -847,6 +2330,196 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parses_computed_boolean_columns_in_select() {
+        let ast = parse_query(
+            "where kind == Pod select metadata.name (status.phase == Running) as ready, \
+             ( not spec.nodeName == a or spec.paused == true ) AS idle",
+        )
+        .expect("must parse valid query");
+
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec![
+                "metadata.name".to_string(),
+                "ready".to_string(),
+                "idle".to_string(),
+            ]))
+        );
+        assert_eq!(ast.computed.len(), 2);
+        assert_eq!(ast.computed[0].alias, "ready");
+        let Expr::Leaf(predicate) = &ast.computed[0].expr else {
+            panic!("single condition must stay a leaf");
+        };
+        assert_eq!(predicate.path, "status.phase");
+        assert_eq!(predicate.value, Value::String("Running".to_string()));
+        assert!(matches!(&ast.computed[1].expr, Expr::Or(terms) if terms.len() == 2));
+
+        assert!(parse_query("where kind == Pod select (status.phase == Running)").is_err());
+        assert_eq!(
+            parse_query("where kind == Pod select count(*) (status.phase == Running) as ready"),
+            Err("cannot mix projection paths and aggregations in SELECT".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_group_by_with_grouping_columns_in_select() {
+        let ast = parse_query(
+            "where kind == Pod select spec.owner, count(*) group by spec.owner, metadata.namespace",
+        )
+        .expect("must parse valid query");
+        assert_eq!(
+            ast.group_by,
+            Some(vec![
+                "spec.owner".to_string(),
+                "metadata.namespace".to_string(),
+            ])
+        );
+        assert!(matches!(
+            ast.select,
+            Some(SelectClause::Aggregations(ref aggregations)) if aggregations.len() == 1
+        ));
+
+        let ast = parse_query("where kind == Pod group by spec.owner select count(*) sum(a)")
+            .expect("must parse valid query");
+        assert_eq!(ast.group_by, Some(vec!["spec.owner".to_string()]));
+
+        assert_eq!(
+            parse_query("where kind == Pod select metadata.name, count(*) group by spec.owner"),
+            Err("SELECT path `metadata.name` must appear in GROUP BY".to_string())
+        );
+        assert_eq!(
+            parse_query("where kind == Pod select spec.owner group by spec.owner"),
+            Err("GROUP BY requires aggregations in SELECT".to_string())
+        );
+        assert_eq!(
+            parse_query("where kind == Pod select count(*) group by a group by b"),
+            Err("invalid query syntax".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_having_over_aggregation_columns() {
+        let ast = parse_query(
+            "where kind == Pod select spec.owner count(*) group by spec.owner \
+             having COUNT( * ) > 1 or not (spec.owner == ops) limit 5",
+        )
+        .expect("must parse valid query");
+
+        let having = ast.having.expect("having must parse");
+        let Expr::Or(terms) = &having else {
+            panic!("having must keep or/not structure");
+        };
+        let Expr::Leaf(count) = &terms[0] else {
+            panic!("aggregation condition must be a leaf");
+        };
+        assert_eq!(
+            count.column,
+            HavingColumn::Aggregation(AggregationExpr {
+                function: AggregationFunction::Count,
+                path: None,
+                field_function: None,
+            })
+        );
+        assert_eq!(count.op, Operator::Gt);
+        assert_eq!(count.value, Value::from(1));
+        assert!(matches!(&terms[1], Expr::Not(_)));
+        assert_eq!(ast.limit.map(|limit| limit.count), Some(5));
+
+        let ast = parse_query(
+            "where kind == Pod select sum(len(spec.containers)) \
+             having sum(len(spec.containers)) >= 4",
+        )
+        .expect("must parse valid query");
+        let having = ast.having.expect("having must parse");
+        assert_eq!(
+            having.predicates()[0].column,
+            HavingColumn::Aggregation(AggregationExpr {
+                function: AggregationFunction::Sum,
+                path: Some("spec.containers".to_string()),
+                field_function: Some(FieldFunction::Len),
+            })
+        );
+        assert_eq!(
+            parse_query("where kind == Pod select count(*) having count(*)"),
+            Err("invalid query syntax".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_select_distinct_over_projection_paths() {
+        let ast = parse_query("where kind == Pod select DISTINCT spec.owner, metadata.namespace")
+            .expect("must parse valid query");
+        assert!(ast.distinct);
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec![
+                "spec.owner".to_string(),
+                "metadata.namespace".to_string(),
+            ]))
+        );
+
+        let ast = parse_query("where kind == Pod select distinct").expect("must parse valid query");
+        assert!(!ast.distinct);
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec!["distinct".to_string()]))
+        );
+        assert_eq!(
+            parse_query("where kind == Pod select distinct count(*)"),
+            Err("aggregation queries do not support DISTINCT".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_limit_after_select_paths_and_order_by() {
+        let ast = parse_query(
+            "where metadata.namespace == demo-a select metadata.name spec.nodeName limit 5",
+        )
+        .expect("must parse valid query");
+        assert_eq!(
+            ast.select,
+            Some(SelectClause::Paths(vec![
+                "metadata.name".to_string(),
+                "spec.nodeName".to_string(),
+            ]))
+        );
+        assert_eq!(
+            ast.limit,
+            Some(LimitClause {
+                count: 5,
+                offset: None,
+            })
+        );
+
+        let ast = parse_query("where metadata.name == limit LIMIT 2 order by metadata.name")
+            .expect("must parse valid query");
+        assert_eq!(ast.limit.map(|limit| limit.count), Some(2));
+        assert!(ast.order_by.is_some());
+
+        assert_eq!(
+            parse_query("where metadata.name == pod-a limit 1 limit 2"),
+            Err("invalid query syntax".to_string())
+        );
+        let ast = parse_query("where metadata.name == pod-a limit 20 OFFSET 40 select spec")
+            .expect("must parse valid query");
+        assert_eq!(
+            ast.limit,
+            Some(LimitClause {
+                count: 20,
+                offset: Some(40),
+            })
+        );
+        assert_eq!(
+            parse_query("where metadata.name == pod-a offset 40"),
+            Err("unexpected token `offset` after WHERE".to_string())
+        );
+        assert_eq!(
+            parse_query("where metadata.name == pod-a limit 0"),
+            Err("LIMIT must be greater than zero".to_string())
+        );
+    }
+
     #[test]
     fn rejects_duplicate_select_clause() {
         let err =
@@ -875,7 +2548,27 @@ mod tests {
     fn rejects_unknown_sort_direction() {
         let err = parse_query("where metadata.name == pod-a order by metadata.name upward")
             .expect_err("must reject unknown direction");
-        assert_eq!(err, "invalid query syntax");
+        assert_eq!(err, "unexpected token `upward` after ORDER BY");
+    }
+
+    #[test]
+    fn reports_unexpected_trailing_token_with_keyword_suggestion() {
+        assert_eq!(
+            parse_query("where metadata.name == pod-a order by metadata.name limt 5"),
+            Err("unexpected token `limt` after ORDER BY; did you mean `limit`?".to_string())
+        );
+        assert_eq!(
+            parse_query("where metadata.name == pod-a SLECT metadata.name"),
+            Err("unexpected token `SLECT` after WHERE; did you mean `select`?".to_string())
+        );
+        assert_eq!(
+            parse_query("where kind == Pod select count(*) group by spec.owner havin x"),
+            Err("unexpected token `havin` after GROUP BY; did you mean `having`?".to_string())
+        );
+        assert_eq!(
+            parse_query("where metadata.name == pod-a limit 5 banana"),
+            Err("unexpected token `banana` after LIMIT".to_string())
+        );
     }
 
     #[test]
@@ -885,6 +2578,45 @@ mod tests {
         assert_eq!(err, "query must start with WHERE");
     }
 
+    #[test]
+    fn suggests_closest_keyword_for_common_misspellings() {
+        let suggestion = |query: &str| {
+            let err = parse_query(query).expect_err("misspelled query must fail");
+            err.split_once("did you mean ")
+                .map(|(_, keyword)| keyword.to_string())
+        };
+
+        assert_eq!(
+            suggestion("wehre metadata.name == pod-a"),
+            Some("`where`?".to_string())
+        );
+        assert_eq!(
+            suggestion("where a == b seelct metadata.name"),
+            Some("`select`?".to_string())
+        );
+        assert_eq!(
+            suggestion("where a == b oder by spec.x"),
+            Some("`order by`?".to_string())
+        );
+        assert_eq!(
+            suggestion("where a == b gruop by spec.owner"),
+            Some("`group by`?".to_string())
+        );
+        assert_eq!(
+            suggestion("where a == b order by x limti 5"),
+            Some("`limit`?".to_string())
+        );
+        assert_eq!(suggestion("where a == b order by x zzz"), None);
+        assert_eq!(
+            parse_query_args(&["selcet".to_string(), "a".to_string()]),
+            Err("query must start with WHERE".to_string())
+        );
+        assert_eq!(
+            parse_query_args(&["whre".to_string(), "a".to_string()]),
+            Err("query must start with WHERE; did you mean `where`?".to_string())
+        );
+    }
+
     #[test]
     fn parse_query_args_preserves_values_with_spaces() {
         let args = vec![
@@ -895,7 +2627,7 @@ mod tests {
         ];
         let ast = parse_query_args(&args).expect("must parse spaced value from args");
         assert_eq!(
-            ast.predicates[0].value,
+            ast.predicates()[0].value,
             Value::String("api pod".to_string())
         );
     }
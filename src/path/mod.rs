@@ -2,6 +2,16 @@ use std::collections::BTreeMap;
 
 use serde_json::{Map, Value};
 
+/// Какие представления массива попадают в flattened поля: значения по
+/// индексам (`x.0`), массив целиком по родительскому пути (`x`) или оба.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayMode {
+    Indexed,
+    Whole,
+    #[default]
+    Both,
+}
+
 pub fn encode_segment(segment: &str) -> String {
     segment.replace('%', "%25").replace('.', "%2E")
 }
@@ -45,8 +55,15 @@ pub fn decode_path(path: &str) -> String {
 }
 
 pub fn flatten_json_to_fields(root: &Value) -> BTreeMap<String, Value> {
+    flatten_json_with_array_mode(root, ArrayMode::Both)
+}
+
+pub fn flatten_json_with_array_mode(
+    root: &Value,
+    array_mode: ArrayMode,
+) -> BTreeMap<String, Value> {
     let mut out = BTreeMap::new();
-    flatten_segments(&mut Vec::new(), root, &mut out);
+    flatten_segments(&mut Vec::new(), root, array_mode, &mut out);
     out
 }
 
@@ -89,23 +106,26 @@ pub fn select_path_value(
 fn flatten_segments(
     path: &mut Vec<String>,
     value: &Value,
+    array_mode: ArrayMode,
     out: &mut BTreeMap<String, Value>,
 ) {
     match value {
         Value::Object(map) => {
             for (key, child) in map {
                 path.push(encode_segment(key));
-                flatten_segments(path, child, out);
+                flatten_segments(path, child, array_mode, out);
                 path.pop();
             }
         }
         Value::Array(array) => {
-            for (index, child) in array.iter().enumerate() {
-                path.push(index.to_string());
-                flatten_segments(path, child, out);
-                path.pop();
+            if array_mode != ArrayMode::Whole {
+                for (index, child) in array.iter().enumerate() {
+                    path.push(index.to_string());
+                    flatten_segments(path, child, array_mode, out);
+                    path.pop();
+                }
             }
-            if !path.is_empty() {
+            if array_mode != ArrayMode::Indexed && !path.is_empty() {
                 out.insert(path.join("."), value.clone());
             }
         }
@@ -165,10 +185,45 @@ mod tests {
     use serde_json::{Value, json};
 
     use super::{
-        decode_path, decode_segment, encode_path, encode_segment, flatten_json_to_fields,
-        reconstruct_nested_from_fields, select_path_value,
+        ArrayMode, decode_path, decode_segment, encode_path, encode_segment,
+        flatten_json_to_fields, flatten_json_with_array_mode, reconstruct_nested_from_fields,
+        select_path_value,
     };
 
+    #[test]
+    fn array_mode_controls_indexed_and_whole_fields() {
+        let root = json!({"spec": {"args": ["--a", "--b"], "ports": [{"port": 80}]}});
+        let keys = |mode: ArrayMode| -> Vec<String> {
+            flatten_json_with_array_mode(&root, mode)
+                .into_keys()
+                .collect()
+        };
+
+        assert_eq!(
+            keys(ArrayMode::Both),
+            vec![
+                "spec.args",
+                "spec.args.0",
+                "spec.args.1",
+                "spec.ports",
+                "spec.ports.0.port"
+            ]
+        );
+        assert_eq!(
+            keys(ArrayMode::Indexed),
+            vec!["spec.args.0", "spec.args.1", "spec.ports.0.port"]
+        );
+        assert_eq!(keys(ArrayMode::Whole), vec!["spec.args", "spec.ports"]);
+        assert_eq!(
+            flatten_json_with_array_mode(&root, ArrayMode::Whole).get("spec.ports"),
+            Some(&json!([{"port": 80}]))
+        );
+        assert_eq!(
+            flatten_json_to_fields(&root),
+            flatten_json_with_array_mode(&root, ArrayMode::Both)
+        );
+    }
+
     #[test]
     fn encodes_and_decodes_segments() {
         assert_eq!(encode_segment("annotations"), "annotations");
@@ -222,6 +277,22 @@ mod tests {
         assert_eq!(reconstructed, root);
     }
 
+    #[test]
+    fn distinct_source_keys_never_share_a_flattened_key() {
+        let root = json!({
+            "a.b": 1,
+            "a": {"b": 2},
+            "a%2Eb": 3,
+            "list": [{"0": 4}, 5]
+        });
+
+        let fields = flatten_json_to_fields(&root);
+        assert_eq!(
+            fields.into_keys().collect::<Vec<_>>(),
+            vec!["a%252Eb", "a%2Eb", "a.b", "list", "list.0.0", "list.1"]
+        );
+    }
+
     #[test]
     fn select_parent_path_rebuilds_with_dotted_keys() {
         let mut fields = BTreeMap::new();
@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+
+use crate::dynamic_object::{CHANGED_FIELD, DynamicObject};
+use crate::error::SnapshotError;
+use crate::path::{self, ArrayMode};
+
+const UID_FIELD: &str = "metadata.uid";
+const NEW_OBJECT_MARKER: &str = "(new)";
+
+pub fn load_snapshot(
+    path: &str,
+    array_mode: ArrayMode,
+) -> Result<Vec<DynamicObject>, SnapshotError> {
+    let content = std::fs::read_to_string(path).map_err(|source| SnapshotError::Read {
+        path: path.to_string(),
+        source,
+    })?;
+    parse_snapshot(path, &content, array_mode)
+}
+
+/// Принимает JSON-массив объектов (`--describe -o json`) или list
+/// с полем `items` (`kubectl get -o json`). Массивы раскладываются в том же
+/// `ArrayMode`, что и текущие объекты, иначе diff видит лишние пути.
+pub fn parse_snapshot(
+    path: &str,
+    content: &str,
+    array_mode: ArrayMode,
+) -> Result<Vec<DynamicObject>, SnapshotError> {
+    let root: Value = serde_json::from_str(content).map_err(|source| SnapshotError::Parse {
+        path: path.to_string(),
+        source,
+    })?;
+    let items = match root {
+        Value::Array(items) => items,
+        Value::Object(mut list) => match list.remove("items") {
+            Some(Value::Array(items)) => items,
+            _ => {
+                return Err(SnapshotError::Shape {
+                    path: path.to_string(),
+                });
+            }
+        },
+        _ => {
+            return Err(SnapshotError::Shape {
+                path: path.to_string(),
+            });
+        }
+    };
+
+    Ok(items
+        .iter()
+        .map(|item| DynamicObject {
+            fields: path::flatten_json_with_array_mode(item, array_mode),
+        })
+        .collect())
+}
+
+/// Оставляет объекты, чьи поля отличаются от snapshot (сопоставление по
+/// `metadata.uid`), и записывает измененные пути в `__changed`.
+/// Объекты без пары в snapshot помечаются как `(new)`.
+pub fn diff_with_snapshot(
+    current: Vec<DynamicObject>,
+    snapshot: &[DynamicObject],
+) -> Vec<DynamicObject> {
+    let previous_by_uid: BTreeMap<&str, &DynamicObject> = snapshot
+        .iter()
+        .filter_map(|object| Some((object_uid(object)?, object)))
+        .collect();
+
+    current
+        .into_iter()
+        .filter_map(|mut object| {
+            let previous = object_uid(&object).and_then(|uid| previous_by_uid.get(uid));
+            let changed = match previous {
+                Some(previous) => {
+                    let changed = changed_paths(previous, &object);
+                    if changed.is_empty() {
+                        return None;
+                    }
+                    changed.join(",")
+                }
+                None => NEW_OBJECT_MARKER.to_string(),
+            };
+            object
+                .fields
+                .insert(CHANGED_FIELD.to_string(), Value::String(changed));
+            Some(object)
+        })
+        .collect()
+}
+
+/// Пути scalar-полей, которые добавились, исчезли или изменились.
+/// Массивы и объекты целиком не сравниваются: их изменения видны по
+/// вложенным путям.
+pub fn changed_paths(previous: &DynamicObject, current: &DynamicObject) -> Vec<String> {
+    let keys: BTreeSet<&String> = previous
+        .fields
+        .keys()
+        .chain(current.fields.keys())
+        .collect();
+    keys.into_iter()
+        .filter(|key| {
+            let before = previous.fields.get(*key);
+            let after = current.fields.get(*key);
+            before != after && !is_composite(before) && !is_composite(after)
+        })
+        .map(|key| path::decode_path(key))
+        .collect()
+}
+
+fn object_uid(object: &DynamicObject) -> Option<&str> {
+    object.fields.get(UID_FIELD).and_then(Value::as_str)
+}
+
+fn is_composite(value: Option<&Value>) -> bool {
+    matches!(value, Some(Value::Array(_) | Value::Object(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::dynamic_object::{CHANGED_FIELD, DynamicObject};
+    use crate::error::SnapshotError;
+    use crate::path::{self, ArrayMode};
+
+    use super::{changed_paths, diff_with_snapshot, parse_snapshot};
+
+    fn object(value: Value) -> DynamicObject {
+        DynamicObject {
+            fields: path::flatten_json_to_fields(&value),
+        }
+    }
+
+    #[test]
+    fn reports_changed_paths_between_two_snapshots() {
+        let previous = parse_snapshot(
+            "before.json",
+            &json!({
+                "items": [
+                    {
+                        "metadata": {"uid": "u1", "name": "api"},
+                        "spec": {"replicas": 2, "containers": [{"image": "api:v1"}]}
+                    },
+                    {"metadata": {"uid": "u2", "name": "web"}, "spec": {"replicas": 1}}
+                ]
+            })
+            .to_string(),
+            ArrayMode::Both,
+        )
+        .expect("snapshot must parse");
+        let current = vec![
+            object(json!({
+                "metadata": {"uid": "u1", "name": "api", "labels": {"app.kubernetes.io/name": "api"}},
+                "spec": {"replicas": 3, "containers": [{"image": "api:v1"}]}
+            })),
+            object(json!({"metadata": {"uid": "u2", "name": "web"}, "spec": {"replicas": 1}})),
+            object(json!({"metadata": {"uid": "u3", "name": "jobs"}})),
+        ];
+
+        assert_eq!(
+            changed_paths(&previous[0], &current[0]),
+            vec![
+                "metadata.labels.app.kubernetes.io/name".to_string(),
+                "spec.replicas".to_string(),
+            ]
+        );
+
+        let drifted = diff_with_snapshot(current, &previous);
+        let changed: Vec<(Option<&str>, Option<&str>)> = drifted
+            .iter()
+            .map(|object| {
+                (
+                    object.get("metadata.name").and_then(Value::as_str),
+                    object.fields.get(CHANGED_FIELD).and_then(Value::as_str),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                (
+                    Some("api"),
+                    Some("metadata.labels.app.kubernetes.io/name,spec.replicas")
+                ),
+                (Some("jobs"), Some("(new)")),
+            ]
+        );
+    }
+
+    #[test]
+    fn flattens_snapshot_with_current_array_mode() {
+        let item = json!({
+            "metadata": {"uid": "u1", "name": "api"},
+            "spec": {"containers": [{"image": "api:v1"}]}
+        });
+        let previous = parse_snapshot(
+            "before.json",
+            &json!([item.clone()]).to_string(),
+            ArrayMode::Whole,
+        )
+        .expect("snapshot must parse");
+        assert!(previous[0].fields.contains_key("spec.containers"));
+        assert!(!previous[0].fields.contains_key("spec.containers.0.image"));
+
+        let current = vec![DynamicObject {
+            fields: path::flatten_json_with_array_mode(&item, ArrayMode::Whole),
+        }];
+        assert!(diff_with_snapshot(current, &previous).is_empty());
+    }
+
+    #[test]
+    fn rejects_snapshot_without_object_list() {
+        let result = parse_snapshot("bad.json", "{\"kind\": \"Pod\"}", ArrayMode::Both);
+        assert!(matches!(result, Err(SnapshotError::Shape { .. })));
+
+        let result = parse_snapshot("bad.json", "not json", ArrayMode::Both);
+        assert!(matches!(result, Err(SnapshotError::Parse { .. })));
+    }
+}
@@ -44,6 +44,7 @@ fn e2e_table_where_select_for_core_resource() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.namespace",
@@ -72,6 +73,7 @@ fn e2e_table_order_by_for_core_resource() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.namespace",
@@ -120,6 +122,7 @@ fn e2e_json_select_parent_path_is_nested() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.name",
@@ -181,6 +184,7 @@ fn e2e_json_select_annotations_preserves_dotted_keys() {
     );
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.name",
@@ -223,6 +227,7 @@ fn e2e_json_order_by_for_crd_widget() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "widgets",
         "where",
         "metadata.name",
@@ -283,6 +288,7 @@ fn e2e_yaml_describe_is_nested() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.name",
@@ -330,6 +336,7 @@ fn e2e_select_for_crd_widget() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "widgets",
         "where",
         "spec.enabled",
@@ -371,6 +378,7 @@ fn e2e_json_aggregation_count_for_core_resource() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.namespace",
@@ -413,6 +421,7 @@ fn e2e_aggregation_sum_non_numeric_returns_error() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.namespace",
@@ -437,6 +446,7 @@ fn e2e_table_aggregation_shows_aggregate_column() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.namespace",
@@ -464,6 +474,7 @@ fn e2e_json_aggregation_count_path_for_core_resource() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.namespace",
@@ -501,6 +512,7 @@ fn e2e_json_aggregation_min_max_generation_for_core_resource() {
     }
 
     let output = run_kubiq(&[
+        "-A",
         "pods",
         "where",
         "metadata.namespace",